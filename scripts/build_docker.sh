@@ -18,19 +18,22 @@ done
 
 echo "Building Docker image: $IMAGE_NAME"
 
-# Build Rust binary locally
-echo "Compiling binary (release)..."
+# Build CPU version with the default features, so it needs no CUDA toolkit
+echo "Compiling CPU binary (release)..."
 cargo build --release
 
 # Copy binary to root context to respect .dockerignore
 cp target/release/ctranslate2-server ./ctranslate2-server
 
-# Build CPU version
 echo "Building Docker image (CPU)..."
 docker build --target runtime-cpu -t "${IMAGE_NAME}:latest" -t "${IMAGE_NAME}:cpu" .
 
 # Build GPU version
-# echo "Building Docker image (GPU)..."
+echo "Compiling GPU binary (release)..."
+cargo build --release --features cuda
+cp target/release/ctranslate2-server ./ctranslate2-server
+
+echo "Building Docker image (GPU)..."
 docker build --target runtime-gpu -t "${IMAGE_NAME}:gpu" .
 
 # Cleanup
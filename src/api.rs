@@ -1 +1,45 @@
+pub mod admin;
+pub mod detect;
+pub mod json;
 pub mod openai;
+pub mod resources;
+pub mod subtitles;
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+pub enum ApiError {
+    BadRequest(String),
+    /// A request field is out of the allowed range; answered with 422 naming the field
+    InvalidParameter {
+        param: String,
+        message: String,
+    },
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    InternalServerError(String),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, body) = match self {
+            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, json!({ "error": msg })),
+            ApiError::InvalidParameter { param, message } => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                json!({ "error": message, "param": param }),
+            ),
+            ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, json!({ "error": msg })),
+            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, json!({ "error": msg })),
+            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, json!({ "error": msg })),
+            ApiError::InternalServerError(msg) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": msg }))
+            }
+        };
+        (status, Json(body)).into_response()
+    }
+}
@@ -0,0 +1,388 @@
+use crate::{
+    api::ApiError,
+    cache::CacheStats,
+    glossary::{self, Glossary, GlossaryError, GlossaryInfo, GlossaryOrigin},
+    lang,
+    memory::{MemoryEntry, MemoryError, MemoryMatch},
+    model::ReloadSummary,
+    reload,
+    state::AppState,
+    tmx::{self, ImportError, ImportSummary},
+};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{
+        HeaderMap,
+        header::{AUTHORIZATION, CONTENT_TYPE},
+    },
+};
+use serde::Deserialize;
+
+/// Rejects the request unless it carries the configured admin bearer token. Without a
+/// configured token the admin endpoints are off altogether.
+pub(crate) fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(), ApiError> {
+    let config = state.model_manager.config();
+    let Some(token) = config.server.admin_token.as_deref() else {
+        return Err(ApiError::Forbidden(
+            "Admin endpoints are disabled; set server.admin_token to enable them".to_string(),
+        ));
+    };
+
+    let provided = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if provided.is_some_and(|provided| constant_time_eq(provided.as_bytes(), token.as_bytes())) {
+        Ok(())
+    } else {
+        Err(ApiError::Unauthorized(
+            "Missing or invalid admin token".to_string(),
+        ))
+    }
+}
+
+/// Compares in a time that does not depend on where the inputs differ, so the token
+/// cannot be guessed byte by byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+pub async fn reload_config(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ReloadSummary>, ApiError> {
+    authorize(&state, &headers)?;
+
+    let summary = reload::reload(&state.model_manager)
+        .await
+        .map_err(|e| ApiError::InternalServerError(format!("Reload failed: {}", e)))?;
+    Ok(Json(summary))
+}
+
+/// Hit/miss counters and size of the translation cache.
+pub async fn cache_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<CacheStats>, ApiError> {
+    authorize(&state, &headers)?;
+    Ok(Json(state.model_manager.cache().stats()))
+}
+
+/// Drops every cached translation.
+pub async fn clear_cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<CacheStats>, ApiError> {
+    authorize(&state, &headers)?;
+    let cache = state.model_manager.cache();
+    cache.clear();
+    Ok(Json(cache.stats()))
+}
+
+impl From<MemoryError> for ApiError {
+    fn from(e: MemoryError) -> Self {
+        match e {
+            MemoryError::Disabled | MemoryError::InvalidLanguage { .. } => {
+                ApiError::BadRequest(e.to_string())
+            }
+            MemoryError::EntryNotFound { .. } => ApiError::NotFound(e.to_string()),
+            _ => ApiError::InternalServerError(format!("Translation memory error: {}", e)),
+        }
+    }
+}
+
+/// The type of the model named `model`, or of the default model if there is one.
+fn native_model_type(state: &AppState, model: Option<&str>) -> Result<Option<String>, ApiError> {
+    match model {
+        Some(model) => tmx_model_type(state, Some(model)).map(Some),
+        None => Ok(state
+            .model_manager
+            .config()
+            .model_type("default")
+            .map(str::to_string)),
+    }
+}
+
+/// `lang` in the codes of a `model_type` model, as translation requests use them to look
+/// up memory entries and glossaries; kept as given without a model type.
+fn native_lang(param: &str, lang: &str, model_type: Option<&str>) -> Result<String, ApiError> {
+    match model_type {
+        Some(model_type) => {
+            lang::normalize(lang, model_type).map_err(|e| ApiError::InvalidParameter {
+                param: param.to_string(),
+                message: e.to_string(),
+            })
+        }
+        None => Ok(lang.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewMemoryEntry {
+    pub source_lang: String,
+    pub target_lang: String,
+    pub source: String,
+    pub target: String,
+    /// Model whose language codes the entry is stored in, the default model otherwise
+    pub model: Option<String>,
+}
+
+/// Stores an approved translation in the translation memory.
+pub async fn add_memory_entry(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(entry): Json<NewMemoryEntry>,
+) -> Result<Json<MemoryEntry>, ApiError> {
+    authorize(&state, &headers)?;
+    let model_type = native_model_type(&state, entry.model.as_deref())?;
+    let source_lang = native_lang("source_lang", &entry.source_lang, model_type.as_deref())?;
+    let target_lang = native_lang("target_lang", &entry.target_lang, model_type.as_deref())?;
+    let memory = state.model_manager.memory();
+    let entry = tokio::task::spawn_blocking(move || {
+        memory.add(&source_lang, &target_lang, &entry.source, &entry.target)
+    })
+    .await
+    .map_err(|e| ApiError::InternalServerError(format!("Join error: {}", e)))??;
+    Ok(Json(entry))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MemorySearch {
+    pub source_lang: String,
+    pub target_lang: String,
+    pub q: String,
+    /// Minimum similarity, defaulting to the configured fuzzy threshold
+    pub threshold: Option<f32>,
+    #[serde(default = "default_search_limit")]
+    pub limit: usize,
+    /// Model whose language codes are searched, the default model otherwise
+    pub model: Option<String>,
+}
+
+fn default_search_limit() -> usize {
+    20
+}
+
+/// Finds stored translations similar to `q`, best first.
+pub async fn search_memory(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(search): Query<MemorySearch>,
+) -> Result<Json<Vec<MemoryMatch>>, ApiError> {
+    authorize(&state, &headers)?;
+    let memory = state.model_manager.memory();
+    if !memory.is_enabled() {
+        return Err(MemoryError::Disabled.into());
+    }
+    let model_type = native_model_type(&state, search.model.as_deref())?;
+    let source_lang = native_lang("source_lang", &search.source_lang, model_type.as_deref())?;
+    let target_lang = native_lang("target_lang", &search.target_lang, model_type.as_deref())?;
+    let threshold = search.threshold.unwrap_or(
+        state
+            .model_manager
+            .config()
+            .translation_memory
+            .fuzzy_threshold,
+    );
+    Ok(Json(memory.search(
+        &source_lang,
+        &target_lang,
+        &search.q,
+        threshold,
+        search.limit,
+    )))
+}
+
+/// Removes an entry from the translation memory.
+pub async fn delete_memory_entry(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u64>,
+) -> Result<Json<MemoryEntry>, ApiError> {
+    authorize(&state, &headers)?;
+    let memory = state.model_manager.memory();
+    let entry = tokio::task::spawn_blocking(move || memory.delete(id))
+        .await
+        .map_err(|e| ApiError::InternalServerError(format!("Join error: {}", e)))??;
+    Ok(Json(entry))
+}
+
+impl From<ImportError> for ApiError {
+    fn from(e: ImportError) -> Self {
+        match e {
+            ImportError::Tmx { source } => ApiError::BadRequest(source.to_string()),
+            ImportError::Memory { source } => source.into(),
+        }
+    }
+}
+
+/// The model type language codes are mapped for, from the `model` parameter or the
+/// default model.
+fn tmx_model_type(state: &AppState, model: Option<&str>) -> Result<String, ApiError> {
+    let model = model.unwrap_or("default");
+    state
+        .model_manager
+        .config()
+        .model_type(model)
+        .map(str::to_string)
+        .ok_or_else(|| ApiError::BadRequest(format!("Model error: Model '{}' not found", model)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TmxImport {
+    pub model: Option<String>,
+}
+
+/// Imports a TMX 1.4 document sent as the request body into the translation memory.
+pub async fn import_tmx(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<TmxImport>,
+    body: String,
+) -> Result<Json<ImportSummary>, ApiError> {
+    authorize(&state, &headers)?;
+    let model_type = tmx_model_type(&state, query.model.as_deref())?;
+    let memory = state.model_manager.memory();
+    let summary = tokio::task::spawn_blocking(move || tmx::import_str(&memory, &body, &model_type))
+        .await
+        .map_err(|e| ApiError::InternalServerError(format!("Join error: {}", e)))??;
+    Ok(Json(summary))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TmxExport {
+    pub source_lang: String,
+    pub target_lang: String,
+    pub model: Option<String>,
+}
+
+/// Exports one language pair of the translation memory as a TMX 1.4 document.
+pub async fn export_tmx(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<TmxExport>,
+) -> Result<([(axum::http::HeaderName, &'static str); 1], String), ApiError> {
+    authorize(&state, &headers)?;
+    let memory = state.model_manager.memory();
+    if !memory.is_enabled() {
+        return Err(MemoryError::Disabled.into());
+    }
+    let model_type = tmx_model_type(&state, query.model.as_deref())?;
+    let source_lang = native_lang("source_lang", &query.source_lang, Some(&model_type))?;
+    let target_lang = native_lang("target_lang", &query.target_lang, Some(&model_type))?;
+    let entries = memory.entries(&source_lang, &target_lang);
+    Ok((
+        [(CONTENT_TYPE, "application/x-tmx+xml")],
+        tmx::export(&source_lang, &target_lang, &entries),
+    ))
+}
+
+impl From<GlossaryError> for ApiError {
+    fn from(e: GlossaryError) -> Self {
+        match e {
+            GlossaryError::NotFound { .. } => ApiError::NotFound(e.to_string()),
+            GlossaryError::Io { .. } => {
+                ApiError::InternalServerError(format!("Glossary error: {}", e))
+            }
+            _ => ApiError::BadRequest(e.to_string()),
+        }
+    }
+}
+
+/// Every glossary with its language pair and number of terms.
+pub async fn list_glossaries(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<GlossaryInfo>>, ApiError> {
+    authorize(&state, &headers)?;
+    Ok(Json(state.model_manager.glossaries().list()))
+}
+
+/// One glossary with its terms.
+pub async fn get_glossary(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Glossary>, ApiError> {
+    authorize(&state, &headers)?;
+    let glossary = state
+        .model_manager
+        .glossaries()
+        .get(&id)
+        .ok_or(GlossaryError::NotFound { id })?;
+    Ok(Json(glossary.as_ref().clone()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GlossaryUpload {
+    pub source_lang: Option<String>,
+    pub target_lang: String,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// `csv` or `tsv`; taken from the content type or the first row if not given
+    pub format: Option<String>,
+    /// Model whose language codes the glossary is declared in, the default model otherwise
+    pub model: Option<String>,
+}
+
+/// Creates or replaces a glossary from a CSV or TSV body.
+pub async fn put_glossary(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(upload): Query<GlossaryUpload>,
+    body: String,
+) -> Result<Json<GlossaryInfo>, ApiError> {
+    authorize(&state, &headers)?;
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let delimiter = match upload.format.as_deref() {
+        Some("csv") => Some(','),
+        Some("tsv") => Some('\t'),
+        Some(other) => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown glossary format '{}', expected csv or tsv",
+                other
+            )));
+        }
+        None if content_type.starts_with("text/tab-separated-values") => Some('\t'),
+        None if content_type.starts_with("text/csv") => Some(','),
+        None => None,
+    };
+
+    let model_type = native_model_type(&state, upload.model.as_deref())?;
+    let source_lang = upload
+        .source_lang
+        .map(|lang| native_lang("source_lang", &lang, model_type.as_deref()))
+        .transpose()?;
+    let target_lang = native_lang("target_lang", &upload.target_lang, model_type.as_deref())?;
+
+    let terms = glossary::parse_terms(&body, delimiter)?;
+    let glossary = Glossary::new(
+        &id,
+        source_lang,
+        target_lang,
+        terms,
+        upload.case_sensitive,
+        GlossaryOrigin::Api,
+    )?;
+    let glossary = state.model_manager.glossaries().insert(glossary);
+    // Cached translations may have used the previous terms
+    state.model_manager.cache().clear();
+    Ok(Json(glossary.info()))
+}
+
+/// Removes a glossary uploaded through the API.
+pub async fn delete_glossary(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<GlossaryInfo>, ApiError> {
+    authorize(&state, &headers)?;
+    let glossary = state.model_manager.glossaries().remove(&id)?;
+    state.model_manager.cache().clear();
+    Ok(Json(glossary.info()))
+}
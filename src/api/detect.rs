@@ -0,0 +1,91 @@
+use crate::{
+    api::{ApiError, openai::model_error},
+    detect::{self, Detection},
+    model::{GenerationParams, ModelError},
+    state::AppState,
+};
+use axum::{Json, extract::State};
+use serde::{Deserialize, Serialize};
+
+/// One text or several.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum DetectInput {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DetectRequest {
+    pub input: DetectInput,
+    /// Model alias or name whose language codes are reported; FLORES-200 codes if not
+    /// given
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DetectResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// One detection per input, in order
+    pub detections: Vec<Detection>,
+}
+
+/// Detects the language of each input text.
+pub async fn detect_language(
+    State(state): State<AppState>,
+    Json(request): Json<DetectRequest>,
+) -> Result<Json<DetectResponse>, ApiError> {
+    let config = state.model_manager.config();
+    let model_type = match &request.model {
+        Some(model) => Some(
+            config
+                .model_type(model)
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    ApiError::BadRequest(format!("Model error: Model '{}' not found", model))
+                })?,
+        ),
+        None => None,
+    };
+    let inputs = match request.input {
+        DetectInput::One(text) => vec![text],
+        DetectInput::Many(texts) => texts,
+    };
+
+    // Detection runs no model, but its cost still grows with the inputs
+    let limits = match &request.model {
+        Some(model) => config.limits(&state.model_manager.resolve_model_name(model)),
+        None => config.limits.clone(),
+    };
+    limits
+        .check(&inputs, &GenerationParams::default())
+        .map_err(|e| match e {
+            ModelError::LimitExceeded { message, .. } => ApiError::InvalidParameter {
+                param: "input".to_string(),
+                message,
+            },
+            e => model_error(e),
+        })?;
+
+    let detections = tokio::task::spawn_blocking(move || {
+        inputs
+            .iter()
+            .map(|text| {
+                let detection = detect::detect(text);
+                match &model_type {
+                    Some(model_type) => detection.for_model(model_type),
+                    None => detection,
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| ApiError::InternalServerError(format!("Join error: {}", e)))?;
+    Ok(Json(DetectResponse {
+        model: request
+            .model
+            .map(|model| state.model_manager.resolve_model_name(&model)),
+        detections,
+    }))
+}
@@ -0,0 +1,99 @@
+use crate::{
+    api::{ApiError, openai::model_error},
+    format::{
+        self, FormatError,
+        json::{JsonDocument, Selector},
+    },
+    glossary::Term,
+    model::GenerationParams,
+    protect::LostPlaceholders,
+    state::AppState,
+};
+use axum::{Json, extract::State};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct JsonTranslationRequest {
+    /// Model alias or name, `default` if not given
+    pub model: Option<String>,
+    /// Any JSON value whose strings are translated
+    pub input: Value,
+    /// Selectors such as `$.products[*].name` picking the strings to translate; all
+    /// strings if empty
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Selectors of strings to leave untranslated
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    pub target_lang: Option<String>,
+    pub source_lang: Option<String>,
+    pub preset: Option<String>,
+    pub glossary_id: Option<String>,
+    pub protect: Option<Vec<String>>,
+    pub on_lost_placeholder: Option<LostPlaceholders>,
+    pub use_cache: Option<bool>,
+    pub use_memory: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JsonTranslationResponse {
+    pub model: String,
+    /// The input with the selected strings translated
+    pub output: Value,
+    /// Number of strings translated
+    pub translated: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub glossary_terms: Vec<Term>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lost_placeholders: Vec<String>,
+}
+
+/// Parses the selectors of the request field `param`.
+fn selectors(selectors: &[String], param: &str) -> Result<Vec<Selector>, ApiError> {
+    selectors
+        .iter()
+        .map(|selector| selector.parse::<Selector>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| ApiError::InvalidParameter {
+            param: param.to_string(),
+            message: e.to_string(),
+        })
+}
+
+/// Translates the strings of a JSON value that the selectors pick, in one batch, and
+/// returns the value with the same structure.
+pub async fn translate_json(
+    State(state): State<AppState>,
+    Json(request): Json<JsonTranslationRequest>,
+) -> Result<Json<JsonTranslationResponse>, ApiError> {
+    let include = selectors(&request.include, "include")?;
+    let exclude = selectors(&request.exclude, "exclude")?;
+    let json = JsonDocument::new(request.input, &include, &exclude);
+
+    let model = request.model.as_deref().unwrap_or("default");
+    let params = GenerationParams {
+        preset: request.preset,
+        target_lang: request.target_lang,
+        source_lang: request.source_lang,
+        glossary_id: request.glossary_id,
+        protect: request.protect,
+        on_lost_placeholder: request.on_lost_placeholder,
+        use_cache: request.use_cache,
+        use_memory: request.use_memory,
+        ..Default::default()
+    };
+    let (output, document) = format::translate_json(&state.model_manager, model, &json, params)
+        .await
+        .map_err(|e| match e {
+            FormatError::Model { source } => model_error(source),
+            e => ApiError::InternalServerError(e.to_string()),
+        })?;
+    Ok(Json(JsonTranslationResponse {
+        model: state.model_manager.resolve_model_name(model),
+        output,
+        translated: json.prompts().len(),
+        glossary_terms: document.glossary_terms,
+        lost_placeholders: document.lost_placeholders,
+    }))
+}
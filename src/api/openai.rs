@@ -1,15 +1,15 @@
 use crate::{
+    api::ApiError,
+    format::{self, Format, FormatError},
+    glossary::Term,
+    memory::MemoryMatch,
     model::{GenerationParams, ModelError},
+    protect::LostPlaceholders,
     state::AppState,
 };
-use axum::{
-    Json,
-    extract::State,
-    http::StatusCode,
-    response::{IntoResponse, Response},
-};
+use axum::{Json, extract::State, response::IntoResponse};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ChatCompletionMessage {
@@ -21,18 +21,64 @@ pub struct ChatCompletionMessage {
 pub struct ChatCompletionRequest {
     pub model: String,
     pub messages: Vec<ChatCompletionMessage>,
+    /// Sampling temperature, ignored unless sampling (`top_k` other than 1)
     #[serde(default)]
     pub temperature: Option<f32>,
     #[serde(default)]
     pub max_tokens: Option<u32>,
+    /// Extension: Name of a configured generation preset (e.g. "fast")
+    pub preset: Option<String>,
     /// Extension: Target language code (e.g. "fra_Latn")
     pub target_lang: Option<String>,
+    /// Extension: Several target languages, each answered with its own choice
+    pub target_langs: Option<Vec<String>>,
+    /// Extension: Source language code (e.g. "eng_Latn")
+    pub source_lang: Option<String>,
     /// Extension: Beam size for beam search (default: 5)
     pub beam_size: Option<usize>,
     /// Extension: Penalty for repeated tokens (default: 1.2)
     pub repetition_penalty: Option<f32>,
     /// Extension: Prevent repetitions of ngrams with this size (default: 0)
     pub no_repeat_ngram_size: Option<usize>,
+    /// Extension: Exponential penalty applied to the length during beam search (default: 1.0)
+    pub length_penalty: Option<f32>,
+    /// Extension: Coverage penalty weight applied during beam search (default: 0)
+    pub coverage_penalty: Option<f32>,
+    /// Extension: Beam search patience factor (default: 1.0)
+    pub patience: Option<f32>,
+    /// Extension: Truncate inputs after this many tokens, 0 disables truncation (default: 1024)
+    pub max_input_length: Option<usize>,
+    /// Extension: Minimum length of the generated output (default: 1)
+    pub min_decoding_length: Option<usize>,
+    /// Extension: Never generate the unknown token (default: false)
+    pub disable_unk: Option<bool>,
+    /// Extension: Replace unknown target tokens by the source token with the highest attention (default: false)
+    pub replace_unknowns: Option<bool>,
+    /// Extension: Phrases that must never appear in the output
+    pub suppress_sequences: Option<Vec<String>>,
+    /// Nucleus sampling probability, used when sampling (`top_k` other than 1)
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Extension: Sample from this many most likely tokens, 0 for all (default: 1, no sampling)
+    pub top_k: Option<usize>,
+    /// Random seed for sampling, best effort
+    #[serde(default)]
+    pub seed: Option<u32>,
+    /// Extension: Set to false to bypass the translation cache
+    pub use_cache: Option<bool>,
+    /// Extension: Set to false to skip the translation memory
+    pub use_memory: Option<bool>,
+    /// Extension: Id of a glossary whose terms are enforced
+    pub glossary_id: Option<String>,
+    /// Extension: Protection patterns, e.g. ["icu", "url"], whose matches are not translated
+    pub protect: Option<Vec<String>>,
+    /// Extension: "fail" or "warn" when the model drops a protected placeholder
+    pub on_lost_placeholder: Option<LostPlaceholders>,
+    /// Extension: Split the input into sentences translated as one batch, for long texts
+    pub segment: Option<bool>,
+    /// Extension: "text" (default), "html", "xml" or "markdown"; markup is kept and only
+    /// the prose translated
+    pub format: Option<Format>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -40,6 +86,18 @@ pub struct ChatCompletionChoice {
     pub index: u32,
     pub message: ChatCompletionMessage,
     pub finish_reason: Option<String>,
+    /// Extension: Target language of this choice, with `target_langs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_lang: Option<String>,
+    /// Extension: Fuzzy translation memory matches for the prompt, best first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<MemoryMatch>,
+    /// Extension: Glossary terms enforced in the translation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub glossary_terms: Vec<Term>,
+    /// Extension: Protected spans the model dropped from the translation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lost_placeholders: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -57,21 +115,9 @@ pub struct ChatCompletionResponse {
     pub model: String,
     pub choices: Vec<ChatCompletionChoice>,
     pub usage: Option<Usage>,
-}
-
-pub enum ApiError {
-    BadRequest(String),
-    InternalServerError(String),
-}
-
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let (status, message) = match self {
-            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            ApiError::InternalServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-        };
-        (status, Json(json!({ "error": message }))).into_response()
-    }
+    /// Extension: Translated texts keyed by target language, with `target_langs`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, String>,
 }
 
 pub async fn chat_completions(
@@ -91,47 +137,154 @@ pub async fn chat_completions(
         .last()
         .map(|m| m.content.clone())
         .ok_or_else(|| ApiError::BadRequest("No messages provided".to_string()))?;
+    if request.target_langs.as_ref().is_some_and(Vec::is_empty) {
+        return Err(ApiError::InvalidParameter {
+            param: "target_langs".to_string(),
+            message: "must list at least one language".to_string(),
+        });
+    }
 
-    // Model resolution is now handled by ModelManager (including aliases and defaults)
-    // We pass the requested model name directly.
+    // Model resolution is handled by ModelManager (including aliases and defaults).
+    // We pass the requested model name directly and report the resolved one back.
+    let resolved_model = state.model_manager.resolve_model_name(&request.model);
     let params = GenerationParams {
+        preset: request.preset.clone(),
         target_lang: request.target_lang.clone(),
+        target_langs: request.target_langs.clone(),
+        source_lang: request.source_lang.clone(),
         beam_size: request.beam_size,
         repetition_penalty: request.repetition_penalty,
         no_repeat_ngram_size: request.no_repeat_ngram_size,
+        length_penalty: request.length_penalty,
+        coverage_penalty: request.coverage_penalty,
+        patience: request.patience,
+        max_input_length: request.max_input_length,
+        min_decoding_length: request.min_decoding_length,
+        max_decoding_length: request.max_tokens.map(|n| n as usize),
+        disable_unk: request.disable_unk,
+        replace_unknowns: request.replace_unknowns,
+        suppress_sequences: request.suppress_sequences.clone(),
+        sampling_topk: request.top_k,
+        sampling_topp: request.top_p,
+        sampling_temperature: request.temperature,
+        seed: request.seed,
+        use_cache: request.use_cache,
+        use_memory: request.use_memory,
+        glossary_id: request.glossary_id.clone(),
+        protect: request.protect.clone(),
+        on_lost_placeholder: request.on_lost_placeholder,
+        segment: request.segment,
     };
 
-    let results = state
-        .model_manager
-        .generate(&request.model, vec![prompt], params)
+    let format = request.format.unwrap_or_default();
+    if format != Format::Text {
+        let document = format::translate(
+            &state.model_manager,
+            &request.model,
+            format,
+            &prompt,
+            params,
+        )
         .await
         .map_err(|e| match e {
-            ModelError::NotFound { .. } | ModelError::ConfigNotFound { .. } => {
-                ApiError::BadRequest(format!("Model error: {}", e))
-            }
-            _ => ApiError::InternalServerError(format!("Inference failed: {}", e)),
+            FormatError::Model { source } => model_error(source),
+            e => ApiError::InvalidParameter {
+                param: "messages".to_string(),
+                message: e.to_string(),
+            },
         })?;
+        return Ok(Json(completion(
+            resolved_model,
+            vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatCompletionMessage {
+                    role: "assistant".to_string(),
+                    content: document.text,
+                },
+                finish_reason: Some("stop".to_string()),
+                target_lang: None,
+                alternatives: Vec::new(),
+                glossary_terms: document.glossary_terms,
+                lost_placeholders: document.lost_placeholders,
+            }],
+        )));
+    }
 
-    let response_text = results.first().cloned().unwrap_or_default();
+    // All target languages are translated in one batch, each answered with its own choice
+    let targets = state
+        .model_manager
+        .generate_targets(&request.model, vec![prompt], params)
+        .await
+        .map_err(model_error)?;
+    let several = request.target_langs.is_some() || targets.len() > 1;
 
-    let response = ChatCompletionResponse {
+    let mut translations = BTreeMap::new();
+    let mut choices = Vec::new();
+    for (index, (target_lang, results)) in targets.into_iter().enumerate() {
+        let Some(translation) = results.into_iter().next() else {
+            continue;
+        };
+        if several {
+            translations.insert(target_lang.clone(), translation.text.clone());
+        }
+        choices.push(ChatCompletionChoice {
+            index: index as u32,
+            message: ChatCompletionMessage {
+                role: "assistant".to_string(),
+                content: translation.text,
+            },
+            finish_reason: Some("stop".to_string()),
+            target_lang: several.then_some(target_lang),
+            alternatives: translation.alternatives,
+            glossary_terms: translation.glossary_terms,
+            lost_placeholders: translation.lost_placeholders,
+        });
+    }
+
+    let mut response = completion(resolved_model, choices);
+    response.translations = translations;
+    Ok(Json(response))
+}
+
+/// Wraps the choices into a completion response.
+fn completion(model: String, choices: Vec<ChatCompletionChoice>) -> ChatCompletionResponse {
+    ChatCompletionResponse {
         id: "chatcmpl-123".to_string(), // TODO: UUID
         object: "chat.completion".to_string(),
         created: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs(),
-        model: request.model.clone(),
-        choices: vec![ChatCompletionChoice {
-            index: 0,
-            message: ChatCompletionMessage {
-                role: "assistant".to_string(),
-                content: response_text,
-            },
-            finish_reason: Some("stop".to_string()),
-        }],
+        model,
+        choices,
         usage: None,
-    };
+        translations: BTreeMap::new(),
+    }
+}
 
-    Ok(Json(response))
+pub(crate) fn model_error(e: ModelError) -> ApiError {
+    match e {
+        ModelError::LimitExceeded { param, message, .. }
+        | ModelError::InvalidSetting { param, message, .. } => {
+            ApiError::InvalidParameter { param, message }
+        }
+        ModelError::Language { param, source, .. } => ApiError::InvalidParameter {
+            param,
+            message: source.to_string(),
+        },
+        ModelError::InvalidProtection { .. } => ApiError::InvalidParameter {
+            param: "protect".to_string(),
+            message: e.to_string(),
+        },
+        ModelError::PlaceholdersLost { .. } => ApiError::InvalidParameter {
+            param: "messages".to_string(),
+            message: e.to_string(),
+        },
+        ModelError::NotFound { .. }
+        | ModelError::ConfigNotFound { .. }
+        | ModelError::AliasCycle { .. }
+        | ModelError::PresetNotFound { .. }
+        | ModelError::Glossary { .. } => ApiError::BadRequest(format!("Model error: {}", e)),
+        _ => ApiError::InternalServerError(format!("Inference failed: {}", e)),
+    }
 }
@@ -0,0 +1,76 @@
+use crate::{
+    api::{ApiError, openai::model_error},
+    format::{
+        self, FormatError,
+        resource::{ResourceDocument, ResourceFormat},
+    },
+    model::GenerationParams,
+    state::AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, HeaderName, header::CONTENT_TYPE},
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct ResourceTranslation {
+    /// `json`, `yaml`, `po` or `xliff`; taken from the content type if not given
+    pub format: Option<ResourceFormat>,
+    pub model: Option<String>,
+    pub target_lang: String,
+    pub source_lang: Option<String>,
+    pub preset: Option<String>,
+    pub glossary_id: Option<String>,
+    pub use_cache: Option<bool>,
+    pub use_memory: Option<bool>,
+    /// Translate PO and XLIFF entries that have a translation already
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Translates the JSON, YAML, PO or XLIFF localization resource sent as the request body,
+/// answering with the target-language file.
+pub async fn translate_resource(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ResourceTranslation>,
+    body: String,
+) -> Result<([(HeaderName, &'static str); 1], String), ApiError> {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let format = query
+        .format
+        .or_else(|| ResourceFormat::from_content_type(content_type))
+        .ok_or_else(|| {
+            ApiError::BadRequest(
+                "Unknown resource format, expected format=json, yaml, po or xliff".to_string(),
+            )
+        })?;
+    let resource = ResourceDocument::parse(&body, format, query.force).map_err(|e| {
+        ApiError::InvalidParameter {
+            param: "body".to_string(),
+            message: e.to_string(),
+        }
+    })?;
+
+    let model = query.model.as_deref().unwrap_or("default");
+    let params = GenerationParams {
+        preset: query.preset,
+        target_lang: Some(query.target_lang),
+        source_lang: query.source_lang,
+        use_cache: query.use_cache,
+        use_memory: query.use_memory,
+        glossary_id: query.glossary_id,
+        ..Default::default()
+    };
+    let document = format::translate_resource(&state.model_manager, model, &resource, params)
+        .await
+        .map_err(|e| match e {
+            FormatError::Model { source } => model_error(source),
+            e => ApiError::InternalServerError(e.to_string()),
+        })?;
+    Ok(([(CONTENT_TYPE, format.content_type())], document.text))
+}
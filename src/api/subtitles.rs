@@ -0,0 +1,70 @@
+use crate::{
+    api::{ApiError, openai::model_error},
+    format::{self, FormatError, subtitle::SubtitleDocument},
+    model::GenerationParams,
+    state::AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderName, header::CONTENT_TYPE},
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct SubtitleTranslation {
+    pub model: Option<String>,
+    pub target_lang: String,
+    pub source_lang: Option<String>,
+    pub preset: Option<String>,
+    pub glossary_id: Option<String>,
+    pub use_cache: Option<bool>,
+    pub use_memory: Option<bool>,
+    /// Wrap cue texts to this many characters per line instead of keeping their line count
+    pub max_width: Option<usize>,
+}
+
+/// Translates the SRT or WebVTT subtitles sent as the request body, answering in the
+/// same format.
+pub async fn translate_subtitles(
+    State(state): State<AppState>,
+    Query(query): Query<SubtitleTranslation>,
+    body: String,
+) -> Result<([(HeaderName, &'static str); 1], String), ApiError> {
+    if query.max_width == Some(0) {
+        return Err(ApiError::InvalidParameter {
+            param: "max_width".to_string(),
+            message: "max_width must be at least 1".to_string(),
+        });
+    }
+    let subtitles = SubtitleDocument::parse(&body).map_err(|e| ApiError::InvalidParameter {
+        param: "body".to_string(),
+        message: format!("Invalid subtitles: {}", e),
+    })?;
+
+    let model = query.model.as_deref().unwrap_or("default");
+    let params = GenerationParams {
+        preset: query.preset,
+        target_lang: Some(query.target_lang),
+        source_lang: query.source_lang,
+        use_cache: query.use_cache,
+        use_memory: query.use_memory,
+        glossary_id: query.glossary_id,
+        ..Default::default()
+    };
+    let document = format::translate_subtitles(
+        &state.model_manager,
+        model,
+        &subtitles,
+        params,
+        query.max_width,
+    )
+    .await
+    .map_err(|e| match e {
+        FormatError::Model { source } => model_error(source),
+        e => ApiError::InternalServerError(e.to_string()),
+    })?;
+    Ok((
+        [(CONTENT_TYPE, subtitles.format().content_type())],
+        document.text,
+    ))
+}
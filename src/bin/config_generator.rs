@@ -7,7 +7,9 @@ fn main() -> std::io::Result<()> {
     config.push_str("default_model = \"nllb\"\n");
     config.push_str("target_lang = \"eng_Latn\"\n");
     config.push_str("device = \"cpu\"\n");
-    config.push_str("device_indices = [0]\n\n");
+    config.push_str("device_indices = [0]\n");
+    config.push_str("compute_type = \"default\"\n");
+    config.push_str("num_threads_per_replica = 0\n\n");
     config.push_str("beam_size = 5\n");
     config.push_str("repetition_penalty = 1.2\n");
     config.push_str("no_repeat_ngram_size = 0\n\n");
@@ -47,7 +49,7 @@ fn main() -> std::io::Result<()> {
                 models_config.push_str(&format!("path = \"{}\"\n", path.display()));
                 models_config.push_str(&format!("model_type = \"{}\"\n", model_type));
                 // models_config.push_str("target_lang = \"zho_Hans\"\n\n");
-                models_config.push_str("\n");
+                models_config.push('\n');
             }
         }
     }
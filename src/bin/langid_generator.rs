@@ -0,0 +1,51 @@
+//! Builds `data/langid.txt`, the trigram profiles used by language detection, from the
+//! sample texts in `scripts/langid`. Each `<flores code>.txt` file holds text in one
+//! language; a language needs enough of it to be told apart from its close relatives.
+//!
+//! Usage: `cargo run --bin langid_generator [samples dir] [output file]`
+
+use ctranslate2_server::detect;
+use std::collections::HashMap;
+use std::fs;
+
+/// Trigrams kept per language.
+const PROFILE_SIZE: usize = 1000;
+
+fn main() -> std::io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let samples = args.next().unwrap_or_else(|| "scripts/langid".to_string());
+    let output = args.next().unwrap_or_else(|| "data/langid.txt".to_string());
+
+    let mut files: Vec<_> = fs::read_dir(&samples)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    files.sort();
+
+    let mut model = String::from("# Auto-generated by langid_generator from scripts/langid\n");
+    model.push_str("# <flores code>\\t<trigram>:<count> ...\n");
+    for path in &files {
+        let Some(language) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let text = fs::read_to_string(path)?;
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for trigram in detect::trigrams(&text) {
+            *counts.entry(trigram).or_default() += 1;
+        }
+        let mut counts: Vec<(String, u32)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(PROFILE_SIZE);
+
+        let profile: Vec<String> = counts
+            .iter()
+            .map(|(trigram, count)| format!("{}:{}", trigram, count))
+            .collect();
+        model.push_str(&format!("{}\t{}\n", language, profile.join(" ")));
+        println!("{}: {} trigrams", language, counts.len());
+    }
+
+    fs::write(&output, model)?;
+    println!("Wrote {} profiles to {}", files.len(), output);
+    Ok(())
+}
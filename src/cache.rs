@@ -0,0 +1,213 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::config::CacheConfig;
+use crate::model::GenerationParams;
+
+/// Identifies one translation: the resolved model, the input with its whitespace
+/// normalized, and every effective generation setting.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// `params` must be fully resolved, so that two requests reaching the same settings
+    /// through different aliases or presets share their entries.
+    pub fn new(model: &str, input: &str, params: &GenerationParams) -> Self {
+        let params = GenerationParams {
+            preset: None,
+            use_cache: None,
+            use_memory: None,
+            on_lost_placeholder: None,
+            segment: None,
+            ..params.clone()
+        };
+        CacheKey(format!(
+            "{}\u{0}{:?}\u{0}{}",
+            model,
+            params,
+            normalize_input(input)
+        ))
+    }
+}
+
+/// Collapses runs of whitespace, which do not change the translation.
+pub fn normalize_input(input: &str) -> String {
+    input.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Counters reported by `GET /admin/cache`.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct CacheStats {
+    pub enabled: bool,
+    pub entries: usize,
+    pub bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+struct Entry {
+    value: String,
+    inserted: Instant,
+    /// Position in the recency order; higher is more recent
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    config: CacheConfig,
+    entries: HashMap<CacheKey, Entry>,
+    /// Keys by last use, least recent first
+    order: BTreeMap<u64, CacheKey>,
+    next_tick: u64,
+    bytes: usize,
+}
+
+impl Inner {
+    fn remove(&mut self, key: &CacheKey) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.tick);
+        self.bytes -= entry_size(key, &entry.value);
+        Some(entry)
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.next_tick += 1;
+        self.next_tick
+    }
+
+    fn over_budget(&self) -> bool {
+        self.entries.len() > self.config.max_entries
+            || (self.config.max_bytes > 0 && self.bytes > self.config.max_bytes)
+    }
+}
+
+fn entry_size(key: &CacheKey, value: &str) -> usize {
+    key.0.len() + value.len()
+}
+
+/// In-memory LRU cache of translations in front of [`crate::model::ModelManager::generate`].
+#[derive(Default)]
+pub struct TranslationCache {
+    inner: Mutex<Inner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl TranslationCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        let cache = Self::default();
+        cache.reconfigure(config);
+        cache
+    }
+
+    /// Applies new settings, dropping every entry and resetting the counters.
+    pub fn reconfigure(&self, config: &CacheConfig) {
+        *self.lock() = Inner {
+            config: config.clone(),
+            ..Default::default()
+        };
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.lock().config.max_entries > 0
+    }
+
+    /// The cached translation for `key`, marking it as recently used.
+    pub fn get(&self, key: &CacheKey) -> Option<String> {
+        let mut inner = self.lock();
+        if inner.config.max_entries == 0 {
+            return None;
+        }
+
+        let ttl = Duration::from_secs(inner.config.ttl_secs);
+        let expired = inner
+            .entries
+            .get(key)
+            .map(|entry| inner.config.ttl_secs > 0 && entry.inserted.elapsed() >= ttl);
+        let value = match expired {
+            Some(false) => {
+                let tick = inner.tick();
+                let entry = inner.entries.get_mut(key)?;
+                let previous = std::mem::replace(&mut entry.tick, tick);
+                let value = entry.value.clone();
+                inner.order.remove(&previous);
+                inner.order.insert(tick, key.clone());
+                Some(value)
+            }
+            Some(true) => {
+                inner.remove(key);
+                None
+            }
+            None => None,
+        };
+
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    /// Stores a translation, evicting the least recently used ones beyond the limits.
+    pub fn insert(&self, key: CacheKey, value: String) {
+        let mut inner = self.lock();
+        let size = entry_size(&key, &value);
+        if inner.config.max_entries == 0
+            || (inner.config.max_bytes > 0 && size > inner.config.max_bytes)
+        {
+            return;
+        }
+
+        inner.remove(&key);
+        let tick = inner.tick();
+        inner.order.insert(tick, key.clone());
+        inner.bytes += size;
+        inner.entries.insert(
+            key,
+            Entry {
+                value,
+                inserted: Instant::now(),
+                tick,
+            },
+        );
+
+        while inner.over_budget() {
+            let Some((_, oldest)) = inner.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = inner.entries.remove(&oldest) {
+                inner.bytes -= entry_size(&oldest, &entry.value);
+            }
+        }
+    }
+
+    /// Drops every entry, keeping the settings and counters.
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.order.clear();
+        inner.bytes = 0;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+        CacheStats {
+            enabled: inner.config.max_entries > 0,
+            entries: inner.entries.len(),
+            bytes: inner.bytes,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
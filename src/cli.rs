@@ -0,0 +1,198 @@
+//! Subcommands that work on the configured stores and models and exit instead of
+//! serving.
+
+use clap::Subcommand;
+use std::path::PathBuf;
+
+use crate::config::AppConfig;
+use crate::format::{
+    self,
+    resource::{ResourceDocument, ResourceFormat},
+};
+use crate::lang;
+use crate::memory::{MemoryError, TranslationMemory};
+use crate::model::{GenerationParams, ModelManager};
+use crate::tmx;
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Import or export the translation memory as TMX 1.4
+    Tmx {
+        #[command(subcommand)]
+        action: TmxCommand,
+    },
+    /// Translate a JSON, YAML, PO or XLIFF localization resource file
+    TranslateResource {
+        /// Resource file to read
+        file: PathBuf,
+
+        #[arg(long)]
+        target_lang: String,
+
+        #[arg(long)]
+        source_lang: Option<String>,
+
+        /// File to write, standard output if not given. Translations it already holds are
+        /// reused unless `--force` is given
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Model or alias to translate with
+        #[arg(long, default_value = "default")]
+        model: String,
+
+        /// `json`, `yaml`, `po` or `xliff`; taken from the file extension if not given
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Translate entries that already have a translation too
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum TmxCommand {
+    /// Import a TMX file into the translation memory
+    Import {
+        /// TMX file to read
+        file: PathBuf,
+
+        /// Model whose language codes the TMX languages are mapped to
+        #[arg(long, default_value = "default")]
+        model: String,
+    },
+    /// Export one language pair of the translation memory
+    Export {
+        #[arg(long)]
+        source_lang: String,
+
+        #[arg(long)]
+        target_lang: String,
+
+        /// File to write, standard output if not given
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Model whose language codes the given languages are mapped to
+        #[arg(long, default_value = "default")]
+        model: String,
+    },
+}
+
+/// Runs `command` against the stores and models configured in `config`.
+pub async fn run(command: &Command, config: &AppConfig) -> Result<(), String> {
+    match command {
+        Command::Tmx { action } => run_tmx(action, config),
+        Command::TranslateResource {
+            file,
+            target_lang,
+            source_lang,
+            output,
+            model,
+            format,
+            force,
+        } => {
+            let format = format
+                .as_deref()
+                .or_else(|| file.extension().and_then(|e| e.to_str()))
+                .and_then(ResourceFormat::from_extension)
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown resource format of {}, expected --format json, yaml, po or xliff",
+                        file.display()
+                    )
+                })?;
+            let input = std::fs::read_to_string(file)
+                .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+            let mut resource =
+                ResourceDocument::parse(&input, format, *force).map_err(|e| e.to_string())?;
+            if let Some(path) = output.as_ref().filter(|path| !*force && path.exists()) {
+                let previous = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                let previous = ResourceDocument::parse(&previous, format, false)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+                resource.reuse(&previous);
+            }
+
+            let manager = ModelManager::new(config.clone());
+            let params = GenerationParams {
+                target_lang: Some(target_lang.clone()),
+                source_lang: source_lang.clone(),
+                ..Default::default()
+            };
+            let document = format::translate_resource(&manager, model, &resource, params)
+                .await
+                .map_err(|e| e.to_string())?;
+            match output {
+                Some(path) => std::fs::write(path, &document.text)
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?,
+                None => print!("{}", document.text),
+            }
+            eprintln!(
+                "Translated {} entries, kept {}",
+                resource.pending(),
+                resource.skipped()
+            );
+            if !document.lost_placeholders.is_empty() {
+                eprintln!(
+                    "Placeholders and markup dropped by the model: {}",
+                    document.lost_placeholders.join(" ")
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+fn run_tmx(action: &TmxCommand, config: &AppConfig) -> Result<(), String> {
+    let memory = TranslationMemory::open(&config.translation_memory).map_err(|e| e.to_string())?;
+    if !memory.is_enabled() {
+        return Err(MemoryError::Disabled.to_string());
+    }
+    let model_type = |model: &str| {
+        config
+            .model_type(model)
+            .ok_or_else(|| format!("Model '{}' not found", model))
+    };
+
+    match action {
+        TmxCommand::Import { file, model } => {
+            let input = std::fs::read_to_string(file)
+                .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+            let summary =
+                tmx::import_str(&memory, &input, model_type(model)?).map_err(|e| e.to_string())?;
+            println!(
+                "Imported {} translations, skipped {}",
+                summary.imported, summary.skipped
+            );
+            if !summary.unmapped_languages.is_empty() {
+                println!(
+                    "Languages without a code for the model: {}",
+                    summary.unmapped_languages.join(", ")
+                );
+            }
+            Ok(())
+        }
+        TmxCommand::Export {
+            source_lang,
+            target_lang,
+            output,
+            model,
+        } => {
+            let model_type = model_type(model)?;
+            let [source_lang, target_lang] = [source_lang, target_lang]
+                .map(|l| lang::normalize(l, model_type).map_err(|e| e.to_string()));
+            let (source_lang, target_lang) = (source_lang?, target_lang?);
+            let entries = memory.entries(&source_lang, &target_lang);
+            let document = tmx::export(&source_lang, &target_lang, &entries);
+            match output {
+                Some(path) => std::fs::write(path, document)
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?,
+                None => print!("{}", document),
+            }
+            eprintln!("Exported {} translations", entries.len());
+            Ok(())
+        }
+    }
+}
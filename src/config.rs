@@ -3,6 +3,15 @@ use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
 use std::collections::HashMap;
 
+use crate::cli::Command;
+use crate::model::{GenerationParams, Limits};
+use crate::protect::LostPlaceholders;
+
+mod validation;
+
+pub(crate) use validation::edit_distance;
+pub use validation::{COMPUTE_TYPES, DEVICES, MODEL_TYPES, ValidationError};
+
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
@@ -17,12 +26,26 @@ pub struct Args {
     /// Config file path
     #[arg(long, short, default_value = "config.toml")]
     pub config: String,
+
+    /// Validate the configuration and exit with a non-zero status if it is invalid
+    #[arg(long)]
+    pub check_config: bool,
+
+    /// Run a maintenance command instead of serving
+    #[command(subcommand)]
+    pub command: Option<Command>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// Bearer token required by the `/admin` endpoints; they are disabled when unset
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Reload the configuration automatically when the config file changes
+    #[serde(default)]
+    pub watch_config: bool,
 }
 
 impl Default for ServerConfig {
@@ -30,21 +53,227 @@ impl Default for ServerConfig {
         Self {
             host: "0.0.0.0".into(),
             port: 8080,
+            admin_token: None,
+            watch_config: false,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// `[cache]` settings for the in-memory translation cache.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct CacheConfig {
+    /// Number of cached translations; 0 disables the cache
+    #[serde(default)]
+    pub max_entries: usize,
+    /// Upper bound on the cached inputs and outputs in bytes; 0 means no bound
+    #[serde(default)]
+    pub max_bytes: usize,
+    /// Seconds a translation stays cached; 0 keeps it until evicted
+    #[serde(default)]
+    pub ttl_secs: u64,
+}
+
+/// `[translation_memory]` settings for the persistent store of approved translations.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TranslationMemoryConfig {
+    /// Directory holding one JSON Lines file per language pair; the memory is off when unset
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Minimum similarity in `[0, 1]` for a stored translation to count as a fuzzy match
+    #[serde(default = "default_fuzzy_threshold")]
+    pub fuzzy_threshold: f32,
+    /// Use the best fuzzy match as the translation instead of only reporting it
+    #[serde(default)]
+    pub apply_fuzzy: bool,
+    /// Fuzzy matches reported as alternatives per input
+    #[serde(default = "default_max_alternatives")]
+    pub max_alternatives: usize,
+}
+
+fn default_fuzzy_threshold() -> f32 {
+    0.85
+}
+
+fn default_max_alternatives() -> usize {
+    3
+}
+
+/// A `[glossaries]` entry: a CSV or TSV file of terms for one language pair.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct GlossaryConfig {
+    /// File of `source,target` rows, tab-separated if its first row holds a tab
+    pub path: String,
+    /// Language of the source terms; the glossary applies to any source language if unset
+    #[serde(default)]
+    pub source_lang: Option<String>,
+    pub target_lang: String,
+    /// Match source terms with their exact case
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+/// `[markup]` settings for HTML and XML documents.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct MarkupConfig {
+    /// Attributes whose values are translated along with the text
+    #[serde(default = "default_translatable_attributes")]
+    pub translatable_attributes: Vec<String>,
+}
+
+fn default_translatable_attributes() -> Vec<String> {
+    ["alt", "title", "placeholder", "aria-label"]
+        .map(String::from)
+        .to_vec()
+}
+
+impl Default for MarkupConfig {
+    fn default() -> Self {
+        Self {
+            translatable_attributes: default_translatable_attributes(),
+        }
+    }
+}
+
+impl Default for TranslationMemoryConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            fuzzy_threshold: default_fuzzy_threshold(),
+            apply_fuzzy: false,
+            max_alternatives: default_max_alternatives(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
 pub struct ModelSpec {
     pub path: String,
     pub model_type: String, // e.g. "t5", "nllb"
     pub tokenizer_path: Option<String>,
     pub target_lang: Option<String>,
+    pub target_langs: Option<Vec<String>>,
+    pub source_lang: Option<String>,
+    /// "cpu", "cuda", "cuda:0,1" or "auto"
     pub device: Option<String>,
     pub device_indices: Option<Vec<i32>>,
+    /// Run on CPU instead of failing when the configured GPUs are missing
+    pub allow_cpu_fallback: Option<bool>,
+    /// e.g. "int8", "int8_float16", "float16", "bfloat16"
+    pub compute_type: Option<String>,
+    pub num_threads_per_replica: Option<usize>,
+    pub max_queued_batches: Option<i32>,
+    pub cpu_core_offset: Option<i32>,
     pub beam_size: Option<usize>,
     pub repetition_penalty: Option<f32>,
     pub no_repeat_ngram_size: Option<usize>,
+    pub length_penalty: Option<f32>,
+    pub coverage_penalty: Option<f32>,
+    pub patience: Option<f32>,
+    pub max_input_length: Option<usize>,
+    pub min_decoding_length: Option<usize>,
+    pub max_decoding_length: Option<usize>,
+    pub disable_unk: Option<bool>,
+    pub replace_unknowns: Option<bool>,
+    pub suppress_sequences: Option<Vec<String>>,
+    pub sampling_topk: Option<usize>,
+    pub sampling_topp: Option<f32>,
+    pub sampling_temperature: Option<f32>,
+    pub seed: Option<u32>,
+    pub use_cache: Option<bool>,
+    pub use_memory: Option<bool>,
+    pub glossary_id: Option<String>,
+    pub protect: Option<Vec<String>>,
+    pub on_lost_placeholder: Option<LostPlaceholders>,
+    pub segment: Option<bool>,
+    /// Preset used when requests don't select one
+    pub preset: Option<String>,
+    /// Caps on client requests, falling back to the global `[limits]`
+    #[serde(default)]
+    pub limits: Limits,
+    /// Load the model at startup instead of on the first request
+    #[serde(default)]
+    pub preload: bool,
+    /// Inputs translated once after preloading so the first real request runs warm
+    #[serde(default)]
+    pub warmup_inputs: Vec<String>,
+}
+
+impl ModelSpec {
+    /// The generation settings this model overrides.
+    pub fn generation_params(&self) -> GenerationParams {
+        GenerationParams {
+            preset: self.preset.clone(),
+            target_lang: self.target_lang.clone(),
+            target_langs: self.target_langs.clone(),
+            source_lang: self.source_lang.clone(),
+            beam_size: self.beam_size,
+            repetition_penalty: self.repetition_penalty,
+            no_repeat_ngram_size: self.no_repeat_ngram_size,
+            length_penalty: self.length_penalty,
+            coverage_penalty: self.coverage_penalty,
+            patience: self.patience,
+            max_input_length: self.max_input_length,
+            min_decoding_length: self.min_decoding_length,
+            max_decoding_length: self.max_decoding_length,
+            disable_unk: self.disable_unk,
+            replace_unknowns: self.replace_unknowns,
+            suppress_sequences: self.suppress_sequences.clone(),
+            sampling_topk: self.sampling_topk,
+            sampling_topp: self.sampling_topp,
+            sampling_temperature: self.sampling_temperature,
+            seed: self.seed,
+            use_cache: self.use_cache,
+            use_memory: self.use_memory,
+            glossary_id: self.glossary_id.clone(),
+            protect: self.protect.clone(),
+            on_lost_placeholder: self.on_lost_placeholder,
+            segment: self.segment,
+        }
+    }
+}
+
+/// An `[aliases]` entry: either just the target name, or a table naming the target plus
+/// generation overrides, which turns the alias into a virtual model.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum AliasSpec {
+    Target(String),
+    Virtual(Box<VirtualModel>),
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct VirtualModel {
+    pub target: String,
+    #[serde(flatten)]
+    pub params: GenerationParams,
+}
+
+impl AliasSpec {
+    pub fn target(&self) -> &str {
+        match self {
+            AliasSpec::Target(target) => target,
+            AliasSpec::Virtual(model) => &model.target,
+        }
+    }
+
+    pub fn params(&self) -> Option<&GenerationParams> {
+        match self {
+            AliasSpec::Target(_) => None,
+            AliasSpec::Virtual(model) => Some(&model.params),
+        }
+    }
+}
+
+impl From<&str> for AliasSpec {
+    fn from(target: &str) -> Self {
+        AliasSpec::Target(target.to_string())
+    }
+}
+
+impl From<String> for AliasSpec {
+    fn from(target: String) -> Self {
+        AliasSpec::Target(target)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -53,7 +282,34 @@ pub struct AppConfig {
     #[serde(default)]
     pub models: HashMap<String, ModelSpec>,
     #[serde(default)]
-    pub aliases: HashMap<String, String>,
+    pub aliases: HashMap<String, AliasSpec>,
+    /// Named sets of generation settings selectable per request, alias or model
+    #[serde(default)]
+    pub presets: HashMap<String, GenerationParams>,
+    /// Caps on client requests, unless a model sets its own
+    #[serde(default)]
+    pub limits: Limits,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub translation_memory: TranslationMemoryConfig,
+    /// Term lists selectable per request with `glossary_id`
+    #[serde(default)]
+    pub glossaries: HashMap<String, GlossaryConfig>,
+    /// Protection patterns applied unless a request, alias or model selects others
+    #[serde(default)]
+    pub protect: Vec<String>,
+    /// Custom protection patterns by name, as regular expressions
+    #[serde(default)]
+    pub protect_patterns: HashMap<String, String>,
+    #[serde(default)]
+    pub on_lost_placeholder: LostPlaceholders,
+    /// Split inputs into sentences translated as one batch, unless a model, alias or
+    /// request says otherwise
+    #[serde(default)]
+    pub segment: bool,
+    #[serde(default)]
+    pub markup: MarkupConfig,
     #[serde(default = "default_model")]
     pub default_model: String,
     #[serde(default = "default_target_lang")]
@@ -62,12 +318,57 @@ pub struct AppConfig {
     pub device: String,
     #[serde(default = "default_device_indices")]
     pub device_indices: Vec<i32>,
+    /// Whether models configured for CUDA may run on CPU when no GPU is found
+    #[serde(default)]
+    pub allow_cpu_fallback: bool,
+    #[serde(default = "default_compute_type")]
+    pub compute_type: String,
+    /// Threads used by each model replica; 0 lets CTranslate2 decide
+    #[serde(default)]
+    pub num_threads_per_replica: usize,
+    /// Batches queued per replica before requests block; 0 means automatic, -1 unlimited
+    #[serde(default)]
+    pub max_queued_batches: i32,
+    /// First CPU core replicas are pinned to; -1 disables pinning
+    #[serde(default = "default_cpu_core_offset")]
+    pub cpu_core_offset: i32,
     #[serde(default = "default_beam_size")]
     pub beam_size: usize,
     #[serde(default = "default_repetition_penalty")]
     pub repetition_penalty: f32,
     #[serde(default = "default_no_repeat_ngram_size")]
     pub no_repeat_ngram_size: usize,
+    #[serde(default = "default_length_penalty")]
+    pub length_penalty: f32,
+    #[serde(default)]
+    pub coverage_penalty: f32,
+    #[serde(default = "default_patience")]
+    pub patience: f32,
+    /// Inputs are truncated to this many tokens; 0 disables truncation
+    #[serde(default = "default_max_input_length")]
+    pub max_input_length: usize,
+    #[serde(default = "default_min_decoding_length")]
+    pub min_decoding_length: usize,
+    #[serde(default = "default_max_decoding_length")]
+    pub max_decoding_length: usize,
+    #[serde(default)]
+    pub disable_unk: bool,
+    #[serde(default)]
+    pub replace_unknowns: bool,
+    /// Phrases the decoder must never produce
+    #[serde(default)]
+    pub suppress_sequences: Vec<String>,
+    /// Sample from this many most likely tokens; 1 decodes deterministically, 0 samples
+    /// from the full distribution
+    #[serde(default = "default_sampling_topk")]
+    pub sampling_topk: usize,
+    #[serde(default = "default_sampling_topp")]
+    pub sampling_topp: f32,
+    #[serde(default = "default_sampling_temperature")]
+    pub sampling_temperature: f32,
+    /// Arguments the configuration was loaded with, reused on reload
+    #[serde(skip)]
+    pub source: Option<Args>,
 }
 
 fn default_model() -> String {
@@ -86,6 +387,14 @@ fn default_device_indices() -> Vec<i32> {
     vec![0]
 }
 
+fn default_compute_type() -> String {
+    "default".to_string()
+}
+
+fn default_cpu_core_offset() -> i32 {
+    -1
+}
+
 fn default_beam_size() -> usize {
     5
 }
@@ -98,19 +407,79 @@ fn default_no_repeat_ngram_size() -> usize {
     0
 }
 
+fn default_length_penalty() -> f32 {
+    1.0
+}
+
+fn default_patience() -> f32 {
+    1.0
+}
+
+fn default_max_input_length() -> usize {
+    1024
+}
+
+fn default_min_decoding_length() -> usize {
+    1
+}
+
+fn default_max_decoding_length() -> usize {
+    256
+}
+
+fn default_sampling_topk() -> usize {
+    1
+}
+
+fn default_sampling_topp() -> f32 {
+    1.0
+}
+
+fn default_sampling_temperature() -> f32 {
+    1.0
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             server: ServerConfig::default(),
             models: HashMap::new(),
             aliases: HashMap::new(),
+            presets: HashMap::new(),
+            limits: Limits::default(),
+            cache: CacheConfig::default(),
+            translation_memory: TranslationMemoryConfig::default(),
+            glossaries: HashMap::new(),
+            protect: Vec::new(),
+            protect_patterns: HashMap::new(),
+            markup: MarkupConfig::default(),
+            on_lost_placeholder: LostPlaceholders::default(),
+            segment: false,
             default_model: default_model(),
             target_lang: default_target_lang(),
             device: default_device(),
             device_indices: default_device_indices(),
+            allow_cpu_fallback: false,
+            compute_type: default_compute_type(),
+            num_threads_per_replica: 0,
+            max_queued_batches: 0,
+            cpu_core_offset: default_cpu_core_offset(),
             beam_size: default_beam_size(),
             repetition_penalty: default_repetition_penalty(),
             no_repeat_ngram_size: default_no_repeat_ngram_size(),
+            length_penalty: default_length_penalty(),
+            coverage_penalty: 0.0,
+            patience: default_patience(),
+            max_input_length: default_max_input_length(),
+            min_decoding_length: default_min_decoding_length(),
+            max_decoding_length: default_max_decoding_length(),
+            disable_unk: false,
+            replace_unknowns: false,
+            suppress_sequences: Vec::new(),
+            sampling_topk: default_sampling_topk(),
+            sampling_topp: default_sampling_topp(),
+            sampling_temperature: default_sampling_temperature(),
+            source: None,
         }
     }
 }
@@ -129,24 +498,189 @@ impl AppConfig {
             .set_default("default_model", "nllb")?
             .set_default("target_lang", "eng_Latn")?
             .set_default("device", "cpu")?
+            .set_default("compute_type", "default")?
+            .set_default("num_threads_per_replica", 0)?
+            .set_default("max_queued_batches", 0)?
+            .set_default("cpu_core_offset", -1)?
             .set_default("beam_size", 5)?
             .set_default("repetition_penalty", 1.2)?
             .set_default("no_repeat_ngram_size", 0)?
+            .set_default("length_penalty", 1.0)?
+            .set_default("coverage_penalty", 0.0)?
+            .set_default("patience", 1.0)?
+            .set_default("max_input_length", 1024)?
+            .set_default("min_decoding_length", 1)?
+            .set_default("max_decoding_length", 256)?
+            .set_default("sampling_topk", 1)?
+            .set_default("sampling_topp", 1.0)?
+            .set_default("sampling_temperature", 1.0)?
             // Add config file
             .add_source(File::with_name(config_path).required(false))
             // Add environment variables (e.g. SERVER_PORT)
             .add_source(Environment::default().separator("_"));
 
         // Add CLI arguments if provided
-        if let Some(args) = args {
-            if let Some(host) = args.host {
-                builder = builder.set_override("server.host", host)?;
+        if let Some(args) = &args {
+            if let Some(host) = &args.host {
+                builder = builder.set_override("server.host", host.clone())?;
             }
             if let Some(port) = args.port {
                 builder = builder.set_override("server.port", port)?;
             }
         }
 
-        builder.build()?.try_deserialize()
+        let mut config: AppConfig = builder.build()?.try_deserialize()?;
+        config.source = args;
+        Ok(config)
+    }
+
+    /// Loads the configuration again from the same file, environment and CLI arguments.
+    pub fn reload(&self) -> Result<Self, ConfigError> {
+        Self::load(self.source.clone())
+    }
+
+    /// Path of the config file this configuration was loaded from.
+    pub fn config_path(&self) -> &str {
+        self.source
+            .as_ref()
+            .map(|a| a.config.as_str())
+            .unwrap_or("config.toml")
+    }
+
+    /// Follows `name` through the aliases to the name it finally stands for.
+    ///
+    /// `"default"` and the empty name stand for `default_model`, which may itself be an
+    /// alias. If the aliases form a cycle, the chain of names visited is returned instead.
+    pub fn resolve_model_name(&self, name: &str) -> Result<String, Vec<String>> {
+        self.alias_chain(name)
+            .map(|chain| chain.last().copied().unwrap_or(name).to_string())
+    }
+
+    /// The `model_type` of the model `name` stands for, following aliases.
+    pub fn model_type(&self, name: &str) -> Option<&str> {
+        let resolved = self.resolve_model_name(name).ok()?;
+        self.models
+            .get(&resolved)
+            .map(|spec| spec.model_type.as_str())
+    }
+
+    /// Generation overrides picked up along the alias chain of `name`, nearest alias first.
+    pub fn alias_params(&self, name: &str) -> GenerationParams {
+        let Ok(chain) = self.alias_chain(name) else {
+            return GenerationParams::default();
+        };
+        chain
+            .iter()
+            .filter_map(|n| self.aliases.get(*n))
+            .filter_map(AliasSpec::params)
+            .fold(GenerationParams::default(), |params, fallback| {
+                params.or(fallback)
+            })
+    }
+
+    /// Fills the settings `params` leaves open from the preset it selects, if any.
+    ///
+    /// Returns the preset name if no such preset is configured.
+    pub fn with_preset(&self, params: GenerationParams) -> Result<GenerationParams, String> {
+        let Some(name) = &params.preset else {
+            return Ok(params);
+        };
+        match self.presets.get(name) {
+            Some(preset) => Ok(params.or(preset)),
+            None => Err(name.clone()),
+        }
+    }
+
+    /// The global generation defaults, every setting given.
+    pub fn generation_defaults(&self) -> GenerationParams {
+        GenerationParams {
+            preset: None,
+            target_lang: Some(self.target_lang.clone()),
+            target_langs: None,
+            source_lang: None,
+            beam_size: Some(self.beam_size),
+            repetition_penalty: Some(self.repetition_penalty),
+            no_repeat_ngram_size: Some(self.no_repeat_ngram_size),
+            length_penalty: Some(self.length_penalty),
+            coverage_penalty: Some(self.coverage_penalty),
+            patience: Some(self.patience),
+            max_input_length: Some(self.max_input_length),
+            min_decoding_length: Some(self.min_decoding_length),
+            max_decoding_length: Some(self.max_decoding_length),
+            disable_unk: Some(self.disable_unk),
+            replace_unknowns: Some(self.replace_unknowns),
+            suppress_sequences: Some(self.suppress_sequences.clone()),
+            sampling_topk: Some(self.sampling_topk),
+            sampling_topp: Some(self.sampling_topp),
+            sampling_temperature: Some(self.sampling_temperature),
+            seed: None,
+            use_cache: Some(true),
+            use_memory: Some(true),
+            glossary_id: None,
+            protect: Some(self.protect.clone()),
+            on_lost_placeholder: Some(self.on_lost_placeholder),
+            segment: Some(self.segment),
+        }
+    }
+
+    /// Limits for the model `resolved_name`, falling back to the global ones per limit.
+    pub fn limits(&self, resolved_name: &str) -> Limits {
+        match self.models.get(resolved_name) {
+            Some(spec) => spec.limits.clone().or(&self.limits),
+            None => self.limits.clone(),
+        }
+    }
+
+    /// Every name visited while resolving `name`, ending with the model name.
+    fn alias_chain<'a>(&'a self, name: &'a str) -> Result<Vec<&'a str>, Vec<String>> {
+        let mut current =
+            if (name == "default" || name.is_empty()) && !self.aliases.contains_key(name) {
+                self.default_model.as_str()
+            } else {
+                name
+            };
+
+        let mut chain: Vec<&str> = Vec::new();
+        while let Some(alias) = self.aliases.get(current) {
+            chain.push(current);
+            if chain.contains(&alias.target()) {
+                chain.push(alias.target());
+                return Err(chain.into_iter().map(String::from).collect());
+            }
+            current = alias.target();
+        }
+        chain.push(current);
+        Ok(chain)
+    }
+
+    /// Returns the settings `name` is loaded with, those it inherits from the global
+    /// defaults filled in and generation settings left out, so two specs compare equal
+    /// exactly when they load the same way.
+    pub fn effective_spec(&self, name: &str) -> Option<ModelSpec> {
+        let spec = self.models.get(name)?;
+        Some(ModelSpec {
+            path: spec.path.clone(),
+            model_type: spec.model_type.clone(),
+            tokenizer_path: spec.tokenizer_path.clone(),
+            device: Some(spec.device.clone().unwrap_or_else(|| self.device.clone())),
+            device_indices: Some(
+                spec.device_indices
+                    .clone()
+                    .unwrap_or_else(|| self.device_indices.clone()),
+            ),
+            allow_cpu_fallback: Some(spec.allow_cpu_fallback.unwrap_or(self.allow_cpu_fallback)),
+            compute_type: Some(
+                spec.compute_type
+                    .clone()
+                    .unwrap_or_else(|| self.compute_type.clone()),
+            ),
+            num_threads_per_replica: Some(
+                spec.num_threads_per_replica
+                    .unwrap_or(self.num_threads_per_replica),
+            ),
+            max_queued_batches: Some(spec.max_queued_batches.unwrap_or(self.max_queued_batches)),
+            cpu_core_offset: Some(spec.cpu_core_offset.unwrap_or(self.cpu_core_offset)),
+            ..Default::default()
+        })
     }
 }
@@ -0,0 +1,755 @@
+use config::{Config, File};
+use snafu::prelude::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::{AliasSpec, AppConfig, GlossaryConfig};
+use crate::detect;
+use crate::device::{self, DeviceSpec};
+use crate::glossary::Glossary;
+use crate::lang;
+use crate::model::{GenerationParams, Limits};
+use crate::protect::PRESETS;
+
+/// Device names accepted by `device` settings.
+pub const DEVICES: &[&str] = &["cpu", "cuda", "gpu", "auto"];
+
+/// Compute types accepted by `compute_type` settings.
+pub const COMPUTE_TYPES: &[&str] = &[
+    "default",
+    "auto",
+    "float32",
+    "int8",
+    "int8_float32",
+    "int8_float16",
+    "int8_bfloat16",
+    "int16",
+    "float16",
+    "bfloat16",
+];
+
+/// Model architectures the server knows how to drive.
+pub const MODEL_TYPES: &[&str] = &["nllb", "t5", "m2m100", "marian"];
+
+/// Files one of which must exist in a tokenizer directory.
+const TOKENIZER_FILES: &[&str] = &[
+    "tokenizer.json",
+    "sentencepiece.model",
+    "sentencepiece.bpe.model",
+    "spiece.model",
+    "source.spm",
+    "vocab.json",
+];
+
+/// Settings of [`GenerationParams`], accepted in model specs and virtual models.
+const GENERATION_KEYS: &[&str] = &[
+    "preset",
+    "target_lang",
+    "target_langs",
+    "source_lang",
+    "beam_size",
+    "repetition_penalty",
+    "no_repeat_ngram_size",
+    "length_penalty",
+    "coverage_penalty",
+    "patience",
+    "max_input_length",
+    "min_decoding_length",
+    "max_decoding_length",
+    "disable_unk",
+    "replace_unknowns",
+    "suppress_sequences",
+    "sampling_topk",
+    "sampling_topp",
+    "sampling_temperature",
+    "seed",
+    "use_cache",
+    "use_memory",
+    "glossary_id",
+    "protect",
+    "on_lost_placeholder",
+    "segment",
+];
+
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "server",
+    "models",
+    "aliases",
+    "presets",
+    "limits",
+    "default_model",
+    "target_lang",
+    "device",
+    "device_indices",
+    "allow_cpu_fallback",
+    "compute_type",
+    "num_threads_per_replica",
+    "max_queued_batches",
+    "cpu_core_offset",
+    "beam_size",
+    "repetition_penalty",
+    "no_repeat_ngram_size",
+    "length_penalty",
+    "coverage_penalty",
+    "patience",
+    "max_input_length",
+    "min_decoding_length",
+    "max_decoding_length",
+    "disable_unk",
+    "replace_unknowns",
+    "suppress_sequences",
+    "sampling_topk",
+    "sampling_topp",
+    "sampling_temperature",
+    "cache",
+    "translation_memory",
+    "glossaries",
+    "protect",
+    "protect_patterns",
+    "on_lost_placeholder",
+    "segment",
+    "markup",
+];
+
+const SERVER_KEYS: &[&str] = &["host", "port", "admin_token", "watch_config"];
+
+/// Keys of a model spec besides the [`GENERATION_KEYS`].
+const MODEL_KEYS: &[&str] = &[
+    "path",
+    "model_type",
+    "tokenizer_path",
+    "device",
+    "device_indices",
+    "allow_cpu_fallback",
+    "compute_type",
+    "num_threads_per_replica",
+    "max_queued_batches",
+    "cpu_core_offset",
+    "preload",
+    "warmup_inputs",
+    "limits",
+];
+
+const CACHE_KEYS: &[&str] = &["max_entries", "max_bytes", "ttl_secs"];
+
+const TRANSLATION_MEMORY_KEYS: &[&str] =
+    &["path", "fuzzy_threshold", "apply_fuzzy", "max_alternatives"];
+
+const MARKUP_KEYS: &[&str] = &["translatable_attributes"];
+
+const GLOSSARY_KEYS: &[&str] = &["path", "source_lang", "target_lang", "case_sensitive"];
+
+/// Keys of a `[limits]` table, globally or per model.
+const LIMIT_KEYS: &[&str] = &[
+    "max_beam_size",
+    "max_input_chars",
+    "max_input_tokens",
+    "max_batch_items",
+    "max_decoding_length",
+    "allowed_target_langs",
+];
+
+/// Keys of a virtual model table in `[aliases]` besides the [`GENERATION_KEYS`].
+const ALIAS_KEYS: &[&str] = &["target"];
+
+/// Every problem found in a configuration, reported together.
+#[derive(Debug, Snafu)]
+#[snafu(display("Invalid configuration:{}", format_issues(issues)))]
+pub struct ValidationError {
+    pub issues: Vec<String>,
+}
+
+fn format_issues(issues: &[String]) -> String {
+    issues
+        .iter()
+        .map(|issue| format!("\n  - {}", issue))
+        .collect()
+}
+
+impl AppConfig {
+    /// Checks the configuration for mistakes that would otherwise only surface on the
+    /// first request, returning warnings about those that may never matter.
+    ///
+    /// A missing model or tokenizer directory is an issue for preloaded models and for the
+    /// models or aliases in `required`, and only a warning for lazily loaded models, which
+    /// may be installed before they are first used. Unknown keys are only reported for
+    /// configurations loaded from a file.
+    pub fn validate(&self, required: &[&str]) -> Result<Vec<String>, ValidationError> {
+        let mut issues = Vec::new();
+        let mut warnings = Vec::new();
+
+        if self.source.is_some() {
+            check_unknown_keys(Path::new(self.config_path()), &mut issues);
+        }
+        self.check_aliases(&mut issues);
+        self.check_presets(&mut issues);
+        self.check_glossaries(&mut issues);
+        self.check_protection(&mut issues);
+        self.check_globals(&mut issues);
+
+        let mut names: Vec<&String> = self.models.keys().collect();
+        names.sort();
+        let required: Vec<String> = required
+            .iter()
+            .filter_map(|name| self.resolve_model_name(name).ok())
+            .collect();
+        for name in names {
+            let missing = if self.models[name].preload || required.contains(name) {
+                &mut issues
+            } else {
+                &mut warnings
+            };
+            self.check_model_dirs(name, missing);
+            self.check_model(name, &mut issues);
+        }
+
+        if issues.is_empty() {
+            Ok(warnings)
+        } else {
+            ValidationSnafu { issues }.fail()
+        }
+    }
+
+    fn check_aliases(&self, issues: &mut Vec<String>) {
+        let mut aliases: Vec<(&String, &AliasSpec)> = self.aliases.iter().collect();
+        aliases.sort_by_key(|(alias, _)| *alias);
+
+        for (alias, spec) in aliases {
+            if let Some(params) = spec.params() {
+                let prefix = format!("aliases.{}", alias);
+                self.check_preset_ref(&prefix, params, issues);
+                self.check_protect_ref(&prefix, params.protect.as_deref(), issues);
+                check_params(&prefix, params, issues);
+            }
+
+            let target = spec.target();
+            if !self.models.contains_key(target) && !self.aliases.contains_key(target) {
+                issues.push(format!(
+                    "aliases.{}: points to unknown model '{}'{}",
+                    alias,
+                    target,
+                    suggestion(target, self.models.keys().chain(self.aliases.keys()))
+                ));
+                continue;
+            }
+
+            if let Err(chain) = self.resolve_model_name(alias) {
+                issues.push(format!(
+                    "aliases.{}: alias cycle {}",
+                    alias,
+                    chain.join(" -> ")
+                ));
+            }
+        }
+
+        if self.models.is_empty() {
+            issues.push("models: no models are configured".to_string());
+        } else if !self.models.contains_key(&self.default_model)
+            && !self.aliases.contains_key(&self.default_model)
+        {
+            issues.push(format!(
+                "default_model: '{}' is neither a model nor an alias{}",
+                self.default_model,
+                suggestion(
+                    &self.default_model,
+                    self.models.keys().chain(self.aliases.keys())
+                )
+            ));
+        }
+    }
+
+    fn check_presets(&self, issues: &mut Vec<String>) {
+        let mut presets: Vec<(&String, &GenerationParams)> = self.presets.iter().collect();
+        presets.sort_by_key(|(name, _)| *name);
+
+        for (name, params) in presets {
+            let prefix = format!("presets.{}", name);
+            if params.preset.is_some() {
+                issues.push(format!(
+                    "{}.preset: presets cannot select another preset",
+                    prefix
+                ));
+            }
+            self.check_protect_ref(&prefix, params.protect.as_deref(), issues);
+            check_params(&prefix, params, issues);
+        }
+    }
+
+    fn check_glossaries(&self, issues: &mut Vec<String>) {
+        let mut glossaries: Vec<(&String, &GlossaryConfig)> = self.glossaries.iter().collect();
+        glossaries.sort_by_key(|(id, _)| *id);
+
+        for (id, glossary) in glossaries {
+            if let Err(e) = Glossary::from_config(id, glossary) {
+                issues.push(format!("glossaries.{}: {}", id, e));
+            }
+        }
+    }
+
+    fn check_protection(&self, issues: &mut Vec<String>) {
+        let mut patterns: Vec<(&String, &String)> = self.protect_patterns.iter().collect();
+        patterns.sort_by_key(|(name, _)| *name);
+
+        for (name, pattern) in patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                issues.push(format!("protect_patterns.{}: invalid pattern: {}", name, e));
+            }
+        }
+        self.check_protect_ref("", Some(&self.protect), issues);
+    }
+
+    /// Reports `protect` entries naming neither a built-in nor a custom pattern.
+    fn check_protect_ref(&self, prefix: &str, names: Option<&[String]>, issues: &mut Vec<String>) {
+        let key = if prefix.is_empty() {
+            "protect".to_string()
+        } else {
+            format!("{}.protect", prefix)
+        };
+        for name in names.unwrap_or_default() {
+            if !self.protect_patterns.contains_key(name)
+                && !PRESETS.iter().any(|(preset, _)| preset == name)
+            {
+                issues.push(format!(
+                    "{}: unknown protection pattern '{}'{}",
+                    key,
+                    name,
+                    suggestion(
+                        name,
+                        PRESETS
+                            .iter()
+                            .map(|(preset, _)| *preset)
+                            .chain(self.protect_patterns.keys().map(String::as_str))
+                    )
+                ));
+            }
+        }
+    }
+
+    /// Reports a `preset` setting naming a preset that is not configured.
+    fn check_preset_ref(&self, prefix: &str, params: &GenerationParams, issues: &mut Vec<String>) {
+        if let Some(preset) = &params.preset
+            && !self.presets.contains_key(preset)
+        {
+            issues.push(format!(
+                "{}.preset: unknown preset '{}'{}",
+                prefix,
+                preset,
+                suggestion(preset, self.presets.keys())
+            ));
+        }
+    }
+
+    fn check_globals(&self, issues: &mut Vec<String>) {
+        check_device("device", &self.device, self.allow_cpu_fallback, issues);
+        check_device_indices("device_indices", &self.device_indices, issues);
+        check_replica_settings(
+            "",
+            Some(&self.compute_type),
+            Some(self.max_queued_batches),
+            Some(self.cpu_core_offset),
+            issues,
+        );
+        if self.beam_size == 0 {
+            issues.push("beam_size: must be at least 1".to_string());
+        }
+        if self.repetition_penalty <= 0.0 {
+            issues.push("repetition_penalty: must be greater than 0".to_string());
+        }
+        if self.patience <= 0.0 {
+            issues.push("patience: must be greater than 0".to_string());
+        }
+        if !(self.sampling_topp > 0.0 && self.sampling_topp <= 1.0) {
+            issues.push("sampling_topp: must be in (0, 1]".to_string());
+        }
+        if self.sampling_temperature <= 0.0 {
+            issues.push("sampling_temperature: must be greater than 0".to_string());
+        }
+        let threshold = self.translation_memory.fuzzy_threshold;
+        if !(0.0..=1.0).contains(&threshold) {
+            issues.push("translation_memory.fuzzy_threshold: must be between 0 and 1".to_string());
+        }
+        if self.max_decoding_length == 0 {
+            issues.push("max_decoding_length: must be at least 1".to_string());
+        }
+        check_limits("limits", &self.limits, issues);
+    }
+
+    /// Reports a missing model or tokenizer directory, or one without a tokenizer.
+    fn check_model_dirs(&self, name: &str, issues: &mut Vec<String>) {
+        let spec = &self.models[name];
+        let prefix = format!("models.{}", name);
+
+        if !Path::new(&spec.path).is_dir() {
+            issues.push(format!(
+                "{}.path: model directory '{}' does not exist",
+                prefix, spec.path
+            ));
+        }
+
+        let tokenizer_path = spec.tokenizer_path.as_ref().unwrap_or(&spec.path);
+        let tokenizer_dir = Path::new(tokenizer_path);
+        if spec.tokenizer_path.is_some() && !tokenizer_dir.is_dir() {
+            issues.push(format!(
+                "{}.tokenizer_path: directory '{}' does not exist",
+                prefix, tokenizer_path
+            ));
+        } else if tokenizer_dir.is_dir()
+            && !TOKENIZER_FILES
+                .iter()
+                .any(|file| tokenizer_dir.join(file).is_file())
+        {
+            issues.push(format!(
+                "{}: no tokenizer found in '{}' (expected one of {})",
+                prefix,
+                tokenizer_path,
+                TOKENIZER_FILES.join(", ")
+            ));
+        }
+    }
+
+    fn check_model(&self, name: &str, issues: &mut Vec<String>) {
+        let spec = &self.models[name];
+        let prefix = format!("models.{}", name);
+
+        if !MODEL_TYPES.contains(&spec.model_type.as_str()) {
+            issues.push(format!(
+                "{}.model_type: unknown model type '{}', expected one of {}{}",
+                prefix,
+                spec.model_type,
+                MODEL_TYPES.join(", "),
+                suggestion(&spec.model_type, MODEL_TYPES.iter())
+            ));
+        }
+
+        if let Some(device) = &spec.device {
+            check_device(
+                &format!("{}.device", prefix),
+                device,
+                spec.allow_cpu_fallback.unwrap_or(self.allow_cpu_fallback),
+                issues,
+            );
+        }
+        if let Some(indices) = &spec.device_indices {
+            check_device_indices(&format!("{}.device_indices", prefix), indices, issues);
+        }
+        check_replica_settings(
+            &format!("{}.", prefix),
+            spec.compute_type.as_deref(),
+            spec.max_queued_batches,
+            spec.cpu_core_offset,
+            issues,
+        );
+        let params = spec.generation_params();
+        self.check_preset_ref(&prefix, &params, issues);
+        self.check_protect_ref(&prefix, params.protect.as_deref(), issues);
+        check_params(&prefix, &params, issues);
+        check_languages(&prefix, &params, &spec.model_type, issues);
+        check_limits(&format!("{}.limits", prefix), &spec.limits, issues);
+    }
+}
+
+/// Reports language codes a `model_type` model has no code for.
+fn check_languages(
+    prefix: &str,
+    params: &GenerationParams,
+    model_type: &str,
+    issues: &mut Vec<String>,
+) {
+    let codes = [
+        ("source_lang", params.source_lang.as_ref()),
+        ("target_lang", params.target_lang.as_ref()),
+    ]
+    .into_iter()
+    .filter_map(|(key, code)| Some((key, code?)))
+    .chain(
+        params
+            .target_langs
+            .iter()
+            .flatten()
+            .map(|code| ("target_langs", code)),
+    );
+    for (key, code) in codes {
+        if code != detect::AUTO
+            && let Err(e) = lang::normalize(code, model_type)
+        {
+            issues.push(format!("{}.{}: {}", prefix, key, e));
+        }
+    }
+}
+
+fn check_limits(prefix: &str, limits: &Limits, issues: &mut Vec<String>) {
+    let caps = [
+        ("max_beam_size", limits.max_beam_size),
+        ("max_input_chars", limits.max_input_chars),
+        ("max_input_tokens", limits.max_input_tokens),
+        ("max_batch_items", limits.max_batch_items),
+        ("max_decoding_length", limits.max_decoding_length),
+    ];
+    for (key, cap) in caps {
+        if cap == Some(0) {
+            issues.push(format!("{}.{}: must be at least 1", prefix, key));
+        }
+    }
+    if limits
+        .allowed_target_langs
+        .as_ref()
+        .is_some_and(Vec::is_empty)
+    {
+        issues.push(format!(
+            "{}.allowed_target_langs: must list at least one language",
+            prefix
+        ));
+    }
+}
+
+fn check_replica_settings(
+    prefix: &str,
+    compute_type: Option<&str>,
+    max_queued_batches: Option<i32>,
+    cpu_core_offset: Option<i32>,
+    issues: &mut Vec<String>,
+) {
+    if let Some(compute_type) = compute_type
+        && !COMPUTE_TYPES.contains(&compute_type.to_lowercase().as_str())
+    {
+        issues.push(format!(
+            "{}compute_type: unknown compute type '{}', expected one of {}{}",
+            prefix,
+            compute_type,
+            COMPUTE_TYPES.join(", "),
+            suggestion(compute_type, COMPUTE_TYPES.iter())
+        ));
+    }
+    if max_queued_batches.is_some_and(|n| n < -1) {
+        issues.push(format!(
+            "{}max_queued_batches: must be -1 (unlimited), 0 (automatic) or positive",
+            prefix
+        ));
+    }
+    if cpu_core_offset.is_some_and(|n| n < -1) {
+        issues.push(format!(
+            "{}cpu_core_offset: must be -1 (no pinning) or a core index",
+            prefix
+        ));
+    }
+}
+
+fn check_params(prefix: &str, params: &GenerationParams, issues: &mut Vec<String>) {
+    if params.beam_size == Some(0) {
+        issues.push(format!("{}.beam_size: must be at least 1", prefix));
+    }
+    if params.max_decoding_length == Some(0) {
+        issues.push(format!(
+            "{}.max_decoding_length: must be at least 1",
+            prefix
+        ));
+    }
+    if params.repetition_penalty.is_some_and(|p| p <= 0.0) {
+        issues.push(format!(
+            "{}.repetition_penalty: must be greater than 0",
+            prefix
+        ));
+    }
+    if params.patience.is_some_and(|p| p <= 0.0) {
+        issues.push(format!("{}.patience: must be greater than 0", prefix));
+    }
+    if params.sampling_topp.is_some_and(|p| !(p > 0.0 && p <= 1.0)) {
+        issues.push(format!("{}.sampling_topp: must be in (0, 1]", prefix));
+    }
+    if params.sampling_temperature.is_some_and(|t| t <= 0.0) {
+        issues.push(format!(
+            "{}.sampling_temperature: must be greater than 0",
+            prefix
+        ));
+    }
+    if params
+        .suppress_sequences
+        .as_ref()
+        .is_some_and(|phrases| phrases.iter().any(|p| p.trim().is_empty()))
+    {
+        issues.push(format!(
+            "{}.suppress_sequences: phrases must not be empty",
+            prefix
+        ));
+    }
+    if params.target_langs.as_ref().is_some_and(Vec::is_empty) {
+        issues.push(format!(
+            "{}.target_langs: must list at least one language",
+            prefix
+        ));
+    }
+}
+
+fn check_device(key: &str, device: &str, allow_cpu_fallback: bool, issues: &mut Vec<String>) {
+    match device.parse::<DeviceSpec>() {
+        Ok(DeviceSpec::Cuda(_)) if !cfg!(feature = "cuda") && !allow_cpu_fallback => {
+            issues.push(format!(
+                "{}: CUDA support is not compiled in {}, use \"auto\" or allow_cpu_fallback",
+                key,
+                device::CUDA_HINT
+            ));
+        }
+        Ok(_) => {}
+        Err(message) => {
+            let name = device.split(':').next().unwrap_or(device);
+            issues.push(format!(
+                "{}: {}, expected one of {} (optionally with indices, e.g. cuda:0,1){}",
+                key,
+                message,
+                DEVICES.join(", "),
+                suggestion(name, DEVICES.iter())
+            ));
+        }
+    }
+}
+
+fn check_device_indices(key: &str, indices: &[i32], issues: &mut Vec<String>) {
+    if indices.is_empty() {
+        issues.push(format!("{}: must list at least one device", key));
+    }
+    if indices.iter().any(|&i| i < 0) {
+        issues.push(format!("{}: device indices must not be negative", key));
+    }
+}
+
+/// Reports keys in the config file that no setting reads, which usually are typos.
+fn check_unknown_keys(path: &Path, issues: &mut Vec<String>) {
+    if !path.is_file() {
+        return;
+    }
+    let raw = Config::builder()
+        .add_source(File::from(path))
+        .build()
+        .and_then(|c| c.try_deserialize::<BTreeMap<String, serde_json::Value>>());
+    let raw = match raw {
+        Ok(raw) => raw,
+        Err(e) => {
+            issues.push(format!("{}: {}", path.display(), e));
+            return;
+        }
+    };
+
+    for (key, value) in &raw {
+        match key.as_str() {
+            "server" => check_table_keys("server", value, &[SERVER_KEYS], issues),
+            "models" => {
+                if let Some(models) = value.as_object() {
+                    for (name, spec) in models {
+                        check_table_keys(
+                            &format!("models.{}", name),
+                            spec,
+                            &[MODEL_KEYS, GENERATION_KEYS],
+                            issues,
+                        );
+                        if let Some(limits) = spec.get("limits") {
+                            check_table_keys(
+                                &format!("models.{}.limits", name),
+                                limits,
+                                &[LIMIT_KEYS],
+                                issues,
+                            );
+                        }
+                    }
+                }
+            }
+            "aliases" => {
+                if let Some(aliases) = value.as_object() {
+                    for (name, spec) in aliases {
+                        check_table_keys(
+                            &format!("aliases.{}", name),
+                            spec,
+                            &[ALIAS_KEYS, GENERATION_KEYS],
+                            issues,
+                        );
+                    }
+                }
+            }
+            "limits" => check_table_keys("limits", value, &[LIMIT_KEYS], issues),
+            "cache" => check_table_keys("cache", value, &[CACHE_KEYS], issues),
+            "markup" => check_table_keys("markup", value, &[MARKUP_KEYS], issues),
+            "translation_memory" => check_table_keys(
+                "translation_memory",
+                value,
+                &[TRANSLATION_MEMORY_KEYS],
+                issues,
+            ),
+            "glossaries" => {
+                if let Some(glossaries) = value.as_object() {
+                    for (id, glossary) in glossaries {
+                        check_table_keys(
+                            &format!("glossaries.{}", id),
+                            glossary,
+                            &[GLOSSARY_KEYS],
+                            issues,
+                        );
+                    }
+                }
+            }
+            "presets" => {
+                if let Some(presets) = value.as_object() {
+                    for (name, params) in presets {
+                        check_table_keys(
+                            &format!("presets.{}", name),
+                            params,
+                            &[GENERATION_KEYS],
+                            issues,
+                        );
+                    }
+                }
+            }
+            _ => check_key("", key, &[TOP_LEVEL_KEYS], issues),
+        }
+    }
+}
+
+fn check_table_keys(
+    prefix: &str,
+    value: &serde_json::Value,
+    known: &[&[&str]],
+    issues: &mut Vec<String>,
+) {
+    if let Some(table) = value.as_object() {
+        for key in table.keys() {
+            check_key(prefix, key, known, issues);
+        }
+    }
+}
+
+fn check_key(prefix: &str, key: &str, known: &[&[&str]], issues: &mut Vec<String>) {
+    let known = known.iter().flat_map(|keys| keys.iter());
+    if known.clone().any(|k| *k == key) {
+        return;
+    }
+    let path = if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    };
+    issues.push(format!("{}: unknown key{}", path, suggestion(key, known)));
+}
+
+/// Formats a "did you mean" hint for the candidate closest to `input`, if any is close enough.
+fn suggestion<S: AsRef<str>>(input: &str, candidates: impl Iterator<Item = S>) -> String {
+    let max_distance = (input.chars().count() / 3).max(2);
+    candidates
+        .map(|c| (edit_distance(input, c.as_ref()), c.as_ref().to_string()))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, c)| format!(" (did you mean '{}'?)", c))
+        .unwrap_or_default()
+}
+
+/// Levenshtein distance between two strings, counted in characters.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
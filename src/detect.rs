@@ -0,0 +1,354 @@
+//! Language identification: the dominant Unicode script of a text, then a character
+//! trigram model for the scripts several languages share.
+//!
+//! The model ships as `data/langid.txt`, built from the sample texts in `scripts/langid`
+//! with `cargo run --bin langid_generator`.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::lang;
+
+/// `source_lang` value asking for the source language to be detected.
+pub const AUTO: &str = "auto";
+
+/// Candidates reported besides the most likely language.
+const MAX_CANDIDATES: usize = 3;
+
+/// Pseudo-count of trigrams a language profile has not seen.
+const UNSEEN: f64 = 0.5;
+
+/// Nominal number of distinct trigrams, for smoothing.
+const VOCABULARY: f64 = 20_000.0;
+
+const MODEL: &str = include_str!("../data/langid.txt");
+
+/// A writing system, named by its ISO 15924 code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Script {
+    Latn,
+    Cyrl,
+    Grek,
+    Armn,
+    Hebr,
+    Arab,
+    Deva,
+    Beng,
+    Guru,
+    Gujr,
+    Taml,
+    Telu,
+    Knda,
+    Mlym,
+    Sinh,
+    Thai,
+    Mymr,
+    Geor,
+    Hang,
+    Ethi,
+    Khmr,
+    /// Kana, along with the Han characters of Japanese text
+    Jpan,
+    Hani,
+}
+
+impl Script {
+    /// The script of the letter `c`, `None` for digits, punctuation and symbols.
+    pub fn of(c: char) -> Option<Script> {
+        let script = match c {
+            'a'..='z' | 'A'..='Z' | '\u{C0}'..='\u{24F}' | '\u{1E00}'..='\u{1EFF}' => {
+                return c.is_alphabetic().then_some(Script::Latn);
+            }
+            '\u{370}'..='\u{3FF}' | '\u{1F00}'..='\u{1FFF}' => Script::Grek,
+            '\u{400}'..='\u{52F}' => Script::Cyrl,
+            '\u{530}'..='\u{58F}' => Script::Armn,
+            '\u{590}'..='\u{5FF}' => Script::Hebr,
+            '\u{600}'..='\u{6FF}'
+            | '\u{750}'..='\u{77F}'
+            | '\u{FB50}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}' => Script::Arab,
+            '\u{900}'..='\u{97F}' => Script::Deva,
+            '\u{980}'..='\u{9FF}' => Script::Beng,
+            '\u{A00}'..='\u{A7F}' => Script::Guru,
+            '\u{A80}'..='\u{AFF}' => Script::Gujr,
+            '\u{B80}'..='\u{BFF}' => Script::Taml,
+            '\u{C00}'..='\u{C7F}' => Script::Telu,
+            '\u{C80}'..='\u{CFF}' => Script::Knda,
+            '\u{D00}'..='\u{D7F}' => Script::Mlym,
+            '\u{D80}'..='\u{DFF}' => Script::Sinh,
+            '\u{E00}'..='\u{E7F}' => Script::Thai,
+            '\u{1000}'..='\u{109F}' => Script::Mymr,
+            '\u{10A0}'..='\u{10FF}' => Script::Geor,
+            '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}' => {
+                Script::Hang
+            }
+            '\u{1200}'..='\u{139F}' => Script::Ethi,
+            '\u{1780}'..='\u{17FF}' => Script::Khmr,
+            '\u{3040}'..='\u{30FF}' => Script::Jpan,
+            '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' => {
+                Script::Hani
+            }
+            _ => return None,
+        };
+        // Vowel signs and viramas are part of words, digits are not
+        (!c.is_numeric()).then_some(script)
+    }
+
+    /// The ISO 15924 code, as in the script part of FLORES-200 codes.
+    pub fn code(self) -> &'static str {
+        match self {
+            Script::Latn => "Latn",
+            Script::Cyrl => "Cyrl",
+            Script::Grek => "Grek",
+            Script::Armn => "Armn",
+            Script::Hebr => "Hebr",
+            Script::Arab => "Arab",
+            Script::Deva => "Deva",
+            Script::Beng => "Beng",
+            Script::Guru => "Guru",
+            Script::Gujr => "Gujr",
+            Script::Taml => "Taml",
+            Script::Telu => "Telu",
+            Script::Knda => "Knda",
+            Script::Mlym => "Mlym",
+            Script::Sinh => "Sinh",
+            Script::Thai => "Thai",
+            Script::Mymr => "Mymr",
+            Script::Geor => "Geor",
+            Script::Hang => "Hang",
+            Script::Ethi => "Ethi",
+            Script::Khmr => "Khmr",
+            Script::Jpan => "Jpan",
+            Script::Hani => "Hani",
+        }
+    }
+
+    /// The one language written in this script, among those the models know.
+    fn language(self) -> Option<&'static str> {
+        Some(match self {
+            Script::Grek => "ell_Grek",
+            Script::Armn => "hye_Armn",
+            Script::Hebr => "heb_Hebr",
+            Script::Beng => "ben_Beng",
+            Script::Guru => "pan_Guru",
+            Script::Gujr => "guj_Gujr",
+            Script::Taml => "tam_Taml",
+            Script::Telu => "tel_Telu",
+            Script::Knda => "kan_Knda",
+            Script::Mlym => "mal_Mlym",
+            Script::Sinh => "sin_Sinh",
+            Script::Thai => "tha_Thai",
+            Script::Mymr => "mya_Mymr",
+            Script::Geor => "kat_Geor",
+            Script::Hang => "kor_Hang",
+            Script::Ethi => "amh_Ethi",
+            Script::Khmr => "khm_Khmr",
+            Script::Jpan => "jpn_Jpan",
+            _ => return None,
+        })
+    }
+}
+
+/// A language a text may be written in.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Candidate {
+    pub language: String,
+    /// Between 0 and 1
+    pub confidence: f64,
+}
+
+/// The outcome of [`detect`].
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct Detection {
+    /// The most likely language, `None` for texts without letters
+    pub language: Option<String>,
+    /// Between 0 and 1
+    pub confidence: f64,
+    /// The script most letters are written in
+    pub script: Option<Script>,
+    /// The most likely languages, best first
+    pub candidates: Vec<Candidate>,
+}
+
+impl Detection {
+    /// The detection with its FLORES-200 codes replaced by those of a `model_type`
+    /// model, dropping the languages it has no code for.
+    pub fn for_model(mut self, model_type: &str) -> Self {
+        self.candidates = self
+            .candidates
+            .into_iter()
+            .filter_map(|candidate| {
+                Some(Candidate {
+                    language: lang::to_model_code(&candidate.language, model_type)?,
+                    confidence: candidate.confidence,
+                })
+            })
+            .collect();
+        let best = self.candidates.first();
+        self.language = best.map(|c| c.language.clone());
+        self.confidence = best.map_or(0.0, |c| c.confidence);
+        self
+    }
+}
+
+/// Detects the language `text` is written in.
+pub fn detect(text: &str) -> Detection {
+    let mut scripts: HashMap<Script, usize> = HashMap::new();
+    for script in text.chars().filter_map(Script::of) {
+        *scripts.entry(script).or_default() += 1;
+    }
+    // Japanese and Korean are written with Han characters too
+    if let Some(&han) = scripts.get(&Script::Hani)
+        && let Some(script) = [Script::Jpan, Script::Hang]
+            .into_iter()
+            .find(|script| scripts.contains_key(script))
+    {
+        scripts.remove(&Script::Hani);
+        *scripts.entry(script).or_default() += han;
+    }
+    let letters: usize = scripts.values().sum();
+    let Some((script, count)) = scripts
+        .into_iter()
+        .max_by_key(|&(script, count)| (count, std::cmp::Reverse(script.code())))
+    else {
+        return Detection::default();
+    };
+
+    let mut candidates = match script.language() {
+        Some(language) => vec![(language.to_string(), 1.0)],
+        None if script == Script::Hani => chinese(text),
+        None => model().classify(text, script),
+    };
+    candidates.truncate(MAX_CANDIDATES);
+    // Letters in other scripts make the detection less certain
+    let share = count as f64 / letters as f64;
+    let candidates: Vec<Candidate> = candidates
+        .into_iter()
+        .map(|(language, confidence)| Candidate {
+            language,
+            confidence: confidence * share,
+        })
+        .collect();
+    Detection {
+        language: candidates.first().map(|c| c.language.clone()),
+        confidence: candidates.first().map_or(0.0, |c| c.confidence),
+        script: Some(script),
+        candidates,
+    }
+}
+
+/// Simplified and Traditional forms of common characters, at the same positions.
+const SIMPLIFIED: &str = "们这个说时为国会来对过还没问开关见长门车东书语话学经发现电头买卖让认识请谢欢爱后从钱动点样种实应该给将无与业产务体万网页码户设选项帮报错误载传输册录马鱼鸟";
+const TRADITIONAL: &str = "們這個說時為國會來對過還沒問開關見長門車東書語話學經發現電頭買賣讓認識請謝歡愛後從錢動點樣種實應該給將無與業產務體萬網頁碼戶設選項幫報錯誤載傳輸冊錄馬魚鳥";
+
+/// Simplified and Traditional Chinese, told apart by characters written differently.
+fn chinese(text: &str) -> Vec<(String, f64)> {
+    let count = |forms: &str| text.chars().filter(|&c| forms.contains(c)).count() as f64;
+    let (simplified, traditional) = (count(SIMPLIFIED), count(TRADITIONAL));
+    // Without telling characters the odds stay even, with Simplified first
+    let hant = (traditional + 0.5) / (simplified + traditional + 1.0);
+    let mut candidates = vec![
+        ("zho_Hans".to_string(), 1.0 - hant),
+        ("zho_Hant".to_string(), hant),
+    ];
+    if hant > 0.5 {
+        candidates.reverse();
+    }
+    candidates
+}
+
+/// The trigrams of the words of `text`, lowercased, with `_` marking word boundaries.
+pub fn trigrams(text: &str) -> Vec<String> {
+    let mut trigrams = Vec::new();
+    let mut word: Vec<char> = vec!['_'];
+    for c in text.chars().chain([' ']) {
+        if Script::of(c).is_some() {
+            word.extend(c.to_lowercase());
+        } else if word.len() > 1 {
+            word.push('_');
+            trigrams.extend(word.windows(3).map(|w| w.iter().collect::<String>()));
+            word.truncate(1);
+        }
+    }
+    trigrams
+}
+
+/// Trigram counts of one language.
+struct Profile {
+    language: String,
+    counts: HashMap<String, u32>,
+    total: f64,
+}
+
+/// The language profiles of `data/langid.txt`.
+struct Model {
+    profiles: Vec<Profile>,
+}
+
+fn model() -> &'static Model {
+    static PARSED: OnceLock<Model> = OnceLock::new();
+    PARSED.get_or_init(|| Model::parse(MODEL))
+}
+
+impl Model {
+    /// Reads one profile per line: a FLORES-200 code, a tab, then `trigram:count` pairs
+    /// separated by spaces. Lines starting with `#` are comments.
+    fn parse(data: &str) -> Self {
+        let profiles = data
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (language, counts) = line.split_once('\t')?;
+                let counts: HashMap<String, u32> = counts
+                    .split(' ')
+                    .filter_map(|pair| {
+                        let (trigram, count) = pair.rsplit_once(':')?;
+                        Some((trigram.to_string(), count.parse().ok()?))
+                    })
+                    .collect();
+                let total = counts.values().sum::<u32>() as f64;
+                Some(Profile {
+                    language: language.to_string(),
+                    counts,
+                    total,
+                })
+            })
+            .collect();
+        Self { profiles }
+    }
+
+    /// The languages written in `script` by their probability of having produced
+    /// `text`, best first.
+    fn classify(&self, text: &str, script: Script) -> Vec<(String, f64)> {
+        let trigrams = trigrams(text);
+        let mut scores: Vec<(String, f64)> = self
+            .profiles
+            .iter()
+            .filter(|profile| profile.language.ends_with(script.code()))
+            .map(|profile| {
+                let denominator = (profile.total + UNSEEN * VOCABULARY).ln();
+                let score = trigrams
+                    .iter()
+                    .map(|trigram| {
+                        let count = profile.counts.get(trigram).copied().unwrap_or_default();
+                        (count as f64 + UNSEEN).ln() - denominator
+                    })
+                    .sum::<f64>();
+                (profile.language.clone(), score)
+            })
+            .collect();
+
+        // Log-likelihoods to probabilities
+        let best = scores
+            .iter()
+            .map(|(_, score)| *score)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let sum: f64 = scores.iter().map(|(_, score)| (score - best).exp()).sum();
+        for (_, score) in &mut scores {
+            *score = (*score - best).exp() / sum;
+        }
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scores
+    }
+}
@@ -0,0 +1,114 @@
+use ct2rs::Device;
+use std::fmt;
+use std::str::FromStr;
+
+/// A `device` setting: `cpu`, `cuda` (alias `gpu`), `cuda:0,1` with its own device
+/// indices, or `auto` for CUDA when a GPU is present and CPU otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceSpec {
+    Cpu,
+    /// CUDA on the listed devices, or on `device_indices` when `None`
+    Cuda(Option<Vec<i32>>),
+    Auto,
+}
+
+impl FromStr for DeviceSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        let (name, indices) = match lower.split_once(':') {
+            Some((name, indices)) => (name, Some(indices)),
+            None => (lower.as_str(), None),
+        };
+        match (name, indices) {
+            ("cpu", None) => Ok(DeviceSpec::Cpu),
+            ("auto", None) => Ok(DeviceSpec::Auto),
+            ("cuda" | "gpu", None) => Ok(DeviceSpec::Cuda(None)),
+            ("cuda" | "gpu", Some(indices)) => indices
+                .split(',')
+                .map(|i| i.trim().parse::<u16>().map(i32::from))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|indices| DeviceSpec::Cuda(Some(indices)))
+                .map_err(|_| {
+                    format!(
+                        "invalid device indices '{}', expected e.g. cuda:0,1",
+                        indices
+                    )
+                }),
+            ("cpu" | "auto", Some(_)) => Err(format!("device '{}' takes no indices", name)),
+            _ => Err(format!("unknown device '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for DeviceSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceSpec::Cpu => write!(f, "cpu"),
+            DeviceSpec::Auto => write!(f, "auto"),
+            DeviceSpec::Cuda(None) => write!(f, "cuda"),
+            DeviceSpec::Cuda(Some(indices)) => {
+                let indices: Vec<String> = indices.iter().map(i32::to_string).collect();
+                write!(f, "cuda:{}", indices.join(","))
+            }
+        }
+    }
+}
+
+impl DeviceSpec {
+    /// Picks the device and device indices to load a model on, given the number of
+    /// CUDA devices present, or `None` if this build has no CUDA support.
+    ///
+    /// `auto` falls back to CPU whenever no usable GPU is found; an explicit CUDA
+    /// device only does so if `allow_cpu_fallback` is set and fails otherwise.
+    pub fn resolve(
+        &self,
+        device_indices: &[i32],
+        allow_cpu_fallback: bool,
+        cuda_devices: Option<i32>,
+    ) -> Result<(Device, Vec<i32>), String> {
+        let indices = match self {
+            DeviceSpec::Cpu => return Ok((Device::CPU, device_indices.to_vec())),
+            DeviceSpec::Cuda(Some(indices)) => indices.clone(),
+            DeviceSpec::Cuda(None) | DeviceSpec::Auto => device_indices.to_vec(),
+        };
+
+        let problem = match cuda_devices {
+            None => Some(format!("CUDA support is not compiled in {}", CUDA_HINT)),
+            Some(n) if n <= 0 => Some("no CUDA device found".to_string()),
+            Some(cuda_devices) => indices
+                .iter()
+                .find(|&&i| i >= cuda_devices)
+                .map(|i| format!("CUDA device {} not found ({} available)", i, cuda_devices)),
+        };
+        match problem {
+            None => Ok((Device::CUDA, indices)),
+            Some(problem) if allow_cpu_fallback || *self == DeviceSpec::Auto => {
+                tracing::warn!("{}, falling back to CPU", problem);
+                Ok((Device::CPU, vec![0]))
+            }
+            Some(problem) => Err(format!(
+                "{} (set allow_cpu_fallback = true to run on CPU instead)",
+                problem
+            )),
+        }
+    }
+}
+
+/// How to get a build that can run on CUDA.
+pub const CUDA_HINT: &str = "(rebuild with `--features cuda`)";
+
+/// Number of CUDA devices CTranslate2 can use on this host, or `None` if this build
+/// has no CUDA support.
+#[cfg(feature = "cuda")]
+pub fn cuda_device_count() -> Option<i32> {
+    Some(ct2rs::sys::get_device_count(Device::CUDA))
+}
+
+/// Number of CUDA devices CTranslate2 can use on this host, or `None` if this build
+/// has no CUDA support.
+#[cfg(not(feature = "cuda"))]
+pub fn cuda_device_count() -> Option<i32> {
+    None
+}
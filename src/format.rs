@@ -0,0 +1,256 @@
+//! Documents in formats other than plain text: their text is extracted in segments,
+//! translated in one batch and put back into the document's structure.
+
+pub mod json;
+pub mod markdown;
+pub mod markup;
+pub mod resource;
+pub mod subtitle;
+
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use std::ops::Range;
+
+use crate::glossary::Term;
+use crate::model::{GenerationParams, ModelError, ModelManager};
+use crate::protect;
+use crate::xml::XmlError;
+use json::JsonDocument;
+use markdown::MarkdownDocument;
+use markup::MarkupDocument;
+use resource::ResourceDocument;
+use subtitle::SubtitleDocument;
+
+/// How the input of a translation request is structured.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// One text, translated as a whole
+    #[default]
+    Text,
+    Html,
+    Xml,
+    Markdown,
+}
+
+#[derive(Debug, Snafu)]
+pub enum FormatError {
+    #[snafu(display("Invalid {} document: {}", format, source))]
+    Markup {
+        format: &'static str,
+        source: XmlError,
+    },
+    #[snafu(display("{}", source))]
+    Model { source: ModelError },
+}
+
+/// How the inline markup behind a placeholder is put back after translation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagKind {
+    /// Markup standing on its own, like a line break or a piece of code
+    Atom,
+    Open,
+    /// Closes the `Open` tag at this index
+    Close(usize),
+}
+
+/// Writes the translated segment `translated` to `out`, replacing the placeholders of the
+/// tags `kinds` by their `markup` and passing the text between through `escape`.
+///
+/// Only the first occurrence of each tag is written, and opening and closing tags only
+/// as properly nested pairs, so tags the model dropped or reordered can't break the
+/// document. The markup of the tags left out is added to `lost`.
+fn render_segment(
+    translated: &str,
+    kinds: &[TagKind],
+    markup: impl Fn(usize) -> String,
+    escape: impl Fn(&str) -> String,
+    out: &mut String,
+    lost: &mut Vec<String>,
+) {
+    let found: Vec<(Range<usize>, usize)> = protect::placeholders(translated)
+        .filter(|(_, tag)| *tag < kinds.len())
+        .collect();
+
+    let mut keep = vec![false; found.len()];
+    let mut seen = vec![false; kinds.len()];
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    for (k, (_, tag)) in found.iter().enumerate() {
+        if std::mem::replace(&mut seen[*tag], true) {
+            continue;
+        }
+        match kinds[*tag] {
+            TagKind::Atom => keep[k] = true,
+            TagKind::Open => open.push((*tag, k)),
+            TagKind::Close(start) => {
+                // Tags opened since the matching one were not closed in time
+                if let Some(depth) = open.iter().rposition(|(tag, _)| *tag == start) {
+                    keep[open[depth].1] = true;
+                    keep[k] = true;
+                    open.truncate(depth);
+                }
+            }
+        }
+    }
+
+    let mut written = vec![false; kinds.len()];
+    let mut pos = 0;
+    for ((range, tag), keep) in found.iter().zip(keep) {
+        out.push_str(&escape(&translated[pos..range.start]));
+        if keep {
+            out.push_str(&markup(*tag));
+            written[*tag] = true;
+        }
+        pos = range.end;
+    }
+    out.push_str(&escape(&translated[pos..]));
+
+    for (tag, kind) in kinds.iter().enumerate() {
+        if !written[tag] && !matches!(kind, TagKind::Close(_)) {
+            lost.push(markup(tag));
+        }
+    }
+}
+
+/// A translated document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    pub text: String,
+    /// Glossary terms enforced anywhere in the document
+    pub glossary_terms: Vec<Term>,
+    /// Protected spans and markup the model dropped
+    pub lost_placeholders: Vec<String>,
+}
+
+/// Translates `input`, structured as `format`, with `model`.
+pub async fn translate(
+    manager: &ModelManager,
+    model: &str,
+    format: Format,
+    input: &str,
+    params: GenerationParams,
+) -> Result<Document, FormatError> {
+    match format {
+        Format::Text => {
+            let (mut texts, mut document) =
+                translate_segments(manager, model, vec![input.to_string()], params).await?;
+            document.text = texts.pop().unwrap_or_default();
+            Ok(document)
+        }
+        Format::Html | Format::Xml => {
+            let html = format == Format::Html;
+            let config = manager.config();
+            let markup = MarkupDocument::parse(input, html, &config.markup.translatable_attributes)
+                .context(MarkupSnafu {
+                    format: if html { "HTML" } else { "XML" },
+                })?;
+            translate_structured(manager, model, markup.prompts(), params, |texts| {
+                markup.render(texts)
+            })
+            .await
+        }
+        Format::Markdown => {
+            let markdown = MarkdownDocument::parse(input);
+            translate_structured(manager, model, markdown.prompts(), params, |texts| {
+                markdown.render(texts)
+            })
+            .await
+        }
+    }
+}
+
+/// Translates the cue texts of `subtitles` with `model`, wrapping them to `max_width`
+/// characters if given.
+pub async fn translate_subtitles(
+    manager: &ModelManager,
+    model: &str,
+    subtitles: &SubtitleDocument,
+    params: GenerationParams,
+    max_width: Option<usize>,
+) -> Result<Document, FormatError> {
+    translate_structured(manager, model, subtitles.prompts(), params, |texts| {
+        subtitles.render(texts, max_width)
+    })
+    .await
+}
+
+/// Translates the entries of the localization resource `resource` that need it with
+/// `model`.
+pub async fn translate_resource(
+    manager: &ModelManager,
+    model: &str,
+    resource: &ResourceDocument,
+    params: GenerationParams,
+) -> Result<Document, FormatError> {
+    translate_structured(manager, model, &resource.prompts(), params, |texts| {
+        resource.render(texts)
+    })
+    .await
+}
+
+/// Translates the selected strings of `json` with `model`, in one batch. The document's
+/// text is left empty; the translated value is returned along with it.
+pub async fn translate_json(
+    manager: &ModelManager,
+    model: &str,
+    json: &JsonDocument,
+    params: GenerationParams,
+) -> Result<(serde_json::Value, Document), FormatError> {
+    let (texts, document) =
+        translate_segments(manager, model, json.prompts().to_vec(), params).await?;
+    Ok((json.render(&texts), document))
+}
+
+/// Translates the `prompts` of a structured document and rebuilds it with `render`,
+/// which also returns the markup the translations lost.
+async fn translate_structured(
+    manager: &ModelManager,
+    model: &str,
+    prompts: &[String],
+    params: GenerationParams,
+    render: impl FnOnce(&[String]) -> (String, Vec<String>),
+) -> Result<Document, FormatError> {
+    let (texts, mut document) =
+        translate_segments(manager, model, prompts.to_vec(), params).await?;
+    let (text, lost) = render(&texts);
+    if !lost.is_empty() {
+        tracing::warn!("Model dropped markup from the document: {}", lost.join(" "));
+    }
+    document.text = text;
+    document.lost_placeholders.extend(lost);
+    Ok(document)
+}
+
+/// Translates `segments` in one batch, returning their translations and a document
+/// collecting what they report.
+async fn translate_segments(
+    manager: &ModelManager,
+    model: &str,
+    segments: Vec<String>,
+    params: GenerationParams,
+) -> Result<(Vec<String>, Document), FormatError> {
+    let mut document = Document::default();
+    if segments.is_empty() {
+        return Ok((Vec::new(), document));
+    }
+
+    let translations = manager
+        .generate_detailed(model, segments, params)
+        .await
+        .context(ModelSnafu)?;
+    let texts = translations
+        .into_iter()
+        .map(|translation| {
+            for term in translation.glossary_terms {
+                if !document.glossary_terms.contains(&term) {
+                    document.glossary_terms.push(term);
+                }
+            }
+            document
+                .lost_placeholders
+                .extend(translation.lost_placeholders);
+            translation.text
+        })
+        .collect();
+    Ok((texts, document))
+}
@@ -0,0 +1,222 @@
+//! Structured JSON: the string leaves picked by JSONPath-style selectors are translated
+//! and put back into the same structure.
+//!
+//! Selectors support `$`, `.key`, `['key']`, `[0]`, `[*]`, `.*` and recursive descent with
+//! `..key` or `..*`. A selector matching an object or array picks every string below it.
+
+use serde_json::Value;
+use snafu::prelude::*;
+use std::str::FromStr;
+
+#[derive(Debug, Snafu, PartialEq)]
+#[snafu(display("Invalid selector '{}': {}", selector, message))]
+pub struct SelectorError {
+    pub selector: String,
+    pub message: String,
+}
+
+/// A key or index on the way from the root to a value.
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Matcher {
+    Key(String),
+    Index(usize),
+    Any,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    /// Whether the step may skip any number of levels first, as in `..key`
+    descendant: bool,
+    matcher: Matcher,
+}
+
+/// A parsed JSONPath-style selector, e.g. `$.products[*].name` or `$..description`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    steps: Vec<Step>,
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        let error = |message: &str| SelectorError {
+            selector: selector.to_string(),
+            message: message.to_string(),
+        };
+        let mut rest = selector
+            .trim()
+            .strip_prefix('$')
+            .ok_or_else(|| error("must start with '$'"))?;
+        let mut steps = Vec::new();
+        while !rest.is_empty() {
+            let descendant = rest.starts_with("..");
+            if descendant {
+                rest = &rest[2..];
+            } else if let Some(after) = rest.strip_prefix('.') {
+                rest = after;
+            } else if !rest.starts_with('[') {
+                return Err(error("expected '.' or '['"));
+            }
+
+            let matcher = if let Some(inner) = rest.strip_prefix('[') {
+                let end = bracket_end(inner).ok_or_else(|| error("unterminated '['"))?;
+                let content = inner[..end].trim();
+                rest = &inner[end + 1..];
+                match content.chars().next() {
+                    Some('*') if content == "*" => Matcher::Any,
+                    Some(quote @ ('\'' | '"')) => Matcher::Key(
+                        content
+                            .strip_prefix(quote)
+                            .and_then(|key| key.strip_suffix(quote))
+                            .ok_or_else(|| error("unterminated quoted key"))?
+                            .to_string(),
+                    ),
+                    _ => Matcher::Index(content.parse().map_err(|_| {
+                        error("expected an index, '*' or a quoted key in brackets")
+                    })?),
+                }
+            } else {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                let name = &rest[..end];
+                rest = &rest[end..];
+                match name {
+                    "" => return Err(error("empty key")),
+                    "*" => Matcher::Any,
+                    name => Matcher::Key(name.to_string()),
+                }
+            };
+            steps.push(Step {
+                descendant,
+                matcher,
+            });
+        }
+        Ok(Self { steps })
+    }
+}
+
+/// Index of the `]` closing a bracket whose content starts `inner`, skipping quoted keys.
+fn bracket_end(inner: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in inner.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ']') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+impl Selector {
+    /// Whether the selector picks the value at `path` or one of its parents.
+    fn matches(&self, path: &[Segment]) -> bool {
+        matches_steps(&self.steps, path)
+    }
+}
+
+fn matches_steps(steps: &[Step], path: &[Segment]) -> bool {
+    let Some((step, rest)) = steps.split_first() else {
+        return true;
+    };
+    let matches_segment = |segment: &Segment| match (&step.matcher, segment) {
+        (Matcher::Any, _) => true,
+        (Matcher::Key(key), Segment::Key(name)) => key == name,
+        (Matcher::Index(index), Segment::Index(i)) => index == i,
+        _ => false,
+    };
+    let skips = if step.descendant { path.len() } else { 1 };
+    (0..skips.min(path.len()))
+        .any(|skip| matches_segment(&path[skip]) && matches_steps(rest, &path[skip + 1..]))
+}
+
+/// A JSON value with the string leaves to translate picked out, ready to be put back
+/// together with translations of its [`JsonDocument::prompts`].
+pub struct JsonDocument {
+    value: Value,
+    /// JSON pointers of the leaves behind the prompts
+    pointers: Vec<String>,
+    prompts: Vec<String>,
+}
+
+impl JsonDocument {
+    /// Picks the strings of `value` matched by a selector of `include`, or every string if
+    /// there is none, and by none of `exclude`. Strings without words are left alone.
+    pub fn new(value: Value, include: &[Selector], exclude: &[Selector]) -> Self {
+        let mut document = Self {
+            value: Value::Null,
+            pointers: Vec::new(),
+            prompts: Vec::new(),
+        };
+        let selected = |path: &[Segment]| {
+            (include.is_empty() || include.iter().any(|s| s.matches(path)))
+                && !exclude.iter().any(|s| s.matches(path))
+        };
+        document.collect(&value, &mut Vec::new(), &selected);
+        document.value = value;
+        document
+    }
+
+    fn collect(
+        &mut self,
+        value: &Value,
+        path: &mut Vec<Segment>,
+        selected: &impl Fn(&[Segment]) -> bool,
+    ) {
+        match value {
+            Value::String(text) if text.chars().any(char::is_alphabetic) && selected(path) => {
+                self.pointers.push(pointer(path));
+                self.prompts.push(text.clone());
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    path.push(Segment::Index(i));
+                    self.collect(item, path, selected);
+                    path.pop();
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map {
+                    path.push(Segment::Key(key.clone()));
+                    self.collect(item, path, selected);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The selected strings, in document order.
+    pub fn prompts(&self) -> &[String] {
+        &self.prompts
+    }
+
+    /// The value with the selected strings replaced by their `translations`.
+    pub fn render(&self, translations: &[String]) -> Value {
+        let mut value = self.value.clone();
+        for (pointer, translation) in self.pointers.iter().zip(translations) {
+            if let Some(leaf) = value.pointer_mut(pointer) {
+                *leaf = Value::String(translation.clone());
+            }
+        }
+        value
+    }
+}
+
+/// The JSON pointer (RFC 6901) of `path`.
+fn pointer(path: &[Segment]) -> String {
+    path.iter()
+        .map(|segment| match segment {
+            Segment::Key(key) => format!("/{}", key.replace('~', "~0").replace('/', "~1")),
+            Segment::Index(i) => format!("/{}", i),
+        })
+        .collect()
+}
@@ -0,0 +1,683 @@
+//! Markdown documents: prose is translated while code, URLs and the document's structure
+//! are kept.
+//!
+//! Blocks are recognized line by line. Paragraphs, headings, list items, quotes, table
+//! cells and front-matter values are segments, in which inline code, link targets,
+//! emphasis markers and inline HTML become placeholders. Multi-line paragraphs are
+//! translated as a whole and written back on one line.
+
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use super::TagKind;
+use crate::protect;
+
+/// Indentation, block quote markers and a list item marker with its task box.
+static CONTAINER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[ \t]*(?:>[ \t]?)*[ \t]*((?:[-*+]|\d{1,9}[.)])(?:[ \t]+(?:\[[ xX]\][ \t]+)?|$))?")
+        .expect("valid container regex")
+});
+static THEMATIC_BREAK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:(?:-[ \t]*){3,}|(?:\*[ \t]*){3,}|(?:_[ \t]*){3,})$")
+        .expect("valid thematic break regex")
+});
+static SETEXT_UNDERLINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:=+|-+)[ \t]*$").expect("valid setext regex"));
+static ATX_HEADING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(#{1,6}(?:[ \t]+|$))(.*?)((?:[ \t]+#+)?[ \t]*)$").expect("valid heading regex")
+});
+static FENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:`{3,}|~{3,})").expect("valid fence regex"));
+static HTML_BLOCK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^<(?:[A-Za-z][A-Za-z0-9-]*[\s/>]|[A-Za-z][A-Za-z0-9-]*$|/[A-Za-z]|!--)")
+        .expect("valid HTML block regex")
+});
+static REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[[^\]]+\]:").expect("valid reference regex"));
+static TABLE_DELIMITER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\|?[ \t]*:?-+:?[ \t]*(?:\|[ \t]*:?-+:?[ \t]*)*\|?[ \t]*$")
+        .expect("valid table delimiter regex")
+});
+/// A `key: value` or `key = value` line of YAML or TOML front matter.
+static FRONT_MATTER_VALUE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([ \t]*[A-Za-z0-9_-]+[ \t]*[:=][ \t]*)(.*?)([ \t]*)$")
+        .expect("valid front matter regex")
+});
+static AUTOLINK_OR_TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^<(?:[A-Za-z][A-Za-z0-9+.-]*:[^\s<>]*|[^\s<>@]+@[^\s<>]+|/?[A-Za-z][A-Za-z0-9-]*(?:\s[^<>]*)?/?|!--.*?--)>")
+        .expect("valid inline HTML regex")
+});
+static URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:https?|ftp)://[^\s<>]*[^\s<>.,;:!?)\]'\x22]").expect("valid URL regex")
+});
+
+/// How the translation of a segment is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quoting {
+    Markdown,
+    /// A plain YAML scalar, quoted if the translation needs it
+    Plain,
+    Double,
+    Single,
+}
+
+impl Quoting {
+    fn escape(self, text: &str) -> String {
+        match self {
+            Quoting::Markdown => text.to_string(),
+            Quoting::Double => text.replace('\\', "\\\\").replace('"', "\\\""),
+            Quoting::Single => text.replace('\'', "''"),
+            Quoting::Plain => {
+                let special = text.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c));
+                if special || text.contains(": ") || text.contains(" #") {
+                    format!("\"{}\"", Quoting::Double.escape(text))
+                } else {
+                    text.to_string()
+                }
+            }
+        }
+    }
+}
+
+enum Piece {
+    Raw(String),
+    Segment(usize),
+}
+
+struct Segment {
+    tags: Vec<(TagKind, String)>,
+    quoting: Quoting,
+}
+
+/// Inline content while it is parsed; emphasis delimiters are paired at the end.
+enum Item {
+    Text(String),
+    Atom(String),
+    Open(String),
+    /// Closes the `Open` item at this index
+    Close(String, usize),
+    Delimiter {
+        run: String,
+        can_open: bool,
+        can_close: bool,
+    },
+}
+
+/// A parsed Markdown document, ready to be put back together with translations of its
+/// [`MarkdownDocument::prompts`].
+#[derive(Default)]
+pub struct MarkdownDocument {
+    pieces: Vec<Piece>,
+    segments: Vec<Segment>,
+    prompts: Vec<String>,
+}
+
+/// A line split into its container prefix, content and line ending.
+struct Line<'a> {
+    prefix: &'a str,
+    content: &'a str,
+    ending: &'a str,
+    /// Whether the prefix holds a list item marker
+    item: bool,
+}
+
+impl<'a> Line<'a> {
+    fn new(line: &'a str) -> Self {
+        let body = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[body.len()..];
+        let caps = CONTAINER
+            .captures(body)
+            .expect("container regex matches any line");
+        let prefix_len = caps.get(0).map_or(0, |m| m.len());
+        Self {
+            prefix: &body[..prefix_len],
+            content: &body[prefix_len..],
+            ending,
+            item: caps.get(1).is_some(),
+        }
+    }
+
+    fn body(&self) -> String {
+        format!("{}{}", self.prefix, self.content)
+    }
+}
+
+impl MarkdownDocument {
+    pub fn parse(input: &str) -> Self {
+        let mut document = Self::default();
+        let lines: Vec<&str> = input.split_inclusive('\n').collect();
+        let mut i = document.front_matter(&lines);
+
+        let mut fence: Option<(char, usize)> = None;
+        let mut paragraph: Vec<Line> = Vec::new();
+        let (mut blank, mut code, mut html, mut table, mut list) =
+            (true, false, false, false, false);
+        while i < lines.len() {
+            let raw = lines[i];
+            let line = Line::new(raw);
+            i += 1;
+
+            if let Some((marker, len)) = fence {
+                let closing = line.content.trim();
+                if closing.len() >= len && closing.chars().all(|c| c == marker) {
+                    fence = None;
+                }
+                document.raw(raw);
+                continue;
+            }
+            if line.content.trim().is_empty() {
+                document.paragraph(&std::mem::take(&mut paragraph));
+                document.raw(raw);
+                (blank, html, table) = (true, false, false);
+                continue;
+            }
+
+            let indented = raw.starts_with("    ") || raw.starts_with('\t');
+            if indented && (code || (blank && paragraph.is_empty() && !list)) {
+                code = true;
+                document.raw(raw);
+                continue;
+            }
+            code = false;
+            let was_blank = std::mem::replace(&mut blank, false);
+            if !line.item && !raw.starts_with([' ', '\t']) && was_blank {
+                list = false;
+            }
+            list |= line.item;
+
+            if html || (table && line.content.contains('|')) {
+                if table {
+                    document.table_row(&line);
+                } else {
+                    document.raw(raw);
+                }
+                continue;
+            }
+            table = false;
+
+            let content = line.content.trim_start();
+            if let Some(fence_marker) = FENCE.find(content) {
+                document.paragraph(&std::mem::take(&mut paragraph));
+                let marker = fence_marker.as_str();
+                fence = Some((marker.chars().next().unwrap_or('`'), marker.len()));
+                document.raw(raw);
+            } else if THEMATIC_BREAK.is_match(raw.trim())
+                || THEMATIC_BREAK.is_match(content.trim_end())
+                || (!paragraph.is_empty() && SETEXT_UNDERLINE.is_match(content))
+            {
+                document.paragraph(&std::mem::take(&mut paragraph));
+                document.raw(raw);
+            } else if let Some(caps) = ATX_HEADING.captures(content) {
+                document.paragraph(&std::mem::take(&mut paragraph));
+                let mut inline = Inline::default();
+                inline.parse(&caps[2]);
+                if inline.has_prose() {
+                    let indent = &line.content[..line.content.len() - content.len()];
+                    document.raw(&format!("{}{}{}", line.prefix, indent, &caps[1]));
+                    document.segment(inline, Quoting::Markdown);
+                    document.raw(&format!("{}{}", &caps[3], line.ending));
+                } else {
+                    document.raw(raw);
+                }
+            } else if paragraph.is_empty()
+                && (HTML_BLOCK.is_match(content) || REFERENCE.is_match(content))
+            {
+                html = HTML_BLOCK.is_match(content);
+                document.raw(raw);
+            } else if content.contains('|')
+                && lines
+                    .get(i)
+                    .is_some_and(|next| TABLE_DELIMITER.is_match(Line::new(next).content.trim()))
+            {
+                document.paragraph(&std::mem::take(&mut paragraph));
+                document.table_row(&line);
+                document.raw(lines[i]);
+                i += 1;
+                table = true;
+            } else {
+                if line.item {
+                    document.paragraph(&std::mem::take(&mut paragraph));
+                }
+                paragraph.push(line);
+            }
+        }
+        document.paragraph(&paragraph);
+        document
+    }
+
+    /// The texts to translate, with inline markup as placeholders.
+    pub fn prompts(&self) -> &[String] {
+        &self.prompts
+    }
+
+    /// Rebuilds the document from the translations of its prompts, returning it along
+    /// with the inline markup the translations lost.
+    pub fn render(&self, translations: &[String]) -> (String, Vec<String>) {
+        let mut out = String::new();
+        let mut lost = Vec::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Raw(raw) => out.push_str(raw),
+                Piece::Segment(i) => {
+                    let segment = &self.segments[*i];
+                    let kinds: Vec<TagKind> = segment.tags.iter().map(|(kind, _)| *kind).collect();
+                    super::render_segment(
+                        &translations[*i],
+                        &kinds,
+                        |tag| segment.tags[tag].1.clone(),
+                        |text| segment.quoting.escape(text),
+                        &mut out,
+                        &mut lost,
+                    );
+                }
+            }
+        }
+        (out, lost)
+    }
+
+    fn raw(&mut self, raw: &str) {
+        match self.pieces.last_mut() {
+            Some(Piece::Raw(last)) => last.push_str(raw),
+            _ => self.pieces.push(Piece::Raw(raw.to_string())),
+        }
+    }
+
+    /// Adds a segment, with the whitespace around it kept out of the prompt.
+    fn segment(&mut self, inline: Inline, quoting: Quoting) {
+        let (prompt, tags) = inline.finish();
+        let core = prompt.trim();
+        self.raw(&prompt[..prompt.len() - prompt.trim_start().len()]);
+        self.pieces.push(Piece::Segment(self.segments.len()));
+        self.segments.push(Segment { tags, quoting });
+        self.prompts.push(core.to_string());
+        self.raw(&prompt[prompt.trim_end().len()..]);
+    }
+
+    /// Adds the lines of a paragraph as one segment. Hard line breaks are kept with the
+    /// next line's prefix, soft ones become spaces.
+    fn paragraph(&mut self, lines: &[Line]) {
+        let Some(first) = lines.first() else {
+            return;
+        };
+        let mut inline = Inline::default();
+        for (k, line) in lines.iter().enumerate() {
+            let Some(next) = lines.get(k + 1) else {
+                inline.parse(line.content);
+                break;
+            };
+            let trimmed = line.content.trim_end();
+            let hard_break = if trimmed.ends_with('\\') {
+                Some(trimmed.len() - 1)
+            } else if line.content.ends_with("  ") {
+                Some(trimmed.len())
+            } else {
+                None
+            };
+            match hard_break {
+                Some(at) => {
+                    inline.parse(&line.content[..at]);
+                    let markup = format!("{}{}{}", &line.content[at..], line.ending, next.prefix);
+                    inline.items.push(Item::Atom(markup));
+                }
+                None => {
+                    inline.parse(trimmed);
+                    inline.items.push(Item::Text(" ".to_string()));
+                }
+            }
+        }
+
+        if !inline.has_prose() {
+            for line in lines {
+                self.raw(&format!("{}{}", line.body(), line.ending));
+            }
+            return;
+        }
+        let last = lines.last().unwrap_or(first);
+        self.raw(first.prefix);
+        self.segment(inline, Quoting::Markdown);
+        self.raw(last.ending);
+    }
+
+    /// Adds a table row, each cell a segment of its own.
+    fn table_row(&mut self, line: &Line) {
+        self.raw(line.prefix);
+        let content = line.content;
+        let mut start = 0;
+        let mut in_code = false;
+        let mut escaped = false;
+        let mut cells = Vec::new();
+        for (i, c) in content.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '`' => in_code = !in_code,
+                '|' if !in_code => {
+                    cells.push(start..i);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        cells.push(start..content.len());
+
+        for (k, cell) in cells.into_iter().enumerate() {
+            if k > 0 {
+                self.raw("|");
+            }
+            let mut inline = Inline::default();
+            inline.parse(&content[cell.clone()]);
+            if inline.has_prose() {
+                self.segment(inline, Quoting::Markdown);
+            } else {
+                self.raw(&content[cell]);
+            }
+        }
+        self.raw(line.ending);
+    }
+
+    /// Adds YAML (`---`) or TOML (`+++`) front matter, translating the values made of
+    /// words, and returns the number of lines it takes.
+    fn front_matter(&mut self, lines: &[&str]) -> usize {
+        let Some(delimiter) = lines.first().map(|line| line.trim_end()) else {
+            return 0;
+        };
+        if delimiter != "---" && delimiter != "+++" {
+            return 0;
+        }
+        let Some(end) = lines[1..]
+            .iter()
+            .position(|line| line.trim_end() == delimiter)
+        else {
+            return 0;
+        };
+
+        self.raw(lines[0]);
+        for line in &lines[1..=end] {
+            let body = line.trim_end_matches(['\n', '\r']);
+            let value = FRONT_MATTER_VALUE.captures(body).and_then(|caps| {
+                let (key, value, trail) = (caps.get(1)?, caps.get(2)?, caps.get(3)?);
+                let (quoting, text) = scalar(value.as_str())?;
+                Some((key.as_str(), quoting, text, trail.as_str()))
+            });
+            match value {
+                Some((key, quoting, text, trail))
+                    if text.contains(' ')
+                        && text.chars().any(char::is_alphabetic)
+                        && !URL.is_match(text) =>
+                {
+                    let quote = match quoting {
+                        Quoting::Double => "\"",
+                        Quoting::Single => "'",
+                        _ => "",
+                    };
+                    self.raw(&format!("{}{}", key, quote));
+                    let mut inline = Inline::default();
+                    inline.items.push(Item::Text(text.to_string()));
+                    self.segment(inline, quoting);
+                    self.raw(&format!("{}{}{}", quote, trail, &line[body.len()..]));
+                }
+                _ => self.raw(line),
+            }
+        }
+        self.raw(lines[end + 1]);
+        end + 2
+    }
+}
+
+/// Splits a front-matter value into its quoting and text; `None` for anything but a
+/// single-line string, such as lists, tables or block scalars.
+fn scalar(value: &str) -> Option<(Quoting, &str)> {
+    let quoted = |quote: char| {
+        value
+            .strip_prefix(quote)?
+            .strip_suffix(quote)
+            .filter(|text| !text.contains(quote))
+    };
+    if let Some(text) = quoted('"') {
+        return (!text.contains('\\')).then_some((Quoting::Double, text));
+    }
+    if let Some(text) = quoted('\'') {
+        return Some((Quoting::Single, text));
+    }
+    let structured = value.starts_with(|c: char| "[{|>&*!\"'#".contains(c));
+    (!value.is_empty() && !structured && !value.contains(" #")).then_some((Quoting::Plain, value))
+}
+
+#[derive(Default)]
+struct Inline {
+    items: Vec<Item>,
+}
+
+impl Inline {
+    /// Whether there are words to translate outside the markup.
+    fn has_prose(&self) -> bool {
+        self.items
+            .iter()
+            .any(|item| matches!(item, Item::Text(text) if text.chars().any(char::is_alphabetic)))
+    }
+
+    fn text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        match self.items.last_mut() {
+            Some(Item::Text(last)) => last.push_str(text),
+            _ => self.items.push(Item::Text(text.to_string())),
+        }
+    }
+
+    /// Splits inline Markdown into text and markup.
+    fn parse(&mut self, text: &str) {
+        let mut plain = 0;
+        let mut pos = 0;
+        while pos < text.len() {
+            let rest = &text[pos..];
+            let prev = text[..pos].chars().next_back();
+            let c = rest.chars().next().unwrap_or_default();
+
+            // Length of the markup at `pos`, if any, after adding its items
+            let len = match c {
+                '\\' if rest[1..].starts_with(|c: char| c.is_ascii_punctuation()) => {
+                    self.text(&text[plain..pos]);
+                    self.items.push(Item::Atom(rest[..2].to_string()));
+                    Some(2)
+                }
+                '`' => {
+                    let run = rest.len() - rest.trim_start_matches('`').len();
+                    match code_span(rest, run) {
+                        Some(len) => {
+                            self.text(&text[plain..pos]);
+                            self.items.push(Item::Atom(rest[..len].to_string()));
+                            Some(len)
+                        }
+                        // An unmatched run of backticks is literal as a whole
+                        None => {
+                            pos += run;
+                            continue;
+                        }
+                    }
+                }
+                '<' => AUTOLINK_OR_TAG.find(rest).map(|m| {
+                    self.text(&text[plain..pos]);
+                    self.items.push(Item::Atom(m.as_str().to_string()));
+                    m.len()
+                }),
+                '[' | '!' => link(rest).map(|(label, end)| {
+                    self.text(&text[plain..pos]);
+                    if rest[label.clone()].starts_with('^') || label.end == end - 1 {
+                        // Footnotes and shortcut references must keep their label
+                        self.items.push(Item::Atom(rest[..end].to_string()));
+                    } else {
+                        let open = self.items.len();
+                        self.items.push(Item::Open(rest[..label.start].to_string()));
+                        self.parse(&rest[label.clone()]);
+                        self.items
+                            .push(Item::Close(rest[label.end..end].to_string(), open));
+                    }
+                    end
+                }),
+                '*' | '_' | '~' => {
+                    let run = rest.len() - rest.trim_start_matches(c).len();
+                    let next = rest[run..].chars().next();
+                    let left = next.is_some_and(|n| !n.is_whitespace());
+                    let right = prev.is_some_and(|p| !p.is_whitespace());
+                    let (can_open, can_close) = match c {
+                        // Underscores inside words are literal
+                        '_' => (
+                            left && !(right && prev.is_some_and(char::is_alphanumeric)),
+                            right && !(left && next.is_some_and(char::is_alphanumeric)),
+                        ),
+                        '~' if run != 2 => (false, false),
+                        _ => (left, right),
+                    };
+                    self.text(&text[plain..pos]);
+                    self.items.push(Item::Delimiter {
+                        run: rest[..run].to_string(),
+                        can_open,
+                        can_close,
+                    });
+                    Some(run)
+                }
+                'h' | 'f' if !prev.is_some_and(char::is_alphanumeric) => URL.find(rest).map(|m| {
+                    self.text(&text[plain..pos]);
+                    self.items.push(Item::Atom(m.as_str().to_string()));
+                    m.len()
+                }),
+                _ => None,
+            };
+            match len {
+                Some(len) => {
+                    pos += len;
+                    plain = pos;
+                }
+                None => pos += c.len_utf8(),
+            }
+        }
+        self.text(&text[plain..]);
+    }
+
+    /// Pairs emphasis delimiters and returns the prompt with the tags of its placeholders.
+    fn finish(mut self) -> (String, Vec<(TagKind, String)>) {
+        let mut stack: Vec<usize> = Vec::new();
+        for i in 0..self.items.len() {
+            let Item::Delimiter {
+                run,
+                can_open,
+                can_close,
+            } = &self.items[i]
+            else {
+                continue;
+            };
+            let (run, can_open, can_close) = (run.clone(), *can_open, *can_close);
+            let opener = stack.iter().rposition(
+                |&j| matches!(&self.items[j], Item::Delimiter { run: other, .. } if *other == run),
+            );
+            match opener.filter(|_| can_close) {
+                Some(depth) => {
+                    let j = stack[depth];
+                    stack.truncate(depth);
+                    self.items[j] = Item::Open(run.clone());
+                    self.items[i] = Item::Close(run, j);
+                }
+                None if can_open => stack.push(i),
+                None => {}
+            }
+        }
+
+        let mut prompt = String::new();
+        let mut tags = Vec::new();
+        let mut tag_of = vec![0; self.items.len()];
+        for (i, item) in self.items.into_iter().enumerate() {
+            let (kind, markup) = match item {
+                Item::Text(text) | Item::Delimiter { run: text, .. } => {
+                    // Placeholders already in the text become atoms of their own
+                    let mut pos = 0;
+                    for (range, _) in protect::placeholders(&text) {
+                        prompt.push_str(&text[pos..range.start]);
+                        prompt.push_str(&protect::placeholder(tags.len()));
+                        tags.push((TagKind::Atom, text[range.clone()].to_string()));
+                        pos = range.end;
+                    }
+                    prompt.push_str(&text[pos..]);
+                    continue;
+                }
+                Item::Atom(markup) => (TagKind::Atom, markup),
+                Item::Open(markup) => (TagKind::Open, markup),
+                Item::Close(markup, open) => (TagKind::Close(tag_of[open]), markup),
+            };
+            tag_of[i] = tags.len();
+            prompt.push_str(&protect::placeholder(tags.len()));
+            tags.push((kind, markup));
+        }
+        (prompt, tags)
+    }
+}
+
+/// Length of the code span opened by the `run` backticks `text` starts with.
+fn code_span(text: &str, run: usize) -> Option<usize> {
+    let mut pos = run;
+    while let Some(offset) = text[pos..].find('`') {
+        let start = pos + offset;
+        let len = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if len == run {
+            return Some(start + len);
+        }
+        pos = start + len;
+    }
+    None
+}
+
+/// Finds the link or image `text` starts with, returning the range of its label and the
+/// length of the whole link. Shortcut references end right after their label.
+fn link(text: &str) -> Option<(Range<usize>, usize)> {
+    let start = if text.starts_with("![") {
+        2
+    } else if text.starts_with('[') {
+        1
+    } else {
+        return None;
+    };
+    let label_end = start + closing(&text[start..], '[', ']')?;
+    let after = &text[label_end + 1..];
+    let end = if let Some(destination) = after.strip_prefix('(') {
+        label_end + 2 + closing(destination, '(', ')')? + 1
+    } else if let Some(reference) = after.strip_prefix('[') {
+        label_end + 2 + closing(reference, '[', ']')? + 1
+    } else {
+        label_end + 1
+    };
+    Some((start..label_end, end))
+}
+
+/// Offset of the `close` bracket matching an already opened one, skipping nested
+/// brackets, escapes and code spans.
+fn closing(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut pos = 0;
+    while let Some(c) = text[pos..].chars().next() {
+        match c {
+            '\\' => {
+                pos += 1;
+                pos += text[pos..].chars().next().map_or(0, char::len_utf8);
+                continue;
+            }
+            '`' => {
+                let rest = &text[pos..];
+                let run = rest.len() - rest.trim_start_matches('`').len();
+                pos += code_span(rest, run).unwrap_or(run);
+                continue;
+            }
+            _ if c == close => {
+                if depth == 0 {
+                    return Some(pos);
+                }
+                depth -= 1;
+            }
+            _ if c == open => depth += 1,
+            _ => {}
+        }
+        pos += c.len_utf8();
+    }
+    None
+}
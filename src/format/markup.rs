@@ -0,0 +1,524 @@
+//! HTML and XML documents, translated text segment by text segment.
+//!
+//! A run of text and inline elements forms one segment, in which the inline tags are
+//! replaced by placeholders the model copies through; block elements end a segment.
+//! Translatable attributes are segments of their own. Tags the model drops or reorders
+//! into invalid nesting are left out of the result rather than breaking the document.
+
+use std::ops::Range;
+
+use super::TagKind;
+use crate::protect;
+use crate::xml::{self, Token, XmlError};
+
+/// HTML elements that flow within text rather than starting a block.
+const HTML_INLINE: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "big", "br", "cite", "code", "data", "del", "dfn", "em",
+    "font", "i", "img", "ins", "kbd", "label", "mark", "q", "s", "samp", "small", "span", "strong",
+    "sub", "sup", "time", "tt", "u", "var", "wbr",
+];
+
+/// HTML elements without an end tag.
+const HTML_VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// HTML elements whose content is code rather than prose.
+const HTML_OPAQUE: &[&str] = &["code", "kbd", "pre", "samp", "script", "style", "var"];
+
+/// HTML elements whose content is not markup.
+const HTML_RAW_TEXT: &[&str] = &["script", "style"];
+
+/// Entities resolved for the model; others, like `&nbsp;`, are kept as they are.
+const PREDEFINED_ENTITIES: &[&str] = &["amp", "apos", "gt", "lt", "quot"];
+
+/// An element with its children, or any other token, by index into the tokens.
+enum Node {
+    Leaf(usize),
+    Element {
+        start: usize,
+        /// `None` for void elements and elements closed implicitly
+        end: Option<usize>,
+        children: Vec<Node>,
+    },
+}
+
+/// A part of the output document.
+enum Piece {
+    /// Tokens written as they were
+    Raw(Range<usize>),
+    /// A start tag, with its translatable attributes replaced
+    Start(usize),
+    Segment(usize),
+}
+
+enum Markup {
+    Start(usize),
+    Raw(String),
+}
+
+/// An inline tag, standing in its segment as the placeholder of its index. Atoms are
+/// void elements, comments, entities and untranslated elements.
+struct Tag {
+    kind: TagKind,
+    markup: Markup,
+}
+
+/// A start tag whose attributes are translated, as (attribute, prompt) indices.
+struct StartTag {
+    token: usize,
+    attrs: Vec<(usize, usize)>,
+}
+
+struct Segment {
+    /// Whitespace around the translated text, kept as it was
+    lead: String,
+    trail: String,
+    tags: Vec<Tag>,
+    prompt: usize,
+}
+
+/// A parsed HTML or XML document, ready to be put back together with translations of
+/// its [`MarkupDocument::prompts`].
+pub struct MarkupDocument<'a> {
+    tokens: Vec<Token<'a>>,
+    pieces: Vec<Piece>,
+    segments: Vec<Segment>,
+    starts: Vec<StartTag>,
+    prompts: Vec<String>,
+}
+
+impl<'a> MarkupDocument<'a> {
+    /// Parses `input`, leniently as HTML if `html` is set. The values of `attributes`
+    /// are translated along with the text.
+    pub fn parse(input: &'a str, html: bool, attributes: &[String]) -> Result<Self, XmlError> {
+        let raw_text = if html { HTML_RAW_TEXT } else { &[] };
+        let tokens = xml::tokenize(input, raw_text)?;
+        let tree = build_tree(&tokens, html);
+
+        let mut builder = Builder {
+            tokens: &tokens,
+            html,
+            attributes,
+            pieces: Vec::new(),
+            segments: Vec::new(),
+            starts: Vec::new(),
+            prompts: Vec::new(),
+        };
+        builder.walk(&tree);
+        let Builder {
+            pieces,
+            segments,
+            starts,
+            prompts,
+            ..
+        } = builder;
+
+        Ok(Self {
+            tokens,
+            pieces,
+            segments,
+            starts,
+            prompts,
+        })
+    }
+
+    /// The texts to translate, with inline tags as placeholders.
+    pub fn prompts(&self) -> &[String] {
+        &self.prompts
+    }
+
+    /// Rebuilds the document from the translations of its prompts, returning it along
+    /// with the markup of tags the translations lost.
+    pub fn render(&self, translations: &[String]) -> (String, Vec<String>) {
+        let starts: Vec<String> = self
+            .starts
+            .iter()
+            .map(|start| render_start(&self.tokens[start.token], &start.attrs, translations))
+            .collect();
+
+        let mut out = String::new();
+        let mut lost = Vec::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Raw(range) => {
+                    for token in &self.tokens[range.clone()] {
+                        out.push_str(token.raw());
+                    }
+                }
+                Piece::Start(start) => out.push_str(&starts[*start]),
+                Piece::Segment(segment) => {
+                    let segment = &self.segments[*segment];
+                    out.push_str(&segment.lead);
+                    segment.render(&translations[segment.prompt], &starts, &mut out, &mut lost);
+                    out.push_str(&segment.trail);
+                }
+            }
+        }
+        (out, lost)
+    }
+}
+
+impl Segment {
+    fn render(
+        &self,
+        translated: &str,
+        starts: &[String],
+        out: &mut String,
+        lost: &mut Vec<String>,
+    ) {
+        let kinds: Vec<TagKind> = self.tags.iter().map(|tag| tag.kind).collect();
+        let markup = |tag: usize| match &self.tags[tag].markup {
+            Markup::Start(start) => starts[*start].clone(),
+            Markup::Raw(raw) => raw.clone(),
+        };
+        super::render_segment(translated, &kinds, markup, xml::escape_text, out, lost);
+    }
+}
+
+struct Builder<'t, 'a> {
+    tokens: &'t [Token<'a>],
+    html: bool,
+    attributes: &'t [String],
+    pieces: Vec<Piece>,
+    segments: Vec<Segment>,
+    starts: Vec<StartTag>,
+    prompts: Vec<String>,
+}
+
+impl Builder<'_, '_> {
+    /// Plans the output for `nodes`, siblings in the tree.
+    fn walk(&mut self, nodes: &[Node]) {
+        // Without a list of inline elements, XML elements are inline in mixed content
+        let mixed = !self.html
+            && nodes
+                .iter()
+                .any(|node| matches!(node, Node::Leaf(i) if self.is_text(*i)));
+
+        let mut run: Vec<&Node> = Vec::new();
+        for node in nodes {
+            let inline = match node {
+                Node::Leaf(_) => true,
+                Node::Element { start, .. } if self.html => {
+                    HTML_INLINE.contains(&name(&self.tokens[*start]).to_ascii_lowercase().as_str())
+                }
+                Node::Element { .. } => mixed,
+            };
+            if inline {
+                run.push(node);
+            } else {
+                self.flush(&run);
+                run.clear();
+                self.block(node);
+            }
+        }
+        self.flush(&run);
+    }
+
+    /// Plans a node outside any segment.
+    fn block(&mut self, node: &Node) {
+        match node {
+            Node::Leaf(i) => self.pieces.push(Piece::Raw(*i..*i + 1)),
+            Node::Element { start, .. } if self.is_opaque(*start) => {
+                self.pieces.push(Piece::Raw(*start..last_token(node) + 1));
+            }
+            Node::Element {
+                start,
+                end,
+                children,
+            } => {
+                let start = self.start_tag(*start);
+                self.pieces.push(Piece::Start(start));
+                self.walk(children);
+                if let Some(end) = end {
+                    self.pieces.push(Piece::Raw(*end..*end + 1));
+                }
+            }
+        }
+    }
+
+    /// Plans a run of inline nodes: one segment if it holds any text.
+    fn flush(&mut self, run: &[&Node]) {
+        if !run.iter().any(|node| self.has_text(node)) {
+            for node in run {
+                self.block(node);
+            }
+            return;
+        }
+
+        let mut text = String::new();
+        let mut tags = Vec::new();
+        for node in run {
+            self.inline(node, &mut text, &mut tags);
+        }
+        let core = text.trim();
+        let lead = text[..text.len() - text.trim_start().len()].to_string();
+        let trail = text[text.trim_end().len()..].to_string();
+        let prompt = if self.html {
+            core.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            core.to_string()
+        };
+
+        self.pieces.push(Piece::Segment(self.segments.len()));
+        self.segments.push(Segment {
+            lead,
+            trail,
+            tags,
+            prompt: self.prompts.len(),
+        });
+        self.prompts.push(prompt);
+    }
+
+    /// Adds a node to the segment being built.
+    fn inline(&mut self, node: &Node, text: &mut String, tags: &mut Vec<Tag>) {
+        match node {
+            Node::Leaf(i) => match &self.tokens[*i] {
+                Token::Text(raw) => push_text(raw, text, tags),
+                token => {
+                    push_tag(text, tags, TagKind::Atom, Markup::Raw(token.raw().into()));
+                }
+            },
+            Node::Element { start, .. } if self.is_opaque(*start) => {
+                let raw: String = self.tokens[*start..last_token(node) + 1]
+                    .iter()
+                    .map(Token::raw)
+                    .collect();
+                push_tag(text, tags, TagKind::Atom, Markup::Raw(raw));
+            }
+            Node::Element {
+                start,
+                end,
+                children,
+            } => {
+                let markup = Markup::Start(self.start_tag(*start));
+                let Some(end) = end else {
+                    push_tag(text, tags, TagKind::Atom, markup);
+                    for child in children {
+                        self.inline(child, text, tags);
+                    }
+                    return;
+                };
+                let open = push_tag(text, tags, TagKind::Open, markup);
+                for child in children {
+                    self.inline(child, text, tags);
+                }
+                let raw = self.tokens[*end].raw().to_string();
+                push_tag(text, tags, TagKind::Close(open), Markup::Raw(raw));
+            }
+        }
+    }
+
+    /// Registers a start tag, queueing its translatable attributes as prompts.
+    fn start_tag(&mut self, token: usize) -> usize {
+        let mut attrs = Vec::new();
+        if let Token::Start { attrs: parsed, .. } = &self.tokens[token] {
+            for (i, attr) in parsed.iter().enumerate() {
+                let translatable = self.attributes.iter().any(|name| {
+                    name == attr.name || (self.html && name.eq_ignore_ascii_case(attr.name))
+                });
+                let value = attr.decoded();
+                if translatable && !value.trim().is_empty() {
+                    attrs.push((i, self.prompts.len()));
+                    self.prompts.push(value.trim().to_string());
+                }
+            }
+        }
+        self.starts.push(StartTag { token, attrs });
+        self.starts.len() - 1
+    }
+
+    fn is_text(&self, token: usize) -> bool {
+        matches!(self.tokens[token], Token::Text(raw) if !xml::unescape(raw).trim().is_empty())
+    }
+
+    /// Whether a node holds text that gets translated.
+    fn has_text(&self, node: &Node) -> bool {
+        match node {
+            Node::Leaf(i) => self.is_text(*i),
+            Node::Element {
+                start, children, ..
+            } => !self.is_opaque(*start) && children.iter().any(|child| self.has_text(child)),
+        }
+    }
+
+    /// Whether the element starting at `token` is left untranslated: code in HTML, and
+    /// anything marked `translate="no"`.
+    fn is_opaque(&self, token: usize) -> bool {
+        let token = &self.tokens[token];
+        let Token::Start { attrs, .. } = token else {
+            return false;
+        };
+        (self.html && HTML_OPAQUE.contains(&name(token).to_ascii_lowercase().as_str()))
+            || attrs.iter().any(|attr| {
+                (attr.name == "translate" || attr.name.ends_with(":translate"))
+                    && attr.decoded().eq_ignore_ascii_case("no")
+            })
+    }
+}
+
+/// Adds a placeholder for a tag to `text`, returning the tag's index.
+fn push_tag(text: &mut String, tags: &mut Vec<Tag>, kind: TagKind, markup: Markup) -> usize {
+    text.push_str(&protect::placeholder(tags.len()));
+    tags.push(Tag { kind, markup });
+    tags.len() - 1
+}
+
+/// Adds raw text to a segment, decoded except for entities the model can't know.
+fn push_text(raw: &str, text: &mut String, tags: &mut Vec<Tag>) {
+    let mut start = 0;
+    let mut pos = 0;
+    while let Some(offset) = raw[pos..].find('&') {
+        let at = pos + offset;
+        match entity_len(&raw[at..]) {
+            Some(len) => {
+                push_decoded(&xml::unescape(&raw[start..at]), text, tags);
+                let entity = Markup::Raw(raw[at..at + len].to_string());
+                push_tag(text, tags, TagKind::Atom, entity);
+                start = at + len;
+                pos = start;
+            }
+            None => pos = at + 1,
+        }
+    }
+    push_decoded(&xml::unescape(&raw[start..]), text, tags);
+}
+
+/// Adds decoded text to a segment. Placeholders it already contains become atoms, so
+/// they can't be taken for those of the segment's tags.
+fn push_decoded(decoded: &str, text: &mut String, tags: &mut Vec<Tag>) {
+    let mut pos = 0;
+    for (range, _) in protect::placeholders(decoded) {
+        text.push_str(&decoded[pos..range.start]);
+        let literal = Markup::Raw(decoded[range.clone()].to_string());
+        push_tag(text, tags, TagKind::Atom, literal);
+        pos = range.end;
+    }
+    text.push_str(&decoded[pos..]);
+}
+
+/// Length of the named entity `text` starts with, unless it is a predefined one.
+fn entity_len(text: &str) -> Option<usize> {
+    let name_len = text[1..]
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|&len| len > 0 && text[1 + len..].starts_with(';'))?;
+    let name = &text[1..1 + name_len];
+    (name.starts_with(|c: char| c.is_ascii_alphabetic()) && !PREDEFINED_ENTITIES.contains(&name))
+        .then_some(name_len + 2)
+}
+
+/// Writes a start tag with the values of `attrs` replaced by their translations.
+fn render_start(token: &Token, attrs: &[(usize, usize)], translations: &[String]) -> String {
+    let Token::Start {
+        attrs: parsed, raw, ..
+    } = token
+    else {
+        return token.raw().to_string();
+    };
+
+    let mut out = String::new();
+    let mut pos = 0;
+    for (attr, prompt) in attrs {
+        let Some(value) = parsed[*attr].value else {
+            continue;
+        };
+        // Attribute values are slices of the tag's raw text
+        let start = value.as_ptr() as usize - raw.as_ptr() as usize;
+        let escaped = xml::escape_attr(&translations[*prompt]).replace('\'', "&#39;");
+        out.push_str(&raw[pos..start]);
+        if raw[..start].ends_with(['"', '\'']) {
+            out.push_str(&escaped);
+        } else {
+            out.push('"');
+            out.push_str(&escaped);
+            out.push('"');
+        }
+        pos = start + value.len();
+    }
+    out.push_str(&raw[pos..]);
+    out
+}
+
+fn name<'a>(token: &Token<'a>) -> &'a str {
+    match token {
+        Token::Start { name, .. } | Token::End { name, .. } => name,
+        _ => "",
+    }
+}
+
+/// Index of the last token of `node`.
+fn last_token(node: &Node) -> usize {
+    match node {
+        Node::Leaf(i) => *i,
+        Node::Element { end: Some(end), .. } => *end,
+        Node::Element {
+            start, children, ..
+        } => children.last().map_or(*start, last_token),
+    }
+}
+
+/// Nests `tokens` into elements. End tags close the nearest open element of the same
+/// name, implicitly closing those opened since; stray end tags are kept as leaves.
+fn build_tree(tokens: &[Token], html: bool) -> Vec<Node> {
+    let same_name = |a: &str, b: &str| a == b || (html && a.eq_ignore_ascii_case(b));
+    let mut root = Vec::new();
+    let mut stack: Vec<(usize, Vec<Node>)> = Vec::new();
+
+    fn close(stack: &mut Vec<(usize, Vec<Node>)>, root: &mut Vec<Node>, end: Option<usize>) {
+        if let Some((start, children)) = stack.pop() {
+            let node = Node::Element {
+                start,
+                end,
+                children,
+            };
+            match stack.last_mut() {
+                Some((_, siblings)) => siblings.push(node),
+                None => root.push(node),
+            }
+        }
+    }
+
+    for (i, token) in tokens.iter().enumerate() {
+        let node = match token {
+            Token::Start {
+                name, self_closing, ..
+            } => {
+                let void = html && HTML_VOID.contains(&name.to_ascii_lowercase().as_str());
+                if !self_closing && !void {
+                    stack.push((i, Vec::new()));
+                    continue;
+                }
+                Node::Element {
+                    start: i,
+                    end: None,
+                    children: Vec::new(),
+                }
+            }
+            Token::End { name, .. } => {
+                let open = stack
+                    .iter()
+                    .rposition(|(start, _)| same_name(self::name(&tokens[*start]), name));
+                match open {
+                    Some(depth) => {
+                        while stack.len() > depth + 1 {
+                            close(&mut stack, &mut root, None);
+                        }
+                        close(&mut stack, &mut root, Some(i));
+                        continue;
+                    }
+                    None => Node::Leaf(i),
+                }
+            }
+            _ => Node::Leaf(i),
+        };
+        match stack.last_mut() {
+            Some((_, siblings)) => siblings.push(node),
+            None => root.push(node),
+        }
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut root, None);
+    }
+    root
+}
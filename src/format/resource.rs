@@ -0,0 +1,1270 @@
+//! Localization resources: nested JSON and YAML bundles, gettext PO files and XLIFF 1.2
+//! and 2.0. Only values and targets are translated; keys, comments, plural forms and the
+//! layout of the file are kept, and so are placeholders within the texts.
+//!
+//! ICU plural and select messages are translated branch by branch. PO and XLIFF entries
+//! that already have a translation are kept unless forced. JSON and YAML bundles hold a
+//! single language, so a previous version of the target file can be passed instead, whose
+//! values are reused for the keys it has.
+
+use regex::Regex;
+use serde::Deserialize;
+use snafu::prelude::*;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use super::TagKind;
+use crate::protect::{self, Protector};
+use crate::xml::{self, Token, XmlError};
+
+/// Placeholders and inline markup kept out of the model.
+static PROTECTOR: LazyLock<Protector> = LazyLock::new(|| {
+    let custom = HashMap::from([("markup".to_string(), r"</?[A-Za-z][^<>]*>".to_string())]);
+    let names = ["icu", "printf", "i18next", "markup"].map(String::from);
+    Protector::new(&names, &custom).expect("valid resource placeholder patterns")
+});
+/// The start of an ICU plural or select argument, up to its first branch.
+static ICU_COMPLEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\s*[\w.]+\s*,\s*(plural|selectordinal|select)\s*,")
+        .expect("valid ICU argument regex")
+});
+
+/// XLIFF elements holding native code, kept with their content.
+const XLIFF_CODES: &[&str] = &["bpt", "ept", "it", "ph"];
+/// XLIFF elements whose `<source>` and `<target>` are translated: 1.2 units and 2.0
+/// segments.
+const XLIFF_SEGMENTS: &[&str] = &["trans-unit", "segment"];
+
+#[derive(Debug, Snafu)]
+pub enum ResourceError {
+    #[snafu(display("Invalid JSON file: {}", source))]
+    Json { source: serde_json::Error },
+    #[snafu(display("Invalid {} file, line {}: {}", format, line, message))]
+    Syntax {
+        format: &'static str,
+        line: usize,
+        message: String,
+    },
+    #[snafu(display("Invalid XLIFF file: {}", source))]
+    Xliff { source: XmlError },
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceFormat {
+    Json,
+    Yaml,
+    Po,
+    Xliff,
+}
+
+impl ResourceFormat {
+    /// The format of files with the extension `extension`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(ResourceFormat::Json),
+            "yaml" | "yml" => Some(ResourceFormat::Yaml),
+            "po" | "pot" => Some(ResourceFormat::Po),
+            "xlf" | "xliff" => Some(ResourceFormat::Xliff),
+            _ => None,
+        }
+    }
+
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        match mime {
+            "application/json" => Some(ResourceFormat::Json),
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(ResourceFormat::Yaml)
+            }
+            "text/x-gettext-translation" | "text/x-po" => Some(ResourceFormat::Po),
+            "application/xliff+xml" | "application/x-xliff+xml" => Some(ResourceFormat::Xliff),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ResourceFormat::Json => "application/json",
+            ResourceFormat::Yaml => "application/yaml",
+            ResourceFormat::Po => "text/x-gettext-translation",
+            ResourceFormat::Xliff => "application/xliff+xml",
+        }
+    }
+}
+
+/// A piece of a text: syntax and placeholders kept as they are, or prose to translate.
+enum Part {
+    Raw(String),
+    Text {
+        prompt: String,
+        tags: Vec<(TagKind, String)>,
+    },
+}
+
+enum YamlStyle {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    /// A `|` (literal) or `>` (folded) block scalar with the indentation of its lines
+    Block {
+        indent: String,
+        literal: bool,
+    },
+}
+
+/// How a translation is written into the file.
+enum Style {
+    Json,
+    Yaml(YamlStyle),
+    /// `msgstr`, or as many `msgstr[n]` as the message has plural forms
+    Po {
+        forms: Option<usize>,
+    },
+    /// The content of a `<target>`, with the indentation of the `<source>` if the element
+    /// is new
+    Xliff {
+        insert: Option<String>,
+    },
+}
+
+struct Entry {
+    /// Path of a JSON or YAML value, context and id of a PO message or id of an XLIFF
+    /// unit, by which translations are reused
+    key: String,
+    /// Span the translation replaces; empty where a new XLIFF `<target>` goes
+    target: Range<usize>,
+    /// The texts to translate: the singular and plural of a PO message, otherwise one
+    sources: Vec<Vec<Part>>,
+    /// The translation the file holds, or the value of a JSON or YAML entry
+    translation: Option<Vec<String>>,
+    /// Whether the entry is translated already and kept unless forced
+    translated: bool,
+    /// Translation taken over from a previous version of the target file
+    kept: Option<Vec<String>>,
+    style: Style,
+}
+
+/// A parsed localization resource, ready to be put back together with translations of
+/// its [`ResourceDocument::prompts`].
+pub struct ResourceDocument {
+    format: ResourceFormat,
+    input: String,
+    bom: bool,
+    /// Line ending of the file, for values written over several lines
+    ending: &'static str,
+    entries: Vec<Entry>,
+    force: bool,
+}
+
+impl ResourceDocument {
+    /// Parses `input` as `format`. With `force`, entries that are translated already are
+    /// translated again.
+    pub fn parse(input: &str, format: ResourceFormat, force: bool) -> Result<Self, ResourceError> {
+        let body = input.strip_prefix('\u{feff}').unwrap_or(input);
+        let ending = if body.contains("\r\n") { "\r\n" } else { "\n" };
+        let entries = match format {
+            ResourceFormat::Json => parse_json(body)?,
+            ResourceFormat::Yaml => YamlParser::new(body).parse()?,
+            ResourceFormat::Po => parse_po(body)?,
+            ResourceFormat::Xliff => parse_xliff(body)?,
+        };
+        Ok(Self {
+            format,
+            input: body.to_string(),
+            bom: body.len() < input.len(),
+            ending,
+            entries,
+            force,
+        })
+    }
+
+    pub fn format(&self) -> ResourceFormat {
+        self.format
+    }
+
+    /// Reuses the translations of `previous`, an earlier version of the target file, for
+    /// the entries with the same key that have none.
+    pub fn reuse(&mut self, previous: &ResourceDocument) {
+        let translations: HashMap<&str, &Vec<String>> = previous
+            .entries
+            .iter()
+            .filter_map(|entry| Some((entry.key.as_str(), entry.translation.as_ref()?)))
+            .collect();
+        for entry in &mut self.entries {
+            if !entry.translated {
+                entry.kept = translations.get(entry.key.as_str()).map(|t| (*t).clone());
+            }
+        }
+    }
+
+    /// Number of entries to translate.
+    pub fn pending(&self) -> usize {
+        self.entries.iter().filter(|e| self.is_pending(e)).count()
+    }
+
+    /// Number of entries kept, as translated already or reused.
+    pub fn skipped(&self) -> usize {
+        self.entries.len() - self.pending()
+    }
+
+    /// The texts to translate, with placeholders and inline markup as tags.
+    pub fn prompts(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| self.is_pending(entry))
+            .flat_map(|entry| entry.sources.iter().flatten())
+            .filter_map(|part| match part {
+                Part::Text { prompt, .. } => Some(prompt.clone()),
+                Part::Raw(_) => None,
+            })
+            .collect()
+    }
+
+    /// Rebuilds the file from the translations of its prompts, returning it along with
+    /// the placeholders and markup the translations lost.
+    pub fn render(&self, translations: &[String]) -> (String, Vec<String>) {
+        let mut out = String::new();
+        let mut lost = Vec::new();
+        if self.bom {
+            out.push('\u{feff}');
+        }
+        let mut translations = translations.iter();
+        let mut pos = 0;
+        for entry in &self.entries {
+            out.push_str(&self.input[pos..entry.target.start]);
+            pos = entry.target.end;
+            let values = if self.is_pending(entry) {
+                let escape: fn(&str) -> String = match entry.style {
+                    Style::Xliff { .. } => xml::escape_text,
+                    _ => str::to_string,
+                };
+                entry
+                    .sources
+                    .iter()
+                    .map(|parts| render_parts(parts, &mut translations, escape, &mut lost))
+                    .collect()
+            } else if let Some(kept) = &entry.kept {
+                kept.clone()
+            } else {
+                out.push_str(&self.input[entry.target.clone()]);
+                continue;
+            };
+            out.push_str(&write(&entry.style, &values, self.ending));
+        }
+        out.push_str(&self.input[pos..]);
+        (out, lost)
+    }
+
+    fn is_pending(&self, entry: &Entry) -> bool {
+        self.force || !(entry.translated || entry.kept.is_some())
+    }
+}
+
+/// Writes the translated `parts` of a text, taking the translations of their prose from
+/// `translations`.
+fn render_parts<'t>(
+    parts: &[Part],
+    translations: &mut impl Iterator<Item = &'t String>,
+    escape: fn(&str) -> String,
+    lost: &mut Vec<String>,
+) -> String {
+    let mut out = String::new();
+    for part in parts {
+        match part {
+            Part::Raw(raw) => out.push_str(raw),
+            Part::Text { prompt, tags } => {
+                let translated = translations.next().map_or(prompt.as_str(), String::as_str);
+                let kinds: Vec<TagKind> = tags.iter().map(|(kind, _)| *kind).collect();
+                super::render_segment(
+                    translated,
+                    &kinds,
+                    |tag| tags[tag].1.clone(),
+                    escape,
+                    &mut out,
+                    lost,
+                );
+            }
+        }
+    }
+    out
+}
+
+/// Encodes the translated `values` of an entry as `style` asks.
+fn write(style: &Style, values: &[String], ending: &str) -> String {
+    let value = values.first().map(String::as_str).unwrap_or_default();
+    match style {
+        Style::Json => serde_json::to_string(value).unwrap_or_default(),
+        Style::Yaml(style) => write_yaml(style, value, ending),
+        Style::Po { forms: None } => format!("msgstr {}", po_quote(value)),
+        Style::Po { forms: Some(forms) } => (0..*forms)
+            .map(|n| {
+                // Translations have the singular and one plural, kept ones every form
+                let value = values.get(n).or(values.last()).map_or("", String::as_str);
+                format!("msgstr[{}] {}", n, po_quote(value))
+            })
+            .collect::<Vec<_>>()
+            .join(ending),
+        Style::Xliff { insert: None } => value.to_string(),
+        Style::Xliff {
+            insert: Some(indent),
+        } => format!("{}<target>{}</target>", indent, value),
+    }
+}
+
+/// Splits an ICU message into its prose and the syntax of its plural and select
+/// arguments, whose branches are split in turn. In plural branches `#` is a placeholder.
+fn split_message(text: &str, plural: bool, parts: &mut Vec<Part>) {
+    let mut pos = 0;
+    let mut search = 0;
+    while let Some(caps) = ICU_COMPLEX.captures_at(text, search) {
+        let head = caps.get(0).expect("whole match");
+        let Some((branches, end)) = icu_branches(text, head.end()) else {
+            search = head.start() + 1;
+            continue;
+        };
+        split_text(&text[pos..head.start()], plural, parts);
+        let nested_plural = &caps[1] != "select";
+        let mut syntax = head.start();
+        for branch in branches {
+            push_raw(parts, &text[syntax..branch.start]);
+            split_message(&text[branch.clone()], nested_plural, parts);
+            syntax = branch.end;
+        }
+        push_raw(parts, &text[syntax..end]);
+        pos = end;
+        search = end;
+    }
+    split_text(&text[pos..], plural, parts);
+}
+
+/// The branch texts of the ICU argument whose selectors start at `start`, and the end of
+/// the argument; `None` if it is malformed.
+fn icu_branches(text: &str, start: usize) -> Option<(Vec<Range<usize>>, usize)> {
+    let mut branches = Vec::new();
+    let mut pos = start;
+    loop {
+        pos += text[pos..].len() - text[pos..].trim_start().len();
+        let rest = &text[pos..];
+        if rest.starts_with('}') {
+            return (!branches.is_empty()).then_some((branches, pos + 1));
+        }
+        // A selector such as `one`, `=0` or `other`, or the `offset:1` of plurals
+        let selector = rest.find(|c: char| c.is_whitespace() || c == '{' || c == '}')?;
+        if selector == 0 {
+            return None;
+        }
+        pos += selector;
+        if rest[..selector].starts_with("offset:") {
+            continue;
+        }
+        pos += text[pos..].len() - text[pos..].trim_start().len();
+        if !text[pos..].starts_with('{') {
+            return None;
+        }
+        let mut depth = 0;
+        let end = text[pos..].find(|c: char| {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        })?;
+        branches.push(pos + 1..pos + end);
+        pos += end + 1;
+    }
+}
+
+/// Adds `text` as prose with its placeholders as tags, or as it is if it has no words.
+fn split_text(text: &str, plural: bool, parts: &mut Vec<Part>) {
+    let mut prompt = String::new();
+    let mut tags = Vec::new();
+    if push_prose(text, plural, str::to_string, &mut prompt, &mut tags) {
+        parts.push(Part::Text { prompt, tags });
+    } else {
+        push_raw(parts, text);
+    }
+}
+
+fn push_raw(parts: &mut Vec<Part>, raw: &str) {
+    match parts.last_mut() {
+        Some(Part::Raw(last)) => last.push_str(raw),
+        _ if raw.is_empty() => {}
+        _ => parts.push(Part::Raw(raw.to_string())),
+    }
+}
+
+/// Adds `text` to `prompt`, replacing its placeholders by tags whose markup `escape`
+/// gives. Returns whether it has words to translate.
+fn push_prose(
+    text: &str,
+    plural: bool,
+    escape: fn(&str) -> String,
+    prompt: &mut String,
+    tags: &mut Vec<(TagKind, String)>,
+) -> bool {
+    let mut spans = PROTECTOR.find(text);
+    if plural {
+        let hashes: Vec<Range<usize>> = text
+            .match_indices('#')
+            .map(|(i, _)| i..i + 1)
+            .filter(|hash| !spans.iter().any(|span| span.contains(&hash.start)))
+            .collect();
+        spans.extend(hashes);
+        spans.sort_by_key(|span| span.start);
+    }
+
+    let mut prose = false;
+    let mut pos = 0;
+    for span in spans {
+        let between = &text[pos..span.start];
+        prose |= between.chars().any(char::is_alphabetic);
+        prompt.push_str(between);
+        prompt.push_str(&protect::placeholder(tags.len()));
+        tags.push((TagKind::Atom, escape(&text[span.clone()])));
+        pos = span.end;
+    }
+    let rest = &text[pos..];
+    prose |= rest.chars().any(char::is_alphabetic);
+    prompt.push_str(rest);
+    prose
+}
+
+/// An entry of a JSON or YAML bundle, unless its value has no words to translate.
+fn value_entry(key: String, target: Range<usize>, value: String, style: Style) -> Option<Entry> {
+    let mut parts = Vec::new();
+    split_message(&value, false, &mut parts);
+    parts
+        .iter()
+        .any(|part| matches!(part, Part::Text { .. }))
+        .then(|| Entry {
+            key,
+            target,
+            sources: vec![parts],
+            translation: Some(vec![value]),
+            translated: false,
+            kept: None,
+            style,
+        })
+}
+
+fn parse_json(input: &str) -> Result<Vec<Entry>, ResourceError> {
+    serde_json::from_str::<serde_json::Value>(input).context(JsonSnafu)?;
+    let mut scanner = JsonScanner {
+        input,
+        pos: 0,
+        entries: Vec::new(),
+    };
+    scanner.value(&mut Vec::new());
+    Ok(scanner.entries)
+}
+
+/// Walks a valid JSON document, recording its string leaves.
+struct JsonScanner<'a> {
+    input: &'a str,
+    pos: usize,
+    entries: Vec<Entry>,
+}
+
+impl JsonScanner<'_> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn next_is(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.input.as_bytes().get(self.pos) == Some(&c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn value(&mut self, path: &mut Vec<String>) {
+        self.skip_whitespace();
+        match self.input.as_bytes().get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1;
+                while !self.next_is(b'}') {
+                    self.skip_whitespace();
+                    let key = self.string();
+                    path.push(serde_json::from_str(&self.input[key]).unwrap_or_default());
+                    self.next_is(b':');
+                    self.value(path);
+                    path.pop();
+                    self.next_is(b',');
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut index = 0;
+                while !self.next_is(b']') {
+                    path.push(index.to_string());
+                    self.value(path);
+                    path.pop();
+                    self.next_is(b',');
+                    index += 1;
+                }
+            }
+            Some(b'"') => {
+                let target = self.string();
+                let value = serde_json::from_str(&self.input[target.clone()]).unwrap_or_default();
+                self.entries
+                    .extend(value_entry(path.join("."), target, value, Style::Json));
+            }
+            _ => {
+                let rest = &self.input[self.pos..];
+                self.pos += rest
+                    .find(|c: char| c == ',' || c == ']' || c == '}' || c.is_whitespace())
+                    .unwrap_or(rest.len());
+            }
+        }
+    }
+
+    /// Skips the string at the current position, returning its span with the quotes.
+    fn string(&mut self) -> Range<usize> {
+        let start = self.pos;
+        let bytes = self.input.as_bytes();
+        self.pos += 1;
+        while self.pos < bytes.len() && bytes[self.pos] != b'"' {
+            self.pos += if bytes[self.pos] == b'\\' { 2 } else { 1 };
+        }
+        self.pos = (self.pos + 1).min(bytes.len());
+        start..self.pos
+    }
+}
+
+/// A mapping key or sequence item enclosing the lines that follow.
+struct YamlParent {
+    indent: usize,
+    key: String,
+    item: bool,
+    /// Number of sequence items seen so far
+    items: usize,
+}
+
+/// Reads the block mappings, sequences and scalars of YAML bundles. Flow collections,
+/// anchors, aliases and tagged values are kept untranslated.
+struct YamlParser<'a> {
+    /// Lines with their offset, without line endings
+    lines: Vec<(usize, &'a str)>,
+    line: usize,
+    parents: Vec<YamlParent>,
+    entries: Vec<Entry>,
+}
+
+impl<'a> YamlParser<'a> {
+    fn new(input: &'a str) -> Self {
+        let mut offset = 0;
+        let lines = input
+            .split_inclusive('\n')
+            .map(|line| {
+                let start = offset;
+                offset += line.len();
+                (start, line.trim_end_matches(['\n', '\r']))
+            })
+            .collect();
+        Self {
+            lines,
+            line: 0,
+            parents: Vec::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    fn error(&self, message: &str) -> ResourceError {
+        SyntaxSnafu {
+            format: "YAML",
+            line: self.line,
+            message,
+        }
+        .build()
+    }
+
+    fn parse(mut self) -> Result<Vec<Entry>, ResourceError> {
+        while self.line < self.lines.len() {
+            let (start, line) = self.lines[self.line];
+            self.line += 1;
+            let mut content = line.trim_start_matches(' ');
+            if content.is_empty()
+                || content.starts_with(['#', '%'])
+                || content.starts_with("---")
+                || content.starts_with("...")
+            {
+                continue;
+            }
+            let mut column = line.len() - content.len();
+
+            // Sequence items, several of them if nested on one line
+            let mut item = false;
+            while let Some(rest) = content
+                .strip_prefix('-')
+                .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+            {
+                while self
+                    .parents
+                    .last()
+                    .is_some_and(|p| p.indent > column || (p.indent == column && p.item))
+                {
+                    self.parents.pop();
+                }
+                let index = self.parents.last_mut().map_or(0, |parent| {
+                    parent.items += 1;
+                    parent.items - 1
+                });
+                self.parents.push(YamlParent {
+                    indent: column,
+                    key: index.to_string(),
+                    item: true,
+                    items: 0,
+                });
+                let value = rest.trim_start();
+                column += content.len() - value.len();
+                content = value;
+                item = true;
+            }
+            if content.is_empty() || content.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = yaml_key(content) else {
+                if !item {
+                    return Err(self.error("expected a key"));
+                }
+                let key = self.path(None);
+                self.value(key, column, content, start + line.len() - content.len())?;
+                continue;
+            };
+            while self.parents.last().is_some_and(|p| p.indent >= column) {
+                self.parents.pop();
+            }
+            let rest = &content[value..];
+            let value = rest.trim_start();
+            if value.is_empty() || value.starts_with('#') {
+                self.parents.push(YamlParent {
+                    indent: column,
+                    key,
+                    item: false,
+                    items: 0,
+                });
+                continue;
+            }
+            let key = self.path(Some(key));
+            self.value(key, column, value, start + line.len() - value.len())?;
+        }
+        Ok(self.entries)
+    }
+
+    /// The dotted path of `key` within the current parents.
+    fn path(&self, key: Option<String>) -> String {
+        let mut path: Vec<&str> = self.parents.iter().map(|p| p.key.as_str()).collect();
+        path.extend(key.as_deref());
+        path.join(".")
+    }
+
+    /// Reads the scalar `text` at byte `at`, the value of a node indented by `column`.
+    fn value(
+        &mut self,
+        key: String,
+        column: usize,
+        text: &str,
+        at: usize,
+    ) -> Result<(), ResourceError> {
+        let (target, value, style) = match text.as_bytes()[0] {
+            b'|' | b'>' => {
+                let Some((target, value, style)) = self.block(column, text.starts_with('|')) else {
+                    return Ok(());
+                };
+                (target, value, style)
+            }
+            b'"' => {
+                let end = double_quoted_end(text)
+                    .ok_or_else(|| self.error("unterminated double-quoted value"))?;
+                let value = yaml_unescape(&text[1..end]);
+                (at..at + end + 1, value, YamlStyle::DoubleQuoted)
+            }
+            b'\'' => {
+                let end = single_quoted_end(text)
+                    .ok_or_else(|| self.error("unterminated single-quoted value"))?;
+                let value = text[1..end].replace("''", "'");
+                (at..at + end + 1, value, YamlStyle::SingleQuoted)
+            }
+            b'[' | b'{' | b'&' | b'*' | b'!' | b'@' | b'`' => return Ok(()),
+            _ => {
+                let value = text.find(" #").map_or(text, |i| &text[..i]).trim_end();
+                if is_yaml_keyword(value) {
+                    return Ok(());
+                }
+                (at..at + value.len(), value.to_string(), YamlStyle::Plain)
+            }
+        };
+        self.entries
+            .extend(value_entry(key, target, value, Style::Yaml(style)));
+        Ok(())
+    }
+
+    /// Reads the lines of a block scalar belonging to a node indented by `column`.
+    fn block(&mut self, column: usize, literal: bool) -> Option<(Range<usize>, String, YamlStyle)> {
+        let first = self.line;
+        let mut last = None;
+        let mut indent = None;
+        while let Some((_, line)) = self.lines.get(self.line) {
+            let content = line.trim_start_matches(' ');
+            if !content.is_empty() {
+                let line_indent = line.len() - content.len();
+                if line_indent <= column {
+                    break;
+                }
+                indent.get_or_insert(line_indent);
+                last = Some(self.line);
+            }
+            self.line += 1;
+        }
+        let (last, indent) = (last?, indent?);
+        self.line = last + 1;
+
+        let lines: Vec<&str> = self.lines[first..=last]
+            .iter()
+            .map(|(_, line)| line.get(indent..).unwrap_or_default())
+            .collect();
+        let value = if literal {
+            lines.join("\n")
+        } else {
+            // Folded lines are joined by spaces, and blank lines separate paragraphs
+            lines
+                .split(|line| line.is_empty())
+                .filter(|paragraph| !paragraph.is_empty())
+                .map(|paragraph| paragraph.join(" "))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let (start, _) = self.lines[first];
+        let (end, line) = self.lines[last];
+        let style = YamlStyle::Block {
+            indent: " ".repeat(indent),
+            literal,
+        };
+        Some((start..end + line.len(), value, style))
+    }
+}
+
+/// The key of a mapping entry and the offset of its value, if `content` is one.
+fn yaml_key(content: &str) -> Option<(String, usize)> {
+    let (key, end) = match content.as_bytes()[0] {
+        b'"' => {
+            let end = double_quoted_end(content)?;
+            (yaml_unescape(&content[1..end]), end + 1)
+        }
+        b'\'' => {
+            let end = single_quoted_end(content)?;
+            (content[1..end].replace("''", "'"), end + 1)
+        }
+        b'[' | b'{' => return None,
+        _ => {
+            let colon = content
+                .match_indices(':')
+                .map(|(i, _)| i)
+                .find(|&i| content[i + 1..].is_empty() || content[i + 1..].starts_with(' '))?;
+            if content[..colon].contains(" #") {
+                return None;
+            }
+            return Some((content[..colon].trim_end().to_string(), colon + 1));
+        }
+    };
+    let rest = &content[end..];
+    let after = rest.trim_start().strip_prefix(':')?;
+    (after.is_empty() || after.starts_with(' ')).then_some((key, content.len() - after.len()))
+}
+
+/// Index of the quote closing the double-quoted scalar `text` starts with.
+fn double_quoted_end(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Index of the quote closing the single-quoted scalar `text` starts with.
+fn single_quoted_end(text: &str) -> Option<usize> {
+    let mut i = 1;
+    while let Some(quote) = text[i..].find('\'') {
+        i += quote;
+        if !text[i + 1..].starts_with('\'') {
+            return Some(i);
+        }
+        i += 2;
+    }
+    None
+}
+
+/// Whether a plain scalar is a null, a boolean or a number rather than text.
+fn is_yaml_keyword(value: &str) -> bool {
+    const KEYWORDS: &[&str] = &["~", "null", "true", "false", "yes", "no", "on", "off"];
+    KEYWORDS.iter().any(|k| value.eq_ignore_ascii_case(k)) || value.parse::<f64>().is_ok()
+}
+
+fn yaml_unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let escaped = chars.next();
+        let unicode = |chars: &mut std::str::Chars, len: usize| {
+            let hex: String = chars.by_ref().take(len).collect();
+            u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+        };
+        let resolved = match escaped {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('0') => Some('\0'),
+            Some('_') => Some('\u{a0}'),
+            Some('x') => unicode(&mut chars, 2),
+            Some('u') => unicode(&mut chars, 4),
+            Some('U') => unicode(&mut chars, 8),
+            Some(c @ ('"' | '\\' | '/' | ' ')) => Some(c),
+            _ => None,
+        };
+        match resolved {
+            Some(c) => out.push(c),
+            None => {
+                out.push('\\');
+                out.extend(escaped);
+            }
+        }
+    }
+    out
+}
+
+fn write_yaml(style: &YamlStyle, value: &str, ending: &str) -> String {
+    let double_quoted = || {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\t', "\\t");
+        format!("\"{}\"", escaped)
+    };
+    match style {
+        YamlStyle::Plain if is_plain_safe(value) => value.to_string(),
+        YamlStyle::SingleQuoted if !value.contains('\n') => {
+            format!("'{}'", value.replace('\'', "''"))
+        }
+        YamlStyle::Plain | YamlStyle::SingleQuoted | YamlStyle::DoubleQuoted => double_quoted(),
+        YamlStyle::Block { indent, literal } => {
+            let separator = if *literal {
+                ending.to_string()
+            } else {
+                format!("{}{}", ending, ending)
+            };
+            value
+                .split('\n')
+                .map(|line| match line {
+                    "" => String::new(),
+                    line => format!("{}{}", indent, line),
+                })
+                .collect::<Vec<_>>()
+                .join(&separator)
+        }
+    }
+}
+
+/// Whether `value` reads back as the same string when written as a plain scalar.
+fn is_plain_safe(value: &str) -> bool {
+    !value.is_empty()
+        && value.trim() == value
+        && !value.starts_with([
+            '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%',
+            '@', '`',
+        ])
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.ends_with(':')
+        && !value.contains(['\n', '\t'])
+        && !is_yaml_keyword(value)
+}
+
+/// A `msgctxt`, `msgid`, `msgid_plural` or `msgstr` line with its continuation lines.
+struct PoField {
+    keyword: String,
+    value: String,
+    /// Span of the lines, without the last line ending
+    range: Range<usize>,
+    /// Line number of the keyword
+    line: usize,
+}
+
+fn parse_po(input: &str) -> Result<Vec<Entry>, ResourceError> {
+    let error = |line: usize, message: &str| {
+        SyntaxSnafu {
+            format: "PO",
+            line,
+            message,
+        }
+        .build()
+    };
+    let mut entries = Vec::new();
+    let mut fields: Vec<PoField> = Vec::new();
+    let has_msgstr = |fields: &[PoField]| fields.iter().any(|f| f.keyword.starts_with("msgstr"));
+    let mut offset = 0;
+    for (n, line) in input.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += line.len();
+        let body = line.trim_end_matches(['\n', '\r']);
+        let trimmed = body.trim();
+        // Comments, including obsolete messages, and blank lines end a message
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            if let Some(field) = fields.first() {
+                if !has_msgstr(&fields) {
+                    return Err(error(field.line, "message without msgstr"));
+                }
+                entries.extend(po_entry(std::mem::take(&mut fields)));
+            }
+            continue;
+        }
+
+        if trimmed.starts_with('"') {
+            let value = po_unquote(trimmed).ok_or_else(|| error(n + 1, "invalid string"))?;
+            let field = fields
+                .last_mut()
+                .ok_or_else(|| error(n + 1, "string outside of a message"))?;
+            field.value.push_str(&value);
+            field.range.end = start + body.len();
+            continue;
+        }
+        let (keyword, rest) = trimmed
+            .split_once([' ', '\t'])
+            .ok_or_else(|| error(n + 1, "expected a keyword and a string"))?;
+        let known = matches!(keyword, "msgctxt" | "msgid" | "msgid_plural" | "msgstr")
+            || keyword
+                .strip_prefix("msgstr[")
+                .and_then(|n| n.strip_suffix(']'))
+                .is_some_and(|n| n.parse::<usize>().is_ok());
+        if !known {
+            return Err(error(n + 1, &format!("unknown keyword '{}'", keyword)));
+        }
+        let value = po_unquote(rest.trim()).ok_or_else(|| error(n + 1, "invalid string"))?;
+        if matches!(keyword, "msgctxt" | "msgid") && has_msgstr(&fields) {
+            entries.extend(po_entry(std::mem::take(&mut fields)));
+        }
+        fields.push(PoField {
+            keyword: keyword.to_string(),
+            value,
+            range: start..start + body.len(),
+            line: n + 1,
+        });
+    }
+    if let Some(field) = fields.first()
+        && !has_msgstr(&fields)
+    {
+        return Err(error(field.line, "message without msgstr"));
+    }
+    entries.extend(po_entry(fields));
+    Ok(entries)
+}
+
+/// The entry of a PO message; `None` for the header.
+fn po_entry(fields: Vec<PoField>) -> Option<Entry> {
+    let field = |keyword: &str| fields.iter().find(|f| f.keyword == keyword);
+    let context = field("msgctxt");
+    let id = field("msgid")?;
+    let plural = field("msgid_plural");
+    if id.value.is_empty() && context.is_none() {
+        return None;
+    }
+    let strings: Vec<&PoField> = fields
+        .iter()
+        .filter(|f| f.keyword.starts_with("msgstr"))
+        .collect();
+    let target = strings.first()?.range.start..strings.last()?.range.end;
+    let translation: Vec<String> = strings.iter().map(|f| f.value.clone()).collect();
+    let translated = translation.iter().any(|value| !value.is_empty());
+
+    let sources = [Some(id), plural]
+        .into_iter()
+        .flatten()
+        .map(|field| {
+            let mut parts = Vec::new();
+            split_message(&field.value, false, &mut parts);
+            parts
+        })
+        .collect();
+    let key = match context {
+        Some(context) => format!("{}\u{4}{}", context.value, id.value),
+        None => id.value.clone(),
+    };
+    Some(Entry {
+        key,
+        target,
+        sources,
+        translation: translated.then_some(translation),
+        translated,
+        kept: None,
+        style: Style::Po {
+            forms: plural.map(|_| strings.len().max(2)),
+        },
+    })
+}
+
+/// Decodes a quoted PO string; `None` if it is not one.
+fn po_unquote(text: &str) -> Option<String> {
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            return None;
+        }
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            'a' => out.push('\u{7}'),
+            'b' => out.push('\u{8}'),
+            'f' => out.push('\u{c}'),
+            'v' => out.push('\u{b}'),
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+fn po_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r");
+    format!("\"{}\"", escaped)
+}
+
+/// The `<source>` and `<target>` of an XLIFF unit or segment being read.
+#[derive(Default)]
+struct XliffSegment {
+    key: String,
+    translatable: bool,
+    /// Start of the source content and the whitespace before `<source>`
+    source_start: Option<(usize, String)>,
+    source: Option<Range<usize>>,
+    /// End of `</source>`
+    source_end: usize,
+    target_start: Option<usize>,
+    target: Option<Range<usize>>,
+    /// Whether the target is an empty `<target/>` element
+    empty_target: bool,
+}
+
+fn parse_xliff(input: &str) -> Result<Vec<Entry>, ResourceError> {
+    let tokens = xml::tokenize(input, &[]).context(XliffSnafu)?;
+    let mut entries = Vec::new();
+    // Open elements with whether their content is translatable
+    let mut open: Vec<(&str, bool)> = Vec::new();
+    let mut unit = String::new();
+    let mut segments = 0;
+    let mut segment: Option<XliffSegment> = None;
+    let mut offset = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        let start = offset;
+        offset += token.raw().len();
+        let parent = open.last().map_or("", |(name, _)| *name);
+        let in_segment = XLIFF_SEGMENTS.contains(&parent);
+        match token {
+            Token::Start {
+                name, self_closing, ..
+            } => {
+                let translatable = open.last().is_none_or(|(_, t)| *t)
+                    && token.attr("translate").as_deref() != Some("no");
+                if matches!(*name, "trans-unit" | "unit") {
+                    unit = token.attr("id").unwrap_or_default().into_owned();
+                    segments = 0;
+                }
+                if XLIFF_SEGMENTS.contains(name) {
+                    let key = match *name {
+                        "segment" => format!("{}#{}", unit, segments),
+                        _ => unit.clone(),
+                    };
+                    segments += 1;
+                    segment = Some(XliffSegment {
+                        key,
+                        translatable,
+                        ..Default::default()
+                    });
+                }
+                if let Some(segment) = segment.as_mut().filter(|_| in_segment) {
+                    match *name {
+                        "source" if !self_closing => {
+                            let indent = match i.checked_sub(1).map(|i| &tokens[i]) {
+                                Some(Token::Text(text)) if text.trim().is_empty() => *text,
+                                _ => "",
+                            };
+                            segment.source_start = Some((offset, indent.to_string()));
+                        }
+                        "target" if *self_closing => {
+                            segment.target = Some(start..offset);
+                            segment.empty_target = true;
+                        }
+                        "target" => segment.target_start = Some(offset),
+                        _ => {}
+                    }
+                }
+                if !self_closing {
+                    open.push((name, translatable));
+                }
+            }
+            Token::End { name, .. } => {
+                if let Some(depth) = open.iter().rposition(|(opened, _)| opened == name) {
+                    open.truncate(depth);
+                }
+                let parent = open.last().map_or("", |(name, _)| *name);
+                let Some(current) = segment.as_mut() else {
+                    continue;
+                };
+                match *name {
+                    "source" if XLIFF_SEGMENTS.contains(&parent) => {
+                        if let Some((content, _)) = &current.source_start {
+                            current.source = Some(*content..start);
+                            current.source_end = offset;
+                        }
+                    }
+                    "target" if XLIFF_SEGMENTS.contains(&parent) => {
+                        if let Some(content) = current.target_start {
+                            current.target = Some(content..start);
+                        }
+                    }
+                    _ if XLIFF_SEGMENTS.contains(name) => {
+                        if let Some(segment) = segment.take() {
+                            entries.extend(xliff_entry(input, segment)?);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+fn xliff_entry(input: &str, segment: XliffSegment) -> Result<Option<Entry>, ResourceError> {
+    let (Some(source), Some((_, indent))) = (segment.source, segment.source_start) else {
+        return Ok(None);
+    };
+    if !segment.translatable {
+        return Ok(None);
+    }
+    let (target, translation, insert) = match segment.target {
+        Some(target) if segment.empty_target => (target, None, Some(String::new())),
+        Some(target) => {
+            let content = &input[target.clone()];
+            let translation = (!content.trim().is_empty()).then(|| vec![content.to_string()]);
+            (target, translation, None)
+        }
+        None => (segment.source_end..segment.source_end, None, Some(indent)),
+    };
+    Ok(Some(Entry {
+        key: segment.key,
+        target,
+        sources: vec![split_xliff(&input[source])?],
+        translated: translation.is_some(),
+        translation,
+        kept: None,
+        style: Style::Xliff { insert },
+    }))
+}
+
+/// Splits the content of a `<source>` into prose, with inline elements as tags, or keeps
+/// it as it is if it has no words.
+fn split_xliff(source: &str) -> Result<Vec<Part>, ResourceError> {
+    let tokens = xml::tokenize(source, &[]).context(XliffSnafu)?;
+    let mut prompt = String::new();
+    let mut tags: Vec<(TagKind, String)> = Vec::new();
+    let mut prose = false;
+    // Names and tag indices of the elements open so far
+    let mut open: Vec<(&str, usize)> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        i += 1;
+        let (kind, markup) = match token {
+            Token::Text(raw) => {
+                let text = xml::unescape(raw);
+                prose |= push_prose(&text, false, xml::escape_text, &mut prompt, &mut tags);
+                continue;
+            }
+            Token::Start {
+                name,
+                self_closing: false,
+                ..
+            } if XLIFF_CODES.contains(name) => {
+                // Native code is kept with its content, up to the matching end tag
+                let mut markup = token.raw().to_string();
+                let mut depth = 1;
+                while depth > 0 && i < tokens.len() {
+                    match &tokens[i] {
+                        Token::Start {
+                            name: inner,
+                            self_closing: false,
+                            ..
+                        } if inner == name => depth += 1,
+                        Token::End { name: inner, .. } if inner == name => depth -= 1,
+                        _ => {}
+                    }
+                    markup.push_str(tokens[i].raw());
+                    i += 1;
+                }
+                (TagKind::Atom, markup)
+            }
+            Token::Start {
+                name,
+                self_closing: false,
+                ..
+            } => {
+                open.push((name, tags.len()));
+                (TagKind::Open, token.raw().to_string())
+            }
+            Token::End { name, .. } => {
+                let kind = match open.iter().rposition(|(opened, _)| opened == name) {
+                    Some(depth) => {
+                        let opened = open[depth].1;
+                        // Elements opened since were never closed
+                        for (_, unclosed) in open.drain(depth..).skip(1) {
+                            tags[unclosed].0 = TagKind::Atom;
+                        }
+                        TagKind::Close(opened)
+                    }
+                    None => TagKind::Atom,
+                };
+                (kind, token.raw().to_string())
+            }
+            _ => (TagKind::Atom, token.raw().to_string()),
+        };
+        prompt.push_str(&protect::placeholder(tags.len()));
+        tags.push((kind, markup));
+    }
+    for (_, unclosed) in open {
+        tags[unclosed].0 = TagKind::Atom;
+    }
+
+    Ok(if prose {
+        vec![Part::Text { prompt, tags }]
+    } else {
+        vec![Part::Raw(source.to_string())]
+    })
+}
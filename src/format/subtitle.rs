@@ -0,0 +1,418 @@
+//! SubRip (`.srt`) and WebVTT (`.vtt`) subtitles: cue texts are translated while cue
+//! numbers, identifiers, timings, settings and styling tags are kept.
+//!
+//! The lines of a cue are translated together and wrapped again afterwards, either to
+//! as many lines as the cue had or to a maximum width. Dialogue cues, whose lines all
+//! start with a dash, keep one line per speaker.
+
+use regex::Regex;
+use snafu::prelude::*;
+use std::sync::LazyLock;
+
+use super::TagKind;
+use crate::protect;
+
+/// `00:01:02,500 --> 00:01:04,000`, optionally followed by WebVTT cue settings.
+static TIMING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:\d+:)?\d{1,2}:\d{2}[,.]\d{3}[ \t]+-->[ \t]+(?:\d+:)?\d{1,2}:\d{2}[,.]\d{3}(?:[ \t].*)?$",
+    )
+    .expect("valid timing regex")
+});
+/// Styling tags, WebVTT timestamps, ASS override blocks such as `{\an8}` and character
+/// references.
+static MARKUP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<[^<>]*>|\{\\[^{}]*\}|&(?:[A-Za-z]+|#[0-9]+|#[xX][0-9A-Fa-f]+);")
+        .expect("valid subtitle markup regex")
+});
+
+#[derive(Debug, Snafu, PartialEq)]
+#[snafu(display("line {}: {}", line, message))]
+pub struct SubtitleError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "application/x-subrip",
+            SubtitleFormat::Vtt => "text/vtt",
+        }
+    }
+}
+
+enum Piece {
+    Raw(String),
+    Cue(usize),
+}
+
+struct Segment {
+    tags: Vec<(TagKind, String)>,
+}
+
+enum Content {
+    Raw(String),
+    Segment(usize),
+}
+
+/// A line of a dialogue cue, or the whole text of any other cue.
+struct CueText {
+    /// The speaker dash of a dialogue line
+    prefix: String,
+    content: Content,
+}
+
+struct Cue {
+    texts: Vec<CueText>,
+    dialogue: bool,
+    /// Number of text lines in the input
+    lines: usize,
+    /// Line ending between the text lines, and after the last one
+    ending: String,
+    trailing: String,
+}
+
+/// A parsed subtitle file, ready to be put back together with translations of its
+/// [`SubtitleDocument::prompts`].
+pub struct SubtitleDocument {
+    format: SubtitleFormat,
+    pieces: Vec<Piece>,
+    cues: Vec<Cue>,
+    segments: Vec<Segment>,
+    prompts: Vec<String>,
+}
+
+impl SubtitleDocument {
+    /// Parses SRT or, if it starts with a `WEBVTT` header, WebVTT subtitles.
+    pub fn parse(input: &str) -> Result<Self, SubtitleError> {
+        let body = input.strip_prefix('\u{feff}').unwrap_or(input);
+        let header = body.split(['\r', '\n']).next().unwrap_or_default();
+        let vtt = header
+            .strip_prefix("WEBVTT")
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']));
+        let mut document = Self {
+            format: if vtt {
+                SubtitleFormat::Vtt
+            } else {
+                SubtitleFormat::Srt
+            },
+            pieces: Vec::new(),
+            cues: Vec::new(),
+            segments: Vec::new(),
+            prompts: Vec::new(),
+        };
+        document.raw(&input[..input.len() - body.len()]);
+
+        let lines: Vec<&str> = body.split_inclusive('\n').collect();
+        let mut i = 0;
+        while i < lines.len() {
+            if lines[i].trim().is_empty() {
+                document.raw(lines[i]);
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < lines.len() && !lines[i].trim().is_empty() {
+                i += 1;
+            }
+            document.block(&lines[start..i], start)?;
+        }
+        Ok(document)
+    }
+
+    pub fn format(&self) -> SubtitleFormat {
+        self.format
+    }
+
+    /// The cue texts to translate, with styling tags as placeholders.
+    pub fn prompts(&self) -> &[String] {
+        &self.prompts
+    }
+
+    /// Rebuilds the subtitles from the translations of their prompts, wrapping cue texts
+    /// to `max_width` characters if given. Returns them along with the tags the
+    /// translations lost.
+    pub fn render(
+        &self,
+        translations: &[String],
+        max_width: Option<usize>,
+    ) -> (String, Vec<String>) {
+        let mut out = String::new();
+        let mut lost = Vec::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Raw(raw) => out.push_str(raw),
+                Piece::Cue(i) => {
+                    let cue = &self.cues[*i];
+                    let mut lines = Vec::new();
+                    for text in &cue.texts {
+                        let content = match &text.content {
+                            Content::Raw(raw) => raw.clone(),
+                            Content::Segment(i) => {
+                                let segment = &self.segments[*i];
+                                let kinds: Vec<TagKind> =
+                                    segment.tags.iter().map(|(kind, _)| *kind).collect();
+                                let mut content = String::new();
+                                super::render_segment(
+                                    &translations[*i],
+                                    &kinds,
+                                    |tag| segment.tags[tag].1.clone(),
+                                    str::to_string,
+                                    &mut content,
+                                    &mut lost,
+                                );
+                                content
+                            }
+                        };
+                        let count = if cue.dialogue { 1 } else { cue.lines };
+                        let mut wrapped = wrap(&content, max_width, count).into_iter();
+                        let first = wrapped.next().unwrap_or_default();
+                        lines.push(format!("{}{}", text.prefix, first));
+                        lines.extend(wrapped);
+                    }
+                    out.push_str(&lines.join(&cue.ending));
+                    out.push_str(&cue.trailing);
+                }
+            }
+        }
+        (out, lost)
+    }
+
+    fn raw(&mut self, raw: &str) {
+        match self.pieces.last_mut() {
+            Some(Piece::Raw(last)) => last.push_str(raw),
+            _ => self.pieces.push(Piece::Raw(raw.to_string())),
+        }
+    }
+
+    /// Adds a block of non-blank lines starting at line index `start`: a cue, or the
+    /// WebVTT header, a note, a style sheet or a region kept as they are.
+    fn block(&mut self, block: &[&str], start: usize) -> Result<(), SubtitleError> {
+        if self.format == SubtitleFormat::Vtt {
+            let first = block[0].trim_end();
+            let keyword = first.split([' ', '\t']).next().unwrap_or_default();
+            if start == 0 || matches!(keyword, "NOTE" | "STYLE" | "REGION") {
+                block.iter().for_each(|line| self.raw(line));
+                return Ok(());
+            }
+        }
+
+        // The timing follows the cue number or identifier, which WebVTT makes optional
+        let numbered = block.len() > 1 && block[0].trim().bytes().all(|b| b.is_ascii_digit());
+        let timing = block
+            .iter()
+            .take(2)
+            .position(|line| line.contains("-->"))
+            .context(SubtitleSnafu {
+                line: start + if numbered { 2 } else { 1 },
+                message: "expected a cue timing",
+            })?;
+        let line = block[timing].trim();
+        ensure!(
+            TIMING.is_match(line),
+            SubtitleSnafu {
+                line: start + timing + 1,
+                message: format!("invalid cue timing '{}'", line),
+            }
+        );
+        block[..=timing].iter().for_each(|line| self.raw(line));
+        let text = &block[timing + 1..];
+        if !text.is_empty() {
+            self.cue(text);
+        }
+        Ok(())
+    }
+
+    /// Adds the text lines of a cue.
+    fn cue(&mut self, lines: &[&str]) {
+        let bodies: Vec<&str> = lines
+            .iter()
+            .map(|line| line.trim_end_matches(['\n', '\r']))
+            .collect();
+        let dialogue = bodies.len() > 1 && bodies.iter().all(|body| body.starts_with('-'));
+        let parts: Vec<(String, Inline)> = if dialogue {
+            bodies
+                .iter()
+                .map(|body| {
+                    let text = body.trim_start_matches('-').trim_start();
+                    let prefix = &body[..body.len() - text.len()];
+                    (prefix.to_string(), Inline::parse(text.trim_end()))
+                })
+                .collect()
+        } else {
+            let text: Vec<&str> = bodies.iter().map(|body| body.trim()).collect();
+            vec![(String::new(), Inline::parse(&text.join(" ")))]
+        };
+        if !parts.iter().any(|(_, inline)| inline.prose) {
+            lines.iter().for_each(|line| self.raw(line));
+            return;
+        }
+
+        let first = lines[0];
+        let last = lines[lines.len() - 1];
+        let texts = parts
+            .into_iter()
+            .map(|(prefix, inline)| {
+                let content = if inline.prose {
+                    self.segments.push(Segment { tags: inline.tags });
+                    self.prompts.push(inline.prompt);
+                    Content::Segment(self.segments.len() - 1)
+                } else {
+                    Content::Raw(inline.source)
+                };
+                CueText { prefix, content }
+            })
+            .collect();
+        self.pieces.push(Piece::Cue(self.cues.len()));
+        self.cues.push(Cue {
+            texts,
+            dialogue,
+            lines: lines.len(),
+            ending: first[first.trim_end_matches(['\n', '\r']).len()..].to_string(),
+            trailing: last[bodies[bodies.len() - 1].len()..].to_string(),
+        });
+    }
+}
+
+/// The text of a cue with its markup as placeholders.
+struct Inline {
+    source: String,
+    prompt: String,
+    tags: Vec<(TagKind, String)>,
+    /// Whether there are words to translate outside the markup
+    prose: bool,
+}
+
+impl Inline {
+    fn parse(text: &str) -> Self {
+        let mut prompt = String::new();
+        let mut tags: Vec<(TagKind, String)> = Vec::new();
+        let mut prose = false;
+        // Names and tag indices of the tags open so far
+        let mut open: Vec<(&str, usize)> = Vec::new();
+        let mut pos = 0;
+        for m in MARKUP.find_iter(text) {
+            let between = &text[pos..m.start()];
+            prose |= between.chars().any(char::is_alphabetic);
+            prompt.push_str(between);
+            pos = m.end();
+
+            let markup = m.as_str();
+            let kind = if let Some(name) = markup.strip_prefix("</") {
+                let name = name.trim_end_matches('>').trim();
+                match open.iter().rposition(|(opened, _)| *opened == name) {
+                    Some(depth) => {
+                        let opened = open[depth].1;
+                        // Tags opened since were never closed
+                        for (_, unclosed) in open.drain(depth..).skip(1) {
+                            tags[unclosed].0 = TagKind::Atom;
+                        }
+                        TagKind::Close(opened)
+                    }
+                    None => TagKind::Atom,
+                }
+            } else if markup.starts_with('<')
+                && markup[1..].starts_with(|c: char| c.is_ascii_alphabetic())
+            {
+                let name = markup[1..]
+                    .split(|c: char| c.is_whitespace() || c == '.' || c == '>' || c == '/')
+                    .next()
+                    .unwrap_or_default();
+                if markup.ends_with("/>") {
+                    TagKind::Atom
+                } else {
+                    open.push((name, tags.len()));
+                    TagKind::Open
+                }
+            } else {
+                TagKind::Atom
+            };
+            prompt.push_str(&protect::placeholder(tags.len()));
+            tags.push((kind, markup.to_string()));
+        }
+        let rest = &text[pos..];
+        prose |= rest.chars().any(char::is_alphabetic);
+        prompt.push_str(rest);
+        for (_, unclosed) in open {
+            tags[unclosed].0 = TagKind::Atom;
+        }
+
+        Self {
+            source: text.to_string(),
+            prompt,
+            tags,
+            prose,
+        }
+    }
+}
+
+/// Number of characters `text` shows on screen, without its markup.
+fn visible_width(text: &str) -> usize {
+    let markup: usize = MARKUP
+        .find_iter(text)
+        .map(|m| m.as_str().chars().count())
+        .sum();
+    text.chars().count() - markup
+}
+
+/// Splits `text` into words at the spaces outside its markup.
+fn words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut markup = MARKUP.find_iter(text).peekable();
+    for (i, c) in text.char_indices() {
+        while markup.next_if(|m| m.end() <= i).is_some() {}
+        let in_markup = markup.peek().is_some_and(|m| m.start() <= i);
+        if c.is_whitespace() && !in_markup {
+            if start < i {
+                words.push(&text[start..i]);
+            }
+            start = i + c.len_utf8();
+        }
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// Wraps `text` to `max_width` characters, or else into at most `count` lines of about
+/// the same width.
+fn wrap(text: &str, max_width: Option<usize>, count: usize) -> Vec<String> {
+    let words = words(text);
+    let greedy = |width: usize| {
+        let mut lines: Vec<String> = Vec::new();
+        for word in &words {
+            match lines.last_mut() {
+                Some(line) if visible_width(line.as_str()) + 1 + visible_width(word) <= width => {
+                    line.push(' ');
+                    line.push_str(word);
+                }
+                _ => lines.push(word.to_string()),
+            }
+        }
+        lines
+    };
+    match max_width {
+        Some(width) => greedy(width),
+        None if count <= 1 => vec![words.join(" ")],
+        None => {
+            let total = visible_width(&words.join(" "));
+            let mut width = total.div_ceil(count);
+            loop {
+                let wrapped = greedy(width);
+                if wrapped.len() <= count {
+                    return wrapped;
+                }
+                width += 1;
+            }
+        }
+    }
+}
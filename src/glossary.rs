@@ -0,0 +1,438 @@
+//! Glossaries of mandated translations for terms such as brand and product names.
+//!
+//! Terms found in an input are masked before translation (see [`crate::protect`]) and
+//! replaced with their target term afterwards, so the model can neither translate nor
+//! mangle them.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use snafu::{Location, prelude::*};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::config::{AppConfig, GlossaryConfig};
+use crate::lang;
+use crate::protect::{self, Masked};
+
+/// A source term and the translation it must get.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Term {
+    pub source: String,
+    pub target: String,
+}
+
+/// Where a glossary was defined.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GlossaryOrigin {
+    Config,
+    Api,
+}
+
+#[derive(Debug, Snafu)]
+pub enum GlossaryError {
+    #[snafu(display("Glossary '{}' not found", id))]
+    NotFound { id: String },
+    #[snafu(display("Glossary '{}' is defined in the configuration", id))]
+    Configured { id: String },
+    #[snafu(display("Invalid glossary id '{}'", id))]
+    InvalidId { id: String },
+    #[snafu(display("Line {}: {}", line, message))]
+    Syntax { line: usize, message: String },
+    #[snafu(display("Glossary '{}' has no terms", id))]
+    Empty { id: String },
+    #[snafu(display("Glossary '{}' cannot be matched: {}", id, source))]
+    Matcher { id: String, source: regex::Error },
+    #[snafu(display("Glossary '{}' is for {}, not {}", id, expected, actual))]
+    LanguageMismatch {
+        id: String,
+        expected: String,
+        actual: String,
+    },
+    #[snafu(display("Failed to read {} at {}: {}", path.display(), location, source))]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Invalid glossary {}: {}", path.display(), source))]
+    File {
+        path: PathBuf,
+        #[snafu(source(from(GlossaryError, Box::new)))]
+        source: Box<GlossaryError>,
+    },
+}
+
+/// The terms of one language pair, with a matcher finding them in inputs.
+#[derive(Debug, Clone, Serialize)]
+pub struct Glossary {
+    pub id: String,
+    /// Language the terms are in; any source language matches when unset
+    pub source_lang: Option<String>,
+    pub target_lang: String,
+    pub case_sensitive: bool,
+    pub origin: GlossaryOrigin,
+    pub terms: Vec<Term>,
+    /// Alternation of every source term, longest first so the longest match wins
+    #[serde(skip)]
+    matcher: Regex,
+    /// Index into `terms` by source term, lowercased unless case sensitive
+    #[serde(skip)]
+    lookup: HashMap<String, usize>,
+}
+
+/// What `GET /admin/glossaries` lists per glossary.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct GlossaryInfo {
+    pub id: String,
+    pub source_lang: Option<String>,
+    pub target_lang: String,
+    pub case_sensitive: bool,
+    pub origin: GlossaryOrigin,
+    pub terms: usize,
+}
+
+impl Glossary {
+    /// Builds a glossary; later terms win over earlier ones with the same source.
+    pub fn new(
+        id: &str,
+        source_lang: Option<String>,
+        target_lang: String,
+        terms: Vec<Term>,
+        case_sensitive: bool,
+        origin: GlossaryOrigin,
+    ) -> Result<Self, GlossaryError> {
+        ensure!(
+            !id.is_empty()
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.'),
+            InvalidIdSnafu { id }
+        );
+        ensure!(!terms.is_empty(), EmptySnafu { id });
+
+        let key = |source: &str| {
+            if case_sensitive {
+                source.to_string()
+            } else {
+                source.to_lowercase()
+            }
+        };
+        let mut lookup = HashMap::new();
+        for (i, term) in terms.iter().enumerate() {
+            lookup.insert(key(&term.source), i);
+        }
+
+        let mut sources: Vec<&str> = lookup.values().map(|&i| terms[i].source.as_str()).collect();
+        sources.sort_by(|a, b| b.chars().count().cmp(&a.chars().count()).then(a.cmp(b)));
+        let alternation = sources
+            .iter()
+            .map(|source| {
+                // Only require word boundaries where the term itself starts or ends a word
+                let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+                format!(
+                    "{}{}{}",
+                    if word(source.chars().next()) {
+                        r"\b"
+                    } else {
+                        ""
+                    },
+                    regex::escape(source),
+                    if word(source.chars().last()) {
+                        r"\b"
+                    } else {
+                        ""
+                    },
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+        let matcher = RegexBuilder::new(&alternation)
+            .case_insensitive(!case_sensitive)
+            .size_limit(1 << 26)
+            .build()
+            .context(MatcherSnafu { id })?;
+
+        Ok(Self {
+            id: id.to_string(),
+            source_lang,
+            target_lang,
+            case_sensitive,
+            origin,
+            terms,
+            matcher,
+            lookup,
+        })
+    }
+
+    /// Loads a glossary configured under `[glossaries]`.
+    pub fn from_config(id: &str, config: &GlossaryConfig) -> Result<Self, GlossaryError> {
+        let path = PathBuf::from(&config.path);
+        let content = std::fs::read_to_string(&path).context(IoSnafu { path: &path })?;
+        let terms = parse_terms(&content, None).context(FileSnafu { path: &path })?;
+        Self::new(
+            id,
+            config.source_lang.clone(),
+            config.target_lang.clone(),
+            terms,
+            config.case_sensitive,
+            GlossaryOrigin::Config,
+        )
+    }
+
+    pub fn info(&self) -> GlossaryInfo {
+        GlossaryInfo {
+            id: self.id.clone(),
+            source_lang: self.source_lang.clone(),
+            target_lang: self.target_lang.clone(),
+            case_sensitive: self.case_sensitive,
+            origin: self.origin,
+            terms: self.terms.len(),
+        }
+    }
+
+    /// Fails unless the glossary is for translating from `source_lang` into `target_lang`,
+    /// both in the codes of a `model_type` model. The glossary's own languages may be
+    /// given in any code system [`lang::normalize`] understands.
+    pub fn check_languages(
+        &self,
+        source_lang: Option<&str>,
+        target_lang: &str,
+        model_type: &str,
+    ) -> Result<(), GlossaryError> {
+        let pair = |source: Option<&str>, target: &str| match source {
+            Some(source) => format!("{} -> {}", source, target),
+            None => target.to_string(),
+        };
+        let native = |lang: &str| lang::normalize(lang, model_type).ok();
+        let source_matches = match (&self.source_lang, source_lang) {
+            (Some(expected), Some(actual)) => {
+                expected == actual || native(expected).is_some_and(|e| e == actual)
+            }
+            _ => true,
+        };
+        let target_matches = self.target_lang == target_lang
+            || native(&self.target_lang).is_some_and(|e| e == target_lang);
+        ensure!(
+            source_matches && target_matches,
+            LanguageMismatchSnafu {
+                id: &self.id,
+                expected: pair(self.source_lang.as_deref(), &self.target_lang),
+                actual: pair(source_lang, target_lang),
+            }
+        );
+        Ok(())
+    }
+
+    /// Every term found in `text` with its byte range, in order.
+    pub fn find(&self, text: &str) -> Vec<(Range<usize>, Term)> {
+        self.matcher
+            .find_iter(text)
+            .filter_map(|m| {
+                let key = if self.case_sensitive {
+                    m.as_str().to_string()
+                } else {
+                    m.as_str().to_lowercase()
+                };
+                let term = &self.terms[*self.lookup.get(&key)?];
+                Some((m.range(), term.clone()))
+            })
+            .collect()
+    }
+
+    /// Masks every term found in `text`, returning the matched term per placeholder.
+    pub fn mask(&self, text: &str) -> (Masked, Vec<Term>) {
+        let spans = self
+            .find(text)
+            .into_iter()
+            .map(|(range, term)| (range, term.target.clone(), term));
+        protect::mask(text, spans)
+    }
+}
+
+/// Parses `source,target` rows of a CSV or TSV file; without a `delimiter`, TSV is
+/// assumed if the first row holds a tab. Fields may be double-quoted, blank lines and
+/// lines starting with `#` are skipped, a `source,target` header row is optional and
+/// further columns (e.g. notes) are ignored. An empty target keeps the source term as
+/// it is.
+pub fn parse_terms(content: &str, delimiter: Option<char>) -> Result<Vec<Term>, GlossaryError> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let delimiter = delimiter.unwrap_or_else(|| {
+        let first = content.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        if first.contains('\t') { '\t' } else { ',' }
+    });
+
+    let mut terms = Vec::new();
+    let mut first_row = true;
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let fields = split_row(line, delimiter).map_err(|message| GlossaryError::Syntax {
+            line: line_number,
+            message,
+        })?;
+        let is_header = first_row
+            && fields.len() >= 2
+            && fields[0].trim().eq_ignore_ascii_case("source")
+            && fields[1].trim().eq_ignore_ascii_case("target");
+        first_row = false;
+        if is_header {
+            continue;
+        }
+
+        let [source, target] = match fields.as_slice() {
+            [source, target, ..] => [source.trim(), target.trim()],
+            _ => {
+                return SyntaxSnafu {
+                    line: line_number,
+                    message: "expected a source and a target term",
+                }
+                .fail();
+            }
+        };
+        ensure!(
+            !source.is_empty(),
+            SyntaxSnafu {
+                line: line_number,
+                message: "empty source term",
+            }
+        );
+        let target = if target.is_empty() { source } else { target };
+        terms.push(Term {
+            source: source.to_string(),
+            target: target.to_string(),
+        });
+    }
+    Ok(terms)
+}
+
+/// Splits one row, honoring double quotes with `""` as an escaped quote.
+fn split_row(line: &str, delimiter: char) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// The glossaries in service: those from the configuration, replaced on every reload, and
+/// those uploaded through the admin API, which take precedence and live until restart.
+#[derive(Default)]
+pub struct GlossaryStore {
+    configured: RwLock<HashMap<String, Arc<Glossary>>>,
+    uploaded: RwLock<HashMap<String, Arc<Glossary>>>,
+}
+
+impl GlossaryStore {
+    pub fn new(config: &AppConfig) -> Self {
+        let store = Self::default();
+        store.load_configured(config);
+        store
+    }
+
+    /// Loads the glossaries of `config`, skipping those that fail with an error logged.
+    pub fn load_configured(&self, config: &AppConfig) {
+        let glossaries = config
+            .glossaries
+            .iter()
+            .filter_map(|(id, glossary)| match Glossary::from_config(id, glossary) {
+                Ok(glossary) => Some((id.clone(), Arc::new(glossary))),
+                Err(e) => {
+                    tracing::error!("Glossary '{}' disabled: {}", id, e);
+                    None
+                }
+            })
+            .collect();
+        *self
+            .configured
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = glossaries;
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Glossary>> {
+        let uploaded = self.uploaded.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(glossary) = uploaded.get(id) {
+            return Some(glossary.clone());
+        }
+        let configured = self
+            .configured
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        configured.get(id).cloned()
+    }
+
+    /// Every glossary, sorted by id.
+    pub fn list(&self) -> Vec<GlossaryInfo> {
+        let uploaded = self.uploaded.read().unwrap_or_else(PoisonError::into_inner);
+        let configured = self
+            .configured
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut infos: Vec<GlossaryInfo> = uploaded
+            .values()
+            .chain(
+                configured
+                    .values()
+                    .filter(|g| !uploaded.contains_key(&g.id)),
+            )
+            .map(|g| g.info())
+            .collect();
+        infos.sort_by(|a, b| a.id.cmp(&b.id));
+        infos
+    }
+
+    /// Adds or replaces an uploaded glossary.
+    pub fn insert(&self, glossary: Glossary) -> Arc<Glossary> {
+        let glossary = Arc::new(glossary);
+        self.uploaded
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(glossary.id.clone(), glossary.clone());
+        glossary
+    }
+
+    /// Removes an uploaded glossary; configured ones can only be removed from the file.
+    pub fn remove(&self, id: &str) -> Result<Arc<Glossary>, GlossaryError> {
+        let removed = self
+            .uploaded
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+        match removed {
+            Some(glossary) => Ok(glossary),
+            None if self
+                .configured
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .contains_key(id) =>
+            {
+                ConfiguredSnafu { id }.fail()
+            }
+            None => NotFoundSnafu { id }.fail(),
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! Mapping between BCP-47 / ISO 639-1 language tags and the codes models expect.
+//!
+//! Clients may send `fr`, `fr-FR`, `fra`, `__fr__` or `fra_Latn` alike; [`normalize`] turns
+//! them into the native code of a model type and rejects the languages it does not know.
+
+use snafu::prelude::*;
+
+/// ISO 639-1 code and FLORES-200 code (as used by NLLB) of common languages.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("af", "afr_Latn"),
+    ("am", "amh_Ethi"),
+    ("ar", "arb_Arab"),
+    ("az", "azj_Latn"),
+    ("be", "bel_Cyrl"),
+    ("bg", "bul_Cyrl"),
+    ("bn", "ben_Beng"),
+    ("bs", "bos_Latn"),
+    ("ca", "cat_Latn"),
+    ("cs", "ces_Latn"),
+    ("cy", "cym_Latn"),
+    ("da", "dan_Latn"),
+    ("de", "deu_Latn"),
+    ("el", "ell_Grek"),
+    ("en", "eng_Latn"),
+    ("es", "spa_Latn"),
+    ("et", "est_Latn"),
+    ("eu", "eus_Latn"),
+    ("fa", "pes_Arab"),
+    ("fi", "fin_Latn"),
+    ("fr", "fra_Latn"),
+    ("ga", "gle_Latn"),
+    ("gl", "glg_Latn"),
+    ("gu", "guj_Gujr"),
+    ("he", "heb_Hebr"),
+    ("hi", "hin_Deva"),
+    ("hr", "hrv_Latn"),
+    ("hu", "hun_Latn"),
+    ("hy", "hye_Armn"),
+    ("id", "ind_Latn"),
+    ("is", "isl_Latn"),
+    ("it", "ita_Latn"),
+    ("ja", "jpn_Jpan"),
+    ("ka", "kat_Geor"),
+    ("kk", "kaz_Cyrl"),
+    ("km", "khm_Khmr"),
+    ("kn", "kan_Knda"),
+    ("ko", "kor_Hang"),
+    ("lt", "lit_Latn"),
+    ("lv", "lvs_Latn"),
+    ("mk", "mkd_Cyrl"),
+    ("ml", "mal_Mlym"),
+    ("mn", "khk_Cyrl"),
+    ("mr", "mar_Deva"),
+    ("ms", "zsm_Latn"),
+    ("my", "mya_Mymr"),
+    ("nb", "nob_Latn"),
+    ("ne", "npi_Deva"),
+    ("nl", "nld_Latn"),
+    ("no", "nob_Latn"),
+    ("pa", "pan_Guru"),
+    ("pl", "pol_Latn"),
+    ("pt", "por_Latn"),
+    ("ro", "ron_Latn"),
+    ("ru", "rus_Cyrl"),
+    ("si", "sin_Sinh"),
+    ("sk", "slk_Latn"),
+    ("sl", "slv_Latn"),
+    ("sq", "als_Latn"),
+    ("sr", "srp_Cyrl"),
+    ("sv", "swe_Latn"),
+    ("sw", "swh_Latn"),
+    ("ta", "tam_Taml"),
+    ("te", "tel_Telu"),
+    ("th", "tha_Thai"),
+    ("tl", "tgl_Latn"),
+    ("tr", "tur_Latn"),
+    ("uk", "ukr_Cyrl"),
+    ("ur", "urd_Arab"),
+    ("uz", "uzn_Latn"),
+    ("vi", "vie_Latn"),
+    ("zh", "zho_Hans"),
+];
+
+/// Whether `code` looks like a FLORES-200 code such as `fra_Latn`, in any case.
+pub fn is_flores_code(code: &str) -> bool {
+    matches!(code.split_once('_'), Some((lang, script))
+        if lang.len() == 3
+            && lang.chars().all(|c| c.is_ascii_alphabetic())
+            && script.len() == 4
+            && script.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+/// The FLORES-200 code for a BCP-47 tag, e.g. `fr-FR` to `fra_Latn`, `zh-TW` to
+/// `zho_Hant` and `sr-Latn` to `srp_Latn`. ISO 639-3 codes such as `fra` and M2M-100
+/// tokens such as `__fr__` are understood, and FLORES-200 codes are returned as they are.
+pub fn to_flores(tag: &str) -> Option<String> {
+    if is_flores_code(tag) {
+        return Some(canonical_flores(tag));
+    }
+    let tag = tag
+        .strip_prefix("__")
+        .and_then(|t| t.strip_suffix("__"))
+        .unwrap_or(tag);
+    let mut subtags = tag.split(['-', '_']);
+    let primary = subtags.next()?.to_ascii_lowercase();
+    let rest: Vec<String> = subtags.map(|s| s.to_ascii_lowercase()).collect();
+
+    if primary == "zh"
+        && rest
+            .iter()
+            .any(|s| matches!(s.as_str(), "hant" | "tw" | "hk" | "mo"))
+    {
+        return Some("zho_Hant".to_string());
+    }
+    let flores = LANGUAGES
+        .iter()
+        .find(|(iso, _)| *iso == primary)
+        .map(|(_, flores)| flores.to_string())
+        // ISO 639-3 codes are the language part of FLORES-200 codes
+        .or_else(|| {
+            NLLB_LANGUAGES
+                .iter()
+                .find(|code| primary.len() == 3 && code.starts_with(&format!("{}_", primary)))
+                .map(|code| code.to_string())
+        })?;
+    // An explicit script subtag picks that script of the language; variants such as
+    // `1996` have four characters too, but never four letters
+    let script = rest
+        .iter()
+        .find(|s| s.len() == 4 && s.chars().all(|c| c.is_ascii_alphabetic()));
+    match script {
+        Some(script) => Some(canonical_flores(&format!("{}_{}", &flores[..3], script))),
+        None => Some(flores),
+    }
+}
+
+/// `fra_latn` written as `fra_Latn`.
+fn canonical_flores(code: &str) -> String {
+    let (lang, script) = code.split_at(3);
+    let mut script = script[1..].chars();
+    let first = script.next().map(|c| c.to_ascii_uppercase());
+    format!(
+        "{}_{}{}",
+        lang.to_ascii_lowercase(),
+        first.into_iter().collect::<String>(),
+        script.as_str().to_ascii_lowercase()
+    )
+}
+
+/// The BCP-47 tag for a model language code, e.g. `fra_Latn` or `__fr__` to `fr`.
+/// Codes that are not recognized are returned as they are.
+pub fn to_bcp47(code: &str) -> String {
+    if let Some(iso) = code.strip_prefix("__").and_then(|c| c.strip_suffix("__")) {
+        return iso.to_string();
+    }
+    match code {
+        "zho_Hant" => "zh-Hant".to_string(),
+        "srp_Latn" => "sr-Latn".to_string(),
+        _ => LANGUAGES
+            .iter()
+            .find(|(_, flores)| *flores == code)
+            .map_or_else(|| code.to_string(), |(iso, _)| iso.to_string()),
+    }
+}
+
+/// The code a model of `model_type` uses for the language `tag`: FLORES-200 for NLLB,
+/// `__xx__` tokens for M2M-100 and plain ISO 639-1 codes otherwise.
+pub fn to_model_code(tag: &str, model_type: &str) -> Option<String> {
+    match model_type {
+        "nllb" | "m2m100" => normalize(tag, model_type).ok(),
+        _ => {
+            let iso = to_bcp47(&to_flores(tag)?);
+            Some(iso.split('-').next().unwrap_or(&iso).to_string())
+        }
+    }
+}
+
+#[derive(Debug, Snafu, PartialEq)]
+pub enum LanguageError {
+    #[snafu(display("Unknown language code '{}'", code))]
+    Unknown { code: String },
+    #[snafu(display("Language '{}' is not supported by {} models", code, model_type))]
+    Unsupported { code: String, model_type: String },
+}
+
+/// The native code of a `model_type` model for the language `tag`, given in any of the
+/// code systems [`to_flores`] understands. Only `nllb` and `m2m100` have a registry; codes
+/// for other model types are passed through as they are, unchecked.
+pub fn normalize(tag: &str, model_type: &str) -> Result<String, LanguageError> {
+    let unknown = || LanguageError::Unknown {
+        code: tag.to_string(),
+    };
+    let unsupported = || LanguageError::Unsupported {
+        code: tag.to_string(),
+        model_type: model_type.to_string(),
+    };
+    match model_type {
+        "nllb" => {
+            let flores = to_flores(tag).ok_or_else(unknown)?;
+            if NLLB_LANGUAGES.contains(&flores.as_str()) {
+                Ok(flores)
+            } else {
+                Err(unsupported())
+            }
+        }
+        "m2m100" => {
+            // M2M-100 codes are ISO 639-1 where there is one, ISO 639-3 otherwise
+            let bare = tag
+                .strip_prefix("__")
+                .and_then(|t| t.strip_suffix("__"))
+                .unwrap_or(tag);
+            let primary = bare
+                .split(['-', '_'])
+                .next()
+                .unwrap_or(bare)
+                .to_ascii_lowercase();
+            let code = if M2M100_LANGUAGES.contains(&primary.as_str()) {
+                primary
+            } else {
+                let iso = to_bcp47(&to_flores(tag).ok_or_else(unknown)?);
+                // FLORES-200 codes without an ISO 639-1 code keep their ISO 639-3 part
+                match iso.split(['-', '_']).next().unwrap_or(&iso) {
+                    "nb" => "no".to_string(),
+                    iso => iso.to_string(),
+                }
+            };
+            if M2M100_LANGUAGES.contains(&code.as_str()) {
+                Ok(format!("__{}__", code))
+            } else {
+                Err(unsupported())
+            }
+        }
+        _ => Ok(tag.to_string()),
+    }
+}
+
+/// The FLORES-200 codes of the languages NLLB-200 was trained on.
+const NLLB_LANGUAGES: &[&str] = &[
+    "ace_Arab", "ace_Latn", "acm_Arab", "acq_Arab", "aeb_Arab", "afr_Latn", "ajp_Arab", "aka_Latn",
+    "amh_Ethi", "apc_Arab", "arb_Arab", "ars_Arab", "ary_Arab", "arz_Arab", "asm_Beng", "ast_Latn",
+    "awa_Deva", "ayr_Latn", "azb_Arab", "azj_Latn", "bak_Cyrl", "bam_Latn", "ban_Latn", "bel_Cyrl",
+    "bem_Latn", "ben_Beng", "bho_Deva", "bjn_Arab", "bjn_Latn", "bod_Tibt", "bos_Latn", "bug_Latn",
+    "bul_Cyrl", "cat_Latn", "ceb_Latn", "ces_Latn", "cjk_Latn", "ckb_Arab", "crh_Latn", "cym_Latn",
+    "dan_Latn", "deu_Latn", "dik_Latn", "dyu_Latn", "dzo_Tibt", "ell_Grek", "eng_Latn", "epo_Latn",
+    "est_Latn", "eus_Latn", "ewe_Latn", "fao_Latn", "pes_Arab", "fij_Latn", "fin_Latn", "fon_Latn",
+    "fra_Latn", "fur_Latn", "fuv_Latn", "gla_Latn", "gle_Latn", "glg_Latn", "grn_Latn", "guj_Gujr",
+    "hat_Latn", "hau_Latn", "heb_Hebr", "hin_Deva", "hne_Deva", "hrv_Latn", "hun_Latn", "hye_Armn",
+    "ibo_Latn", "ilo_Latn", "ind_Latn", "isl_Latn", "ita_Latn", "jav_Latn", "jpn_Jpan", "kab_Latn",
+    "kac_Latn", "kam_Latn", "kan_Knda", "kas_Arab", "kas_Deva", "kat_Geor", "knc_Arab", "knc_Latn",
+    "kaz_Cyrl", "kbp_Latn", "kea_Latn", "khm_Khmr", "kik_Latn", "kin_Latn", "kir_Cyrl", "kmb_Latn",
+    "kon_Latn", "kor_Hang", "kmr_Latn", "lao_Laoo", "lvs_Latn", "lij_Latn", "lim_Latn", "lin_Latn",
+    "lit_Latn", "lmo_Latn", "ltg_Latn", "ltz_Latn", "lua_Latn", "lug_Latn", "luo_Latn", "lus_Latn",
+    "mag_Deva", "mai_Deva", "mal_Mlym", "mar_Deva", "min_Latn", "mkd_Cyrl", "plt_Latn", "mlt_Latn",
+    "mni_Beng", "khk_Cyrl", "mos_Latn", "mri_Latn", "zsm_Latn", "mya_Mymr", "nld_Latn", "nno_Latn",
+    "nob_Latn", "npi_Deva", "nso_Latn", "nus_Latn", "nya_Latn", "oci_Latn", "gaz_Latn", "ory_Orya",
+    "pag_Latn", "pan_Guru", "pap_Latn", "pol_Latn", "por_Latn", "prs_Arab", "pbt_Arab", "quy_Latn",
+    "ron_Latn", "run_Latn", "rus_Cyrl", "sag_Latn", "san_Deva", "sat_Beng", "scn_Latn", "shn_Mymr",
+    "sin_Sinh", "slk_Latn", "slv_Latn", "smo_Latn", "sna_Latn", "snd_Arab", "som_Latn", "sot_Latn",
+    "spa_Latn", "als_Latn", "srd_Latn", "srp_Cyrl", "ssw_Latn", "sun_Latn", "swe_Latn", "swh_Latn",
+    "szl_Latn", "tam_Taml", "tat_Cyrl", "tel_Telu", "tgk_Cyrl", "tgl_Latn", "tha_Thai", "tir_Ethi",
+    "taq_Latn", "taq_Tfng", "tpi_Latn", "tsn_Latn", "tso_Latn", "tuk_Latn", "tum_Latn", "tur_Latn",
+    "twi_Latn", "tzm_Tfng", "uig_Arab", "ukr_Cyrl", "umb_Latn", "urd_Arab", "uzn_Latn", "vec_Latn",
+    "vie_Latn", "war_Latn", "wol_Latn", "xho_Latn", "ydd_Hebr", "yor_Latn", "yue_Hant", "zho_Hans",
+    "zho_Hant", "zul_Latn",
+];
+
+/// The language codes of M2M-100, without the `__` around them.
+const M2M100_LANGUAGES: &[&str] = &[
+    "af", "am", "ar", "ast", "az", "ba", "be", "bg", "bn", "br", "bs", "ca", "ceb", "cs", "cy",
+    "da", "de", "el", "en", "es", "et", "fa", "ff", "fi", "fr", "fy", "ga", "gd", "gl", "gu", "ha",
+    "he", "hi", "hr", "ht", "hu", "hy", "id", "ig", "ilo", "is", "it", "ja", "jv", "ka", "kk",
+    "km", "kn", "ko", "lb", "lg", "ln", "lo", "lt", "lv", "mg", "mk", "ml", "mn", "mr", "ms", "my",
+    "ne", "nl", "no", "ns", "oc", "or", "pa", "pl", "ps", "pt", "ro", "ru", "sd", "si", "sk", "sl",
+    "so", "sq", "sr", "ss", "su", "sv", "sw", "ta", "th", "tl", "tn", "tr", "uk", "ur", "uz", "vi",
+    "wo", "xh", "yi", "yo", "zh", "zu",
+];
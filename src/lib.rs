@@ -1,18 +1,76 @@
 pub mod api;
+pub mod cache;
+pub mod cli;
 pub mod config;
+pub mod detect;
+pub mod device;
+pub mod format;
+pub mod glossary;
+pub mod lang;
+pub mod memory;
 pub mod model;
+pub mod protect;
+pub mod reload;
+pub mod segment;
 pub mod state;
+pub mod tmx;
+pub mod tokenizer;
+pub mod xml;
 
-use axum::{Router, routing::get, routing::post};
+use axum::{
+    Router,
+    extract::State,
+    http::StatusCode,
+    routing::{delete, get, post},
+};
 use state::AppState;
 
 pub fn app(state: AppState) -> Router {
     Router::new()
         .route("/health", get(health_check))
+        .route("/health/live", get(health_check))
+        .route("/health/ready", get(readiness_check))
         .route("/v1/chat/completions", post(api::openai::chat_completions))
+        .route("/v1/detect", post(api::detect::detect_language))
+        .route("/v1/json", post(api::json::translate_json))
+        .route("/v1/resources", post(api::resources::translate_resource))
+        .route("/v1/subtitles", post(api::subtitles::translate_subtitles))
+        .route("/admin/reload", post(api::admin::reload_config))
+        .route(
+            "/admin/cache",
+            get(api::admin::cache_stats).delete(api::admin::clear_cache),
+        )
+        .route(
+            "/admin/memory",
+            get(api::admin::search_memory).post(api::admin::add_memory_entry),
+        )
+        .route(
+            "/admin/memory/tmx",
+            get(api::admin::export_tmx).post(api::admin::import_tmx),
+        )
+        .route(
+            "/admin/memory/{id}",
+            delete(api::admin::delete_memory_entry),
+        )
+        .route("/admin/glossaries", get(api::admin::list_glossaries))
+        .route(
+            "/admin/glossaries/{id}",
+            get(api::admin::get_glossary)
+                .put(api::admin::put_glossary)
+                .delete(api::admin::delete_glossary),
+        )
         .with_state(state)
 }
 
 async fn health_check() -> &'static str {
     "OK"
 }
+
+/// Reports 503 until all preloaded models are loaded and warmed up.
+async fn readiness_check(State(state): State<AppState>) -> (StatusCode, &'static str) {
+    if state.model_manager.is_ready() {
+        (StatusCode::OK, "OK")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "Not ready")
+    }
+}
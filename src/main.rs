@@ -1,8 +1,9 @@
 use clap::Parser;
 use ctranslate2_server::{
-    app,
+    app, cli,
     config::{AppConfig, Args},
     model::ModelManager,
+    reload,
     state::AppState,
 };
 use std::net::SocketAddr;
@@ -12,8 +13,38 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    let check_config = args.check_config;
+    let command = args.command.clone();
     let config = AppConfig::load(Some(args)).expect("Failed to load configuration");
 
+    // Models a command translates with must exist; the server loads the others lazily
+    let required: Vec<&str> = match &command {
+        Some(cli::Command::TranslateResource { model, .. }) => vec![model.as_str()],
+        _ => Vec::new(),
+    };
+    match config.validate(&required) {
+        Ok(warnings) => {
+            for warning in warnings {
+                eprintln!("{}: warning: {}", config.config_path(), warning);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}: {}", config.config_path(), e);
+            std::process::exit(1);
+        }
+    }
+    if check_config {
+        println!("{}: configuration is valid", config.config_path());
+        return;
+    }
+    if let Some(command) = command {
+        if let Err(e) = cli::run(&command, &config).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Initialize tracing
     tracing_subscriber::registry()
         .with(
@@ -24,7 +55,17 @@ async fn main() {
         .init();
 
     let model_manager = Arc::new(ModelManager::new(config.clone()));
-    let state = AppState { model_manager };
+    let state = AppState {
+        model_manager: model_manager.clone(),
+    };
+
+    reload::spawn_signal_handler(model_manager.clone());
+    if config.server.watch_config {
+        reload::spawn_file_watcher(model_manager.clone(), reload::WATCH_INTERVAL);
+    }
+
+    // Preload in the background so liveness probes answer while models load
+    reload::spawn_preload(model_manager);
 
     let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port)
         .parse()
@@ -0,0 +1,466 @@
+use serde::{Deserialize, Serialize};
+use snafu::{Location, prelude::*};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
+
+use crate::cache::normalize_input;
+use crate::config::{TranslationMemoryConfig, edit_distance};
+
+/// An approved translation stored in the translation memory.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemoryEntry {
+    pub id: u64,
+    pub source: String,
+    pub target: String,
+}
+
+/// A translation memory entry similar to a looked up text; `score` is 1 for exact matches.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemoryMatch {
+    pub id: u64,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub source: String,
+    pub target: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Snafu)]
+pub enum MemoryError {
+    #[snafu(display("Translation memory is not configured"))]
+    Disabled,
+    #[snafu(display("Invalid language code '{}'", lang))]
+    InvalidLanguage { lang: String },
+    #[snafu(display("Translation memory entry {} not found", id))]
+    EntryNotFound { id: u64 },
+    #[snafu(display("Failed to access {} at {}: {}", path.display(), location, source))]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Failed to parse {} at {}: {}", path.display(), location, source))]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+}
+
+/// Source and target language of a set of entries, stored together in one file.
+type LangPair = (String, String);
+
+/// Similarity of two texts in `[0, 1]`, from the character edit distance of their
+/// whitespace-normalized forms.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    normalized_similarity(&normalize_input(a), &normalize_input(b))
+}
+
+fn normalized_similarity(a: &str, b: &str) -> f32 {
+    let len = a.chars().count().max(b.chars().count());
+    if len == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f32 / len as f32
+}
+
+/// One line of the log of a language pair. An entry replaces the earlier one with the
+/// same source text; a deletion names the entry it removes.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Record {
+    Entry(MemoryEntry),
+    Deleted {
+        deleted: u64,
+    },
+    /// Heads a compacted log, so the ids of dropped entries are never handed out again
+    NextId {
+        next_id: u64,
+    },
+}
+
+/// An entry with its normalized source and that source's length in characters, worked
+/// out once so searches only pay for the edit distance.
+#[derive(Debug, Clone)]
+struct Stored {
+    entry: MemoryEntry,
+    normalized: String,
+    len: usize,
+}
+
+impl From<MemoryEntry> for Stored {
+    fn from(entry: MemoryEntry) -> Self {
+        let normalized = normalize_input(&entry.source);
+        Self {
+            len: normalized.chars().count(),
+            normalized,
+            entry,
+        }
+    }
+}
+
+/// The entries of a language pair, indexed by normalized source text and by id.
+#[derive(Default)]
+struct Pair {
+    entries: Vec<Stored>,
+    by_source: HashMap<String, usize>,
+    by_id: HashMap<u64, usize>,
+}
+
+impl Pair {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Adds `entry`, replacing the entry with the same source text.
+    fn insert(&mut self, entry: MemoryEntry) {
+        let stored = Stored::from(entry);
+        let index = match self.by_source.get(&stored.normalized) {
+            Some(&index) => {
+                self.by_id.remove(&self.entries[index].entry.id);
+                self.entries[index] = stored;
+                index
+            }
+            None => {
+                self.by_source
+                    .insert(stored.normalized.clone(), self.entries.len());
+                self.entries.push(stored);
+                self.entries.len() - 1
+            }
+        };
+        self.by_id.insert(self.entries[index].entry.id, index);
+    }
+
+    /// Removes entry `id`, if the pair holds it.
+    fn remove(&mut self, id: u64) -> Option<MemoryEntry> {
+        let index = self.by_id.remove(&id)?;
+        let removed = self.entries.swap_remove(index);
+        self.by_source.remove(&removed.normalized);
+        if let Some(moved) = self.entries.get(index) {
+            self.by_source.insert(moved.normalized.clone(), index);
+            self.by_id.insert(moved.entry.id, index);
+        }
+        Some(removed.entry)
+    }
+}
+
+/// The entries of every language pair and the id of the next one; ids are unique across
+/// pairs and never reused, even after deletions.
+struct Store {
+    next_id: u64,
+    pairs: HashMap<LangPair, Pair>,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self {
+            next_id: 1,
+            pairs: HashMap::new(),
+        }
+    }
+}
+
+/// Approved translations per language pair, kept in memory and persisted as one
+/// append-only JSON Lines file per pair under the configured directory.
+#[derive(Default)]
+pub struct TranslationMemory {
+    dir: Option<PathBuf>,
+    store: RwLock<Store>,
+}
+
+impl TranslationMemory {
+    /// Loads every stored language pair from `config.path`; without a path the memory
+    /// stays disabled. Logs holding more replaced or deleted records than live entries
+    /// are compacted.
+    pub fn open(config: &TranslationMemoryConfig) -> Result<Self, MemoryError> {
+        let Some(dir) = &config.path else {
+            return Ok(Self::default());
+        };
+        let dir = PathBuf::from(dir);
+        std::fs::create_dir_all(&dir).context(IoSnafu { path: &dir })?;
+
+        let mut store = Store::default();
+        let mut stale = Vec::new();
+        for file in std::fs::read_dir(&dir).context(IoSnafu { path: &dir })? {
+            let path = file.context(IoSnafu { path: &dir })?.path();
+            let Some(pair) = pair_from_path(&path) else {
+                continue;
+            };
+            let content = std::fs::read_to_string(&path).context(IoSnafu { path: &path })?;
+            let log = replay(&path, &content)?;
+            store.next_id = store.next_id.max(log.next_id);
+            if log.records > 2 * log.entries.len() {
+                stale.push(pair.clone());
+            }
+            store.pairs.insert(pair, log.entries);
+        }
+        for pair in &stale {
+            compact(&dir, pair, store.next_id, &store.pairs[pair])?;
+        }
+
+        let count: usize = store.pairs.values().map(Pair::len).sum();
+        tracing::info!(
+            "Loaded {} translation memory entries in {} language pairs from {}",
+            count,
+            store.pairs.len(),
+            dir.display()
+        );
+        Ok(Self {
+            dir: Some(dir),
+            store: RwLock::new(store),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Entries of a language pair whose source is at least `threshold` similar to `text`,
+    /// best first and at most `limit` of them. An exact match is found without scanning
+    /// the pair when it is all that is asked for.
+    pub fn search(
+        &self,
+        source_lang: &str,
+        target_lang: &str,
+        text: &str,
+        threshold: f32,
+        limit: usize,
+    ) -> Vec<MemoryMatch> {
+        let store = self.store.read().unwrap_or_else(PoisonError::into_inner);
+        let Some(pair) = store
+            .pairs
+            .get(&(source_lang.to_string(), target_lang.to_string()))
+        else {
+            return Vec::new();
+        };
+        let to_match = |stored: &Stored, score: f32| MemoryMatch {
+            id: stored.entry.id,
+            source_lang: source_lang.to_string(),
+            target_lang: target_lang.to_string(),
+            source: stored.entry.source.clone(),
+            target: stored.entry.target.clone(),
+            score,
+        };
+
+        let text = normalize_input(text);
+        let exact = pair.by_source.get(&text).map(|&index| &pair.entries[index]);
+        if threshold >= 1.0 || (limit <= 1 && exact.is_some()) {
+            let mut matches: Vec<MemoryMatch> = exact
+                .map(|stored| to_match(stored, 1.0))
+                .into_iter()
+                .collect();
+            matches.truncate(limit);
+            return matches;
+        }
+
+        let len = text.chars().count();
+        let mut matches: Vec<MemoryMatch> = pair
+            .entries
+            .iter()
+            .filter_map(|stored| {
+                // The length difference bounds the similarity, which spares most distances
+                let other = stored.len;
+                let bound = 1.0 - len.abs_diff(other) as f32 / len.max(other).max(1) as f32;
+                if bound < threshold {
+                    return None;
+                }
+                let score = normalized_similarity(&text, &stored.normalized);
+                (score >= threshold).then(|| to_match(stored, score))
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Stores an approved translation, replacing the one for the same source text.
+    pub fn add(
+        &self,
+        source_lang: &str,
+        target_lang: &str,
+        source: &str,
+        target: &str,
+    ) -> Result<MemoryEntry, MemoryError> {
+        let dir = self.dir.as_ref().context(DisabledSnafu)?;
+        let pair = checked_pair(source_lang, target_lang)?;
+
+        let mut store = self.store.write().unwrap_or_else(PoisonError::into_inner);
+        let entry = MemoryEntry {
+            id: store.next_id,
+            source: source.trim().to_string(),
+            target: target.trim().to_string(),
+        };
+        append(dir, &pair, &[Record::Entry(entry.clone())])?;
+        store.next_id += 1;
+        store.pairs.entry(pair).or_default().insert(entry.clone());
+        Ok(entry)
+    }
+
+    /// Stores many translations of one language pair at once, appending to its log a
+    /// single time. Returns how many distinct source texts were stored.
+    pub fn add_many(
+        &self,
+        source_lang: &str,
+        target_lang: &str,
+        translations: &[(String, String)],
+    ) -> Result<usize, MemoryError> {
+        let dir = self.dir.as_ref().context(DisabledSnafu)?;
+        let pair = checked_pair(source_lang, target_lang)?;
+
+        let mut store = self.store.write().unwrap_or_else(PoisonError::into_inner);
+        // Later translations of the same source replace earlier ones, as with `add`
+        let mut added: HashMap<String, MemoryEntry> = HashMap::new();
+        for ((source, target), id) in translations.iter().zip(store.next_id..) {
+            added.insert(
+                normalize_input(source),
+                MemoryEntry {
+                    id,
+                    source: source.trim().to_string(),
+                    target: target.trim().to_string(),
+                },
+            );
+        }
+        let mut added: Vec<MemoryEntry> = added.into_values().collect();
+        added.sort_by_key(|e| e.id);
+        let records: Vec<Record> = added.iter().cloned().map(Record::Entry).collect();
+        append(dir, &pair, &records)?;
+
+        store.next_id += translations.len() as u64;
+        let count = added.len();
+        let entries = store.pairs.entry(pair).or_default();
+        for entry in added {
+            entries.insert(entry);
+        }
+        Ok(count)
+    }
+
+    /// Removes an entry from whichever language pair holds it.
+    pub fn delete(&self, id: u64) -> Result<MemoryEntry, MemoryError> {
+        let dir = self.dir.as_ref().context(DisabledSnafu)?;
+        let mut store = self.store.write().unwrap_or_else(PoisonError::into_inner);
+        for (lang_pair, pair) in store.pairs.iter_mut() {
+            if pair.by_id.contains_key(&id) {
+                append(dir, lang_pair, &[Record::Deleted { deleted: id }])?;
+                return pair.remove(id).context(EntryNotFoundSnafu { id });
+            }
+        }
+        EntryNotFoundSnafu { id }.fail()
+    }
+
+    /// Every entry of a language pair, oldest first.
+    pub fn entries(&self, source_lang: &str, target_lang: &str) -> Vec<MemoryEntry> {
+        let store = self.store.read().unwrap_or_else(PoisonError::into_inner);
+        let mut entries: Vec<MemoryEntry> = store
+            .pairs
+            .get(&(source_lang.to_string(), target_lang.to_string()))
+            .map(|pair| pair.entries.iter().map(|e| e.entry.clone()).collect())
+            .unwrap_or_default();
+        entries.sort_by_key(|e| e.id);
+        entries
+    }
+}
+
+/// Language codes end up in file names, so only letters, digits, `_` and `-` pass.
+fn checked_pair(source_lang: &str, target_lang: &str) -> Result<LangPair, MemoryError> {
+    for lang in [source_lang, target_lang] {
+        ensure!(
+            !lang.is_empty()
+                && lang
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+            InvalidLanguageSnafu { lang }
+        );
+    }
+    Ok((source_lang.to_string(), target_lang.to_string()))
+}
+
+fn pair_path(dir: &Path, (source_lang, target_lang): &LangPair) -> PathBuf {
+    dir.join(format!("{}__{}.jsonl", source_lang, target_lang))
+}
+
+fn pair_from_path(path: &Path) -> Option<LangPair> {
+    if path.extension()? != "jsonl" {
+        return None;
+    }
+    let (source_lang, target_lang) = path.file_stem()?.to_str()?.split_once("__")?;
+    checked_pair(source_lang, target_lang).ok()
+}
+
+/// The live entries of a language pair log.
+struct PairLog {
+    entries: Pair,
+    /// Past every id the log mentions, deleted and replaced entries included
+    next_id: u64,
+    records: usize,
+}
+
+fn replay(path: &Path, content: &str) -> Result<PairLog, MemoryError> {
+    let mut lines: Vec<&str> = content.lines().collect();
+    // A crash in the middle of an append leaves the last line unterminated
+    if !content.is_empty() && !content.ends_with('\n') {
+        tracing::warn!("Ignoring the incomplete last line of {}", path.display());
+        lines.pop();
+    }
+
+    let mut log = PairLog {
+        entries: Pair::default(),
+        next_id: 1,
+        records: 0,
+    };
+    for line in lines.into_iter().filter(|line| !line.trim().is_empty()) {
+        log.records += 1;
+        match serde_json::from_str(line).context(ParseSnafu { path })? {
+            Record::Entry(entry) => {
+                log.next_id = log.next_id.max(entry.id + 1);
+                log.entries.insert(entry);
+            }
+            Record::Deleted { deleted } => {
+                log.next_id = log.next_id.max(deleted + 1);
+                log.entries.remove(deleted);
+            }
+            Record::NextId { next_id } => log.next_id = log.next_id.max(next_id),
+        }
+    }
+    Ok(log)
+}
+
+fn to_lines(path: &Path, records: &[Record]) -> Result<String, MemoryError> {
+    let mut content = String::new();
+    for record in records {
+        content.push_str(&serde_json::to_string(record).context(ParseSnafu { path })?);
+        content.push('\n');
+    }
+    Ok(content)
+}
+
+/// Appends records to the log of a language pair.
+fn append(dir: &Path, pair: &LangPair, records: &[Record]) -> Result<(), MemoryError> {
+    let path = pair_path(dir, pair);
+    let content = to_lines(&path, records)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context(IoSnafu { path: &path })?;
+    file.write_all(content.as_bytes())
+        .context(IoSnafu { path: &path })
+}
+
+/// Rewrites the log of a language pair with only its live entries, going through a
+/// temporary file so a crash never leaves it half written.
+fn compact(dir: &Path, lang_pair: &LangPair, next_id: u64, pair: &Pair) -> Result<(), MemoryError> {
+    let path = pair_path(dir, lang_pair);
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut entries: Vec<&MemoryEntry> = pair.entries.iter().map(|e| &e.entry).collect();
+    entries.sort_by_key(|e| e.id);
+    let records: Vec<Record> = std::iter::once(Record::NextId { next_id })
+        .chain(entries.into_iter().cloned().map(Record::Entry))
+        .collect();
+    std::fs::write(&tmp, to_lines(&path, &records)?).context(IoSnafu { path: &tmp })?;
+    std::fs::rename(&tmp, &path).context(IoSnafu { path: &path })
+}
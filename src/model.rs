@@ -1,19 +1,309 @@
-use ct2rs::tokenizers::auto::Tokenizer as AutoTokenizer;
-use ct2rs::{Config as Ct2Config, Device, TranslationOptions, Translator};
+use ct2rs::{ComputeType, Config as Ct2Config, Tokenizer as _, TranslationOptions, Translator};
+use serde::{Deserialize, Serialize};
 use snafu::{Location, prelude::*};
 use std::collections::HashMap;
+use std::ops::Range;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError};
 use tokio::sync::RwLock;
 
-use crate::config::AppConfig;
+use crate::cache::{CacheKey, TranslationCache};
+use crate::config::{AppConfig, ModelSpec, ValidationError};
+use crate::detect;
+use crate::device::{self, DeviceSpec};
+use crate::glossary::{Glossary, GlossaryError, GlossaryStore, Term};
+use crate::lang::{self, LanguageError};
+use crate::memory::{MemoryMatch, TranslationMemory};
+use crate::protect::{self, LostPlaceholders, Masked, Protector};
+use crate::segment::{self, Segmented};
+use crate::tokenizer::{self, ModelTokenizer};
 
-#[derive(Debug, Clone, Default)]
+/// Generation settings that can be given per request, per alias, per model or in a preset.
+///
+/// `None` means "not set at this level"; [`ModelManager::generate`] falls back from the
+/// request to the alias, then to the model spec, then to the global defaults. At each
+/// level, settings given explicitly win over those of the preset the level selects.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
 pub struct GenerationParams {
+    /// Name of a `[presets]` entry filling the settings not given at this level
+    pub preset: Option<String>,
     pub target_lang: Option<String>,
+    /// Several target languages every input is translated into, in one batch; takes
+    /// precedence over `target_lang`
+    pub target_langs: Option<Vec<String>>,
+    pub source_lang: Option<String>,
     pub beam_size: Option<usize>,
     pub repetition_penalty: Option<f32>,
     pub no_repeat_ngram_size: Option<usize>,
+    pub length_penalty: Option<f32>,
+    pub coverage_penalty: Option<f32>,
+    pub patience: Option<f32>,
+    pub max_input_length: Option<usize>,
+    pub min_decoding_length: Option<usize>,
+    pub max_decoding_length: Option<usize>,
+    pub disable_unk: Option<bool>,
+    pub replace_unknowns: Option<bool>,
+    /// Phrases the decoder must never produce
+    pub suppress_sequences: Option<Vec<String>>,
+    pub sampling_topk: Option<usize>,
+    pub sampling_topp: Option<f32>,
+    pub sampling_temperature: Option<f32>,
+    /// Random seed for sampling
+    pub seed: Option<u32>,
+    /// Whether translations may be served from and stored in the translation cache
+    pub use_cache: Option<bool>,
+    /// Whether the translation memory is consulted
+    pub use_memory: Option<bool>,
+    /// Glossary whose terms are enforced in the translation
+    pub glossary_id: Option<String>,
+    /// Protection patterns (built-in presets or `[protect_patterns]`) whose matches are
+    /// kept out of the model
+    pub protect: Option<Vec<String>>,
+    /// Whether a protected span the model drops fails the request
+    pub on_lost_placeholder: Option<LostPlaceholders>,
+    /// Whether inputs are split into sentences, translated as one batch
+    pub segment: Option<bool>,
+}
+
+impl GenerationParams {
+    /// Fills every setting not given here from `fallback`.
+    pub fn or(self, fallback: &GenerationParams) -> Self {
+        // One or several target languages are a single setting, given either way
+        let (target_lang, target_langs) =
+            if self.target_lang.is_some() || self.target_langs.is_some() {
+                (self.target_lang, self.target_langs)
+            } else {
+                (fallback.target_lang.clone(), fallback.target_langs.clone())
+            };
+        Self {
+            preset: self.preset.or_else(|| fallback.preset.clone()),
+            target_lang,
+            target_langs,
+            source_lang: self.source_lang.or_else(|| fallback.source_lang.clone()),
+            beam_size: self.beam_size.or(fallback.beam_size),
+            repetition_penalty: self.repetition_penalty.or(fallback.repetition_penalty),
+            no_repeat_ngram_size: self.no_repeat_ngram_size.or(fallback.no_repeat_ngram_size),
+            length_penalty: self.length_penalty.or(fallback.length_penalty),
+            coverage_penalty: self.coverage_penalty.or(fallback.coverage_penalty),
+            patience: self.patience.or(fallback.patience),
+            max_input_length: self.max_input_length.or(fallback.max_input_length),
+            min_decoding_length: self.min_decoding_length.or(fallback.min_decoding_length),
+            max_decoding_length: self.max_decoding_length.or(fallback.max_decoding_length),
+            disable_unk: self.disable_unk.or(fallback.disable_unk),
+            replace_unknowns: self.replace_unknowns.or(fallback.replace_unknowns),
+            suppress_sequences: self
+                .suppress_sequences
+                .or_else(|| fallback.suppress_sequences.clone()),
+            sampling_topk: self.sampling_topk.or(fallback.sampling_topk),
+            sampling_topp: self.sampling_topp.or(fallback.sampling_topp),
+            sampling_temperature: self.sampling_temperature.or(fallback.sampling_temperature),
+            seed: self.seed.or(fallback.seed),
+            use_cache: self.use_cache.or(fallback.use_cache),
+            use_memory: self.use_memory.or(fallback.use_memory),
+            glossary_id: self.glossary_id.or_else(|| fallback.glossary_id.clone()),
+            protect: self.protect.or_else(|| fallback.protect.clone()),
+            on_lost_placeholder: self.on_lost_placeholder.or(fallback.on_lost_placeholder),
+            segment: self.segment.or(fallback.segment),
+        }
+    }
+
+    /// Rejects settings CTranslate2 can't decode with, naming the request field at fault
+    /// like [`Limits::check`]. Configured settings are checked by the config validation.
+    pub fn check_ranges(&self) -> Result<(), ModelError> {
+        let not_positive = |value: Option<f32>| value.is_some_and(|v| !(v > 0.0 && v.is_finite()));
+        let not_finite = |value: Option<f32>| value.is_some_and(|v| !v.is_finite());
+        let checks = [
+            ("beam_size", self.beam_size == Some(0), "must be at least 1"),
+            (
+                "max_tokens",
+                self.max_decoding_length == Some(0),
+                "must be at least 1",
+            ),
+            (
+                "repetition_penalty",
+                not_positive(self.repetition_penalty),
+                "must be greater than 0",
+            ),
+            (
+                "patience",
+                not_positive(self.patience),
+                "must be greater than 0",
+            ),
+            (
+                "length_penalty",
+                not_finite(self.length_penalty),
+                "must be a finite number",
+            ),
+            (
+                "coverage_penalty",
+                not_finite(self.coverage_penalty),
+                "must be a finite number",
+            ),
+            (
+                "top_p",
+                self.sampling_topp.is_some_and(|p| !(p > 0.0 && p <= 1.0)),
+                "must be in (0, 1]",
+            ),
+            (
+                "temperature",
+                not_positive(self.sampling_temperature),
+                "must be greater than 0",
+            ),
+        ];
+        match checks.into_iter().find(|(_, invalid, _)| *invalid) {
+            Some((param, _, message)) => InvalidSettingSnafu { param, message }.fail(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Caps on what clients may request, set globally under `[limits]` or per model.
+///
+/// `None` means "no limit at this level"; per-model limits fall back to the global ones.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct Limits {
+    pub max_beam_size: Option<usize>,
+    /// Characters per input text
+    pub max_input_chars: Option<usize>,
+    /// Tokens per input text, as counted by the model's tokenizer
+    pub max_input_tokens: Option<usize>,
+    /// Input texts per call
+    pub max_batch_items: Option<usize>,
+    pub max_decoding_length: Option<usize>,
+    pub allowed_target_langs: Option<Vec<String>>,
+}
+
+impl Limits {
+    /// Fills every limit not set here from `fallback`.
+    pub fn or(self, fallback: &Limits) -> Self {
+        Self {
+            max_beam_size: self.max_beam_size.or(fallback.max_beam_size),
+            max_input_chars: self.max_input_chars.or(fallback.max_input_chars),
+            max_input_tokens: self.max_input_tokens.or(fallback.max_input_tokens),
+            max_batch_items: self.max_batch_items.or(fallback.max_batch_items),
+            max_decoding_length: self.max_decoding_length.or(fallback.max_decoding_length),
+            allowed_target_langs: self
+                .allowed_target_langs
+                .or_else(|| fallback.allowed_target_langs.clone()),
+        }
+    }
+
+    /// Rejects inputs and request settings beyond these limits, naming the request field
+    /// at fault. Token counts are checked separately as they need the model's tokenizer.
+    pub fn check(&self, prompts: &[String], params: &GenerationParams) -> Result<(), ModelError> {
+        // Every input is translated once per target language
+        let targets = params
+            .target_langs
+            .as_ref()
+            .map_or(1, |langs| langs.len().max(1));
+        if let Some(max) = self.max_batch_items
+            && prompts.len() * targets > max
+        {
+            let message = if targets > 1 {
+                format!(
+                    "{} inputs in {} target languages exceed the limit of {}",
+                    prompts.len(),
+                    targets,
+                    max
+                )
+            } else {
+                format!("{} inputs exceed the limit of {}", prompts.len(), max)
+            };
+            return LimitExceededSnafu {
+                param: "messages",
+                message,
+            }
+            .fail();
+        }
+        if let Some(max) = self.max_input_chars
+            && let Some(chars) = prompts
+                .iter()
+                .map(|p| p.chars().count())
+                .find(|&chars| chars > max)
+        {
+            return LimitExceededSnafu {
+                param: "messages",
+                message: format!("input of {} characters exceeds the limit of {}", chars, max),
+            }
+            .fail();
+        }
+        if let Some(max) = self.max_beam_size
+            && let Some(beam_size) = params.beam_size.filter(|&n| n > max)
+        {
+            return LimitExceededSnafu {
+                param: "beam_size",
+                message: format!("beam size {} exceeds the limit of {}", beam_size, max),
+            }
+            .fail();
+        }
+        if let Some(max) = self.max_decoding_length
+            && let Some(length) = params.max_decoding_length.filter(|&n| n > max)
+        {
+            return LimitExceededSnafu {
+                param: "max_tokens",
+                message: format!("decoding length {} exceeds the limit of {}", length, max),
+            }
+            .fail();
+        }
+        if let Some(allowed) = &self.allowed_target_langs
+            && let Some((param, lang)) = params
+                .target_lang
+                .iter()
+                .map(|lang| ("target_lang", lang))
+                .chain(
+                    params
+                        .target_langs
+                        .iter()
+                        .flatten()
+                        .map(|lang| ("target_langs", lang)),
+                )
+                .find(|&(_, lang)| !allowed.contains(lang))
+        {
+            return LimitExceededSnafu {
+                param,
+                message: format!(
+                    "target language '{}' is not allowed, expected one of {}",
+                    lang,
+                    allowed.join(", ")
+                ),
+            }
+            .fail();
+        }
+        Ok(())
+    }
+}
+
+/// Where a [`Translation`] came from.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    Model,
+    Cache,
+    Memory,
+}
+
+/// One translated input.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Translation {
+    pub text: String,
+    pub origin: Origin,
+    /// Fuzzy translation memory matches besides the one used, best first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<MemoryMatch>,
+    /// Glossary terms enforced in the translation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub glossary_terms: Vec<Term>,
+    /// Protected spans the model dropped, with `on_lost_placeholder = "warn"`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lost_placeholders: Vec<String>,
+}
+
+/// A loaded model: the translator plus a handle on its tokenizer for encoding text
+/// outside of translation.
+pub struct LoadedModel {
+    pub translator: Translator<ModelTokenizer>,
+    pub tokenizer: ModelTokenizer,
 }
 
 #[derive(Debug, Snafu)]
@@ -43,99 +333,394 @@ pub enum ModelError {
         #[snafu(implicit)]
         location: Location,
     },
+    #[snafu(display("Preset not found: '{}' at {}", preset, location))]
+    PresetNotFound {
+        preset: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Invalid '{}': {} at {}", param, message, location))]
+    LimitExceeded {
+        param: String,
+        message: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Invalid '{}': {} at {}", param, message, location))]
+    InvalidSetting {
+        param: String,
+        message: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Invalid '{}': {} at {}", param, source, location))]
+    Language {
+        param: String,
+        source: LanguageError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("{} at {}", source, location))]
+    Glossary {
+        source: GlossaryError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Invalid protection patterns: {} at {}", message, location))]
+    InvalidProtection {
+        message: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Translation lost protected spans: {} at {}", placeholders, location))]
+    PlaceholdersLost {
+        placeholders: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Device unavailable: {} at {}", message, location))]
+    DeviceUnavailable {
+        message: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Alias cycle {} at {}", chain, location))]
+    AliasCycle {
+        chain: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Failed to reload configuration at {}: {}", location, source))]
+    ReloadError {
+        source: config::ConfigError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+    #[snafu(display("Reloaded configuration rejected at {}: {}", location, source))]
+    InvalidConfig {
+        source: ValidationError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+}
+
+/// Outcome of applying a new configuration to a running [`ModelManager`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReloadSummary {
+    /// Loaded models that were kept because their spec did not change
+    pub kept: Vec<String>,
+    /// Loaded models that were unloaded because their spec changed or was removed
+    pub unloaded: Vec<String>,
 }
 
 pub struct ModelManager {
-    translators: Arc<RwLock<HashMap<String, Arc<Translator<AutoTokenizer>>>>>,
-    config: AppConfig,
+    translators: Arc<RwLock<HashMap<String, Arc<LoadedModel>>>>,
+    config: std::sync::RwLock<Arc<AppConfig>>,
+    ready: AtomicBool,
+    /// Set while the last preload failed, so that loading the missing models by other
+    /// means makes the manager ready
+    preload_failed: AtomicBool,
+    cache: TranslationCache,
+    memory: std::sync::RwLock<Arc<TranslationMemory>>,
+    glossaries: GlossaryStore,
 }
 
 impl ModelManager {
     pub fn new(config: AppConfig) -> Self {
+        // Without preloaded models there is nothing to wait for
+        let ready = !config.models.values().any(|spec| spec.preload);
         Self {
             translators: Arc::new(RwLock::new(HashMap::new())),
-            config,
+            cache: TranslationCache::new(&config.cache),
+            memory: std::sync::RwLock::new(Arc::new(open_memory(&config))),
+            glossaries: GlossaryStore::new(&config),
+            config: std::sync::RwLock::new(Arc::new(config)),
+            ready: AtomicBool::new(ready),
+            preload_failed: AtomicBool::new(false),
         }
     }
 
-    fn parse_device(device: &str) -> Device {
-        match device.to_lowercase().as_str() {
-            "cuda" | "gpu" => Device::CUDA,
+    /// Snapshot of the configuration currently in service.
+    pub fn config(&self) -> Arc<AppConfig> {
+        self.config
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// The translation cache in front of [`ModelManager::generate`].
+    pub fn cache(&self) -> &TranslationCache {
+        &self.cache
+    }
+
+    /// Snapshot of the translation memory currently in service.
+    pub fn memory(&self) -> Arc<TranslationMemory> {
+        self.memory
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// The glossaries selectable with `glossary_id`.
+    pub fn glossaries(&self) -> &GlossaryStore {
+        &self.glossaries
+    }
+
+    /// Whether every model marked with `preload` has been loaded and warmed up.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    /// Loads and warms up all models marked with `preload`, then marks the manager as ready.
+    ///
+    /// Models are loaded one after another to avoid a memory spike at startup, and models
+    /// that are already loaded are skipped. On failure the manager stays not ready so
+    /// orchestrators keep traffic away, until a retry, a reload or a request loads the
+    /// missing models.
+    pub async fn preload(&self) -> Result<(), ModelError> {
+        let result = self.preload_models().await;
+        self.preload_failed
+            .store(result.is_err(), Ordering::Release);
+        result
+    }
+
+    async fn preload_models(&self) -> Result<(), ModelError> {
+        let config = self.config();
+        let mut names: Vec<&String> = config
+            .models
+            .iter()
+            .filter(|(_, spec)| spec.preload)
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+
+        for name in names {
+            if self.translators.read().await.contains_key(name) {
+                continue;
+            }
+
+            let start = std::time::Instant::now();
+            self.load_model(name).await?;
+            tracing::info!(
+                "Preloaded model '{}' in {:.2} s",
+                name,
+                start.elapsed().as_secs_f64()
+            );
+
+            // Warmup strings have no place in the cache or the translation memory
+            let warmup_inputs = &config.models[name].warmup_inputs;
+            if !warmup_inputs.is_empty() {
+                let start = std::time::Instant::now();
+                let params = GenerationParams {
+                    use_cache: Some(false),
+                    use_memory: Some(false),
+                    ..Default::default()
+                };
+                self.generate(name, warmup_inputs.clone(), params).await?;
+                tracing::info!(
+                    "Warmed up model '{}' with {} inputs in {:.2} ms",
+                    name,
+                    warmup_inputs.len(),
+                    start.elapsed().as_secs_f64() * 1000.0
+                );
+            }
+        }
+
+        self.ready.store(true, Ordering::Release);
+        tracing::info!("All preloaded models are ready");
+        Ok(())
+    }
+
+    /// After a failed preload, marks the manager as ready once every model `config`
+    /// preloads is loaded after all.
+    fn recover_ready(&self, config: &AppConfig, translators: &HashMap<String, Arc<LoadedModel>>) {
+        if self.preload_failed.load(Ordering::Acquire)
+            && config
+                .models
+                .iter()
+                .all(|(name, spec)| !spec.preload || translators.contains_key(name))
+        {
+            self.preload_failed.store(false, Ordering::Release);
+            self.ready.store(true, Ordering::Release);
+            tracing::info!("All preloaded models are ready");
+        }
+    }
+
+    /// Re-reads the configuration from its original sources and applies it.
+    pub async fn reload(&self) -> Result<ReloadSummary, ModelError> {
+        let config = self.config().reload().context(ReloadSnafu)?;
+        for warning in config.validate(&[]).context(InvalidConfigSnafu)? {
+            tracing::warn!("{}", warning);
+        }
+        Ok(self.apply_config(config).await)
+    }
+
+    /// Swaps in a new configuration.
+    ///
+    /// Loaded models whose effective spec is unchanged stay loaded; the others are dropped
+    /// and will be loaded again from the new spec on demand. Requests already running keep
+    /// their translator until they finish.
+    pub async fn apply_config(&self, config: AppConfig) -> ReloadSummary {
+        // Holding the write lock keeps concurrent loads from caching a model from the old spec
+        let mut translators = self.translators.write().await;
+        let old = self.config();
+
+        let mut summary = ReloadSummary::default();
+        translators.retain(|name, _| {
+            let spec = old.effective_spec(name);
+            let keep = spec.is_some() && spec == config.effective_spec(name);
+            if keep {
+                summary.kept.push(name.clone());
+            } else {
+                summary.unloaded.push(name.clone());
+            }
+            keep
+        });
+        summary.kept.sort();
+        summary.unloaded.sort();
+
+        if old.server.host != config.server.host || old.server.port != config.server.port {
+            tracing::warn!("Server address changes only take effect after a restart");
+        }
+
+        // Cached translations may stem from models or settings that just changed
+        self.cache.reconfigure(&config.cache);
+
+        if old.translation_memory.path != config.translation_memory.path {
+            *self.memory.write().unwrap_or_else(PoisonError::into_inner) =
+                Arc::new(open_memory(&config));
+        }
+        self.glossaries.load_configured(&config);
+
+        self.recover_ready(&config, &translators);
+        *self.config.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(config);
+        summary
+    }
+
+    fn parse_compute_type(compute_type: &str) -> ComputeType {
+        match compute_type.to_lowercase().as_str() {
+            "auto" => ComputeType::AUTO,
+            "float32" => ComputeType::FLOAT32,
+            "int8" => ComputeType::INT8,
+            "int8_float32" => ComputeType::INT8_FLOAT32,
+            "int8_float16" => ComputeType::INT8_FLOAT16,
+            "int8_bfloat16" => ComputeType::INT8_BFLOAT16,
+            "int16" => ComputeType::INT16,
+            "float16" => ComputeType::FLOAT16,
+            "bfloat16" => ComputeType::BFLOAT16,
             other => {
-                if other != "cpu" {
-                    tracing::warn!("Unknown device '{}', defaulting to CPU", other);
+                if other != "default" {
+                    tracing::warn!("Unknown compute type '{}', using the model's own", other);
                 }
-                Device::CPU
+                ComputeType::DEFAULT
             }
         }
     }
 
+    /// Resolves aliases and the `"default"` name to the configured model name.
+    ///
+    /// Falls back to `name` itself if the aliases form a cycle; use
+    /// [`try_resolve_model_name`](Self::try_resolve_model_name) to see that error.
     pub fn resolve_model_name(&self, name: &str) -> String {
-        // 1. Check if it's an alias
-        if let Some(real_name) = self.config.aliases.get(name) {
-            return real_name.clone();
-        }
-        // 2. Check if it's the default request
-        if name == "default" || name.is_empty() {
-            return self.config.default_model.clone();
-        }
-        // 3. Return as is
-        name.to_string()
+        self.try_resolve_model_name(name)
+            .unwrap_or_else(|_| name.to_string())
+    }
+
+    /// Resolves aliases and the `"default"` name to the configured model name.
+    pub fn try_resolve_model_name(&self, name: &str) -> Result<String, ModelError> {
+        self.config().resolve_model_name(name).map_err(|chain| {
+            AliasCycleSnafu {
+                chain: chain.join(" -> "),
+            }
+            .build()
+        })
     }
 
     pub async fn load_model(&self, name: &str) -> Result<(), ModelError> {
-        let resolved_name = self.resolve_model_name(name);
+        let resolved_name = self.try_resolve_model_name(name)?;
 
         // Check if already loaded
         if self.translators.read().await.contains_key(&resolved_name) {
             return Ok(());
         }
 
+        self.load(&resolved_name).await.map(|_| ())
+    }
+
+    async fn load(&self, resolved_name: &str) -> Result<Arc<LoadedModel>, ModelError> {
         // Get config
-        let spec = self
-            .config
-            .models
-            .get(&resolved_name)
+        let config = self.config();
+        let spec = config
+            .effective_spec(resolved_name)
             .context(ConfigNotFoundSnafu {
-                model_name: resolved_name.clone(),
+                model_name: resolved_name,
             })?;
 
         let model_path = PathBuf::from(&spec.path);
 
-        // Resolve device settings
-        let device_str = spec.device.as_ref().unwrap_or(&self.config.device);
-        let device = Self::parse_device(device_str);
+        // Resolve device settings, checking for GPUs only when CUDA may be used
+        let device_str = spec.device.as_ref().unwrap_or(&config.device);
+        let device_spec = device_str
+            .parse::<DeviceSpec>()
+            .map_err(|message| DeviceUnavailableSnafu { message }.build())?;
+        let cuda_devices = match device_spec {
+            DeviceSpec::Cpu => None,
+            _ => device::cuda_device_count(),
+        };
+        let (device, device_indices) = device_spec
+            .resolve(
+                spec.device_indices
+                    .as_ref()
+                    .unwrap_or(&config.device_indices),
+                spec.allow_cpu_fallback.unwrap_or(config.allow_cpu_fallback),
+                cuda_devices,
+            )
+            .map_err(|message| DeviceUnavailableSnafu { message }.build())?;
 
-        let device_indices = spec
-            .device_indices
-            .as_ref()
-            .unwrap_or(&self.config.device_indices);
+        // Resolve replica settings
+        let compute_type_str = spec.compute_type.as_ref().unwrap_or(&config.compute_type);
+        let compute_type = Self::parse_compute_type(compute_type_str);
+        let num_threads_per_replica = spec
+            .num_threads_per_replica
+            .unwrap_or(config.num_threads_per_replica);
+        let max_queued_batches = spec.max_queued_batches.unwrap_or(config.max_queued_batches);
+        let cpu_core_offset = spec.cpu_core_offset.unwrap_or(config.cpu_core_offset);
 
         tracing::info!(
-            "Loading model '{}' on device: {:?} (config: '{}'), indices: {:?}",
+            "Loading model '{}' on device: {:?} (config: '{}'), indices: {:?}, compute type: {:?}, threads per replica: {}, max queued batches: {}, cpu core offset: {}",
             resolved_name,
             device,
             device_str,
-            device_indices
+            device_indices,
+            compute_type,
+            num_threads_per_replica,
+            max_queued_batches,
+            cpu_core_offset
         );
 
         // CTranslate2 loading is blocking
         let model_path_clone = model_path.clone();
         let ct2_config = Ct2Config {
             device,
-            device_indices: device_indices.clone(),
+            compute_type,
+            device_indices,
+            num_threads_per_replica,
+            max_queued_batches,
+            cpu_core_offset,
             ..Default::default()
         };
 
         let tokenizer_path = spec.tokenizer_path.as_ref().unwrap_or(&spec.path);
         let tokenizer = ct2rs::tokenizers::auto::Tokenizer::new(tokenizer_path)
+            .map(ModelTokenizer::new)
             .map_err(|e| anyhow::anyhow!("Failed to load tokenizer: {}", e))
             .context(LoadSnafu {
                 path: tokenizer_path,
             })?;
 
+        let translator_tokenizer = tokenizer.clone();
         let translator = tokio::task::spawn_blocking(move || {
-            Translator::with_tokenizer(model_path_clone, tokenizer, &ct2_config)
+            Translator::with_tokenizer(model_path_clone, translator_tokenizer, &ct2_config)
         })
         .await
         .map_err(|e| anyhow::anyhow!("Join error: {}", e))
@@ -146,98 +731,441 @@ impl ModelManager {
         .context(LoadSnafu {
             path: model_path.clone(),
         })?;
+        let model = Arc::new(LoadedModel {
+            translator,
+            tokenizer,
+        });
 
         let mut translators = self.translators.write().await;
-        translators.insert(resolved_name, Arc::new(translator));
-        Ok(())
+        if self.config().effective_spec(resolved_name).as_ref() != Some(&spec) {
+            // A reload replaced the spec while we were loading; serve this request but don't cache
+            tracing::warn!(
+                "Configuration of model '{}' changed while loading, not caching it",
+                resolved_name
+            );
+            return Ok(model);
+        }
+        let model = translators
+            .entry(resolved_name.to_string())
+            .or_insert(model)
+            .clone();
+        self.recover_ready(&self.config(), &translators);
+        Ok(model)
     }
 
-    pub async fn get_translator(
-        &self,
-        name: &str,
-    ) -> Result<Arc<Translator<AutoTokenizer>>, ModelError> {
-        let resolved_name = self.resolve_model_name(name);
+    pub async fn get_model(&self, name: &str) -> Result<Arc<LoadedModel>, ModelError> {
+        let resolved_name = self.try_resolve_model_name(name)?;
 
         // 1. Check if already loaded
         {
             let translators = self.translators.read().await;
-            if let Some(translator) = translators.get(&resolved_name) {
-                return Ok(translator.clone());
+            if let Some(model) = translators.get(&resolved_name) {
+                return Ok(model.clone());
             }
         }
 
         // 2. Try to load if not loaded (Lazy loading)
         tracing::info!("Lazy loading model: {}", resolved_name);
-        self.load_model(&resolved_name).await?;
-
-        // 3. Get after load
-        let translators = self.translators.read().await;
-        translators
-            .get(&resolved_name)
-            .cloned()
-            .context(NotFoundSnafu {
-                model_name: resolved_name,
-            })
+        self.load(&resolved_name).await
     }
 
+    /// Translates `prompts` with the model or alias `name`, returning only the texts.
     pub async fn generate(
         &self,
         name: &str,
         prompts: Vec<String>,
         params: GenerationParams,
     ) -> Result<Vec<String>, ModelError> {
-        let resolved_name = self.resolve_model_name(name);
-        let translator = self.get_translator(name).await?;
-
-        // Resolve config
-        let model_spec = self.config.models.get(&resolved_name);
-
-        // 1. Target Lang
-        let target_lang = params
-            .target_lang
-            .or_else(|| model_spec.and_then(|m| m.target_lang.clone()))
-            .unwrap_or_else(|| self.config.target_lang.clone());
-
-        // 2. Beam Size
-        let beam_size = params
-            .beam_size
-            .or_else(|| model_spec.and_then(|m| m.beam_size))
-            .unwrap_or(self.config.beam_size);
-
-        // 3. Repetition Penalty
-        let repetition_penalty = params
-            .repetition_penalty
-            .or_else(|| model_spec.and_then(|m| m.repetition_penalty))
-            .unwrap_or(self.config.repetition_penalty);
-
-        // 4. No Repeat Ngram Size
-        let no_repeat_ngram_size = params
-            .no_repeat_ngram_size
-            .or_else(|| model_spec.and_then(|m| m.no_repeat_ngram_size))
-            .unwrap_or(self.config.no_repeat_ngram_size);
-
-        tokio::task::spawn_blocking(move || {
-            let options = TranslationOptions {
-                beam_size,
-                repetition_penalty,
-                no_repeat_ngram_size,
-                ..Default::default()
+        let translations = self.generate_detailed(name, prompts, params).await?;
+        Ok(translations.into_iter().map(|t| t.text).collect())
+    }
+
+    /// Translates `prompts` with the model or alias `name`, reporting where each
+    /// translation came from along with fuzzy translation memory matches.
+    ///
+    /// Fails if the settings ask for several target languages; see
+    /// [`ModelManager::generate_targets`].
+    pub async fn generate_detailed(
+        &self,
+        name: &str,
+        prompts: Vec<String>,
+        params: GenerationParams,
+    ) -> Result<Vec<Translation>, ModelError> {
+        let targets = self.translate(name, prompts, params, false).await?;
+        Ok(targets
+            .into_iter()
+            .next()
+            .map(|(_, translations)| translations)
+            .unwrap_or_default())
+    }
+
+    /// Translates `prompts` into every language of `target_langs` (or the single target
+    /// language) in one batch, returning the translations per language in the order given.
+    pub async fn generate_targets(
+        &self,
+        name: &str,
+        prompts: Vec<String>,
+        params: GenerationParams,
+    ) -> Result<Vec<(String, Vec<Translation>)>, ModelError> {
+        self.translate(name, prompts, params, true).await
+    }
+
+    async fn translate(
+        &self,
+        name: &str,
+        prompts: Vec<String>,
+        params: GenerationParams,
+        several_targets: bool,
+    ) -> Result<Vec<(String, Vec<Translation>)>, ModelError> {
+        let resolved_name = self.try_resolve_model_name(name)?;
+
+        // Resolve config. Precedence: request > alias > model spec > global defaults,
+        // where each level's preset sits right below the settings given at that level
+        let config = self.config();
+        let model_spec = config.models.get(&resolved_name);
+        let with_preset = |params: GenerationParams| {
+            config
+                .with_preset(params)
+                .map_err(|preset| PresetNotFoundSnafu { preset }.build())
+        };
+        let mut params = with_preset(params)?;
+        params.check_ranges()?;
+        let model_type = model_spec.map(|spec| spec.model_type.as_str());
+        if let Some(model_type) = model_type {
+            normalize_languages(&mut params, model_type)?;
+        }
+
+        // Limits apply to what the client asked for, not to the operator's own defaults,
+        // and are checked before a model gets loaded on the client's behalf
+        let mut limits = config.limits(&resolved_name);
+        if let (Some(model_type), Some(allowed)) = (model_type, &mut limits.allowed_target_langs) {
+            for allowed_lang in allowed.iter_mut() {
+                if let Ok(code) = lang::normalize(allowed_lang, model_type) {
+                    *allowed_lang = code;
+                }
+            }
+        }
+        limits.check(&prompts, &params)?;
+
+        let mut params = params
+            .or(&with_preset(config.alias_params(name))?)
+            .or(&with_preset(
+                model_spec
+                    .map(ModelSpec::generation_params)
+                    .unwrap_or_default(),
+            )?)
+            .or(&config.generation_defaults());
+
+        // OpenAI clients send a temperature by default, so sampling settings only count
+        // once sampling is on and must not change greedy decoding or split the cache
+        if params.sampling_topk == Some(1) {
+            params.sampling_topp = None;
+            params.sampling_temperature = None;
+            params.seed = None;
+        }
+
+        // `auto` stands for the language detected in the whole batch, in the model's codes
+        if params.source_lang.as_deref() == Some(detect::AUTO) {
+            let detection = detect::detect(&prompts.join("\n"));
+            let detection = match model_type {
+                Some(model_type) => detection.for_model(model_type),
+                None => detection,
             };
+            tracing::debug!(
+                "Detected source language {:?} ({:.2})",
+                detection.language,
+                detection.confidence
+            );
+            params.source_lang = detection.language;
+        }
+        // Codes from the configuration are given to the model in its own codes too
+        if let Some(model_type) = model_type {
+            normalize_languages(&mut params, model_type)?;
+        }
 
-            // Replicate the prefix for each prompt in the batch
-            let target_prefixes: Vec<Vec<String>> = std::iter::repeat(vec![target_lang.clone()])
-                .take(prompts.len())
-                .collect();
+        let mut targets: Vec<String> = Vec::new();
+        match params.target_langs.as_deref() {
+            Some(langs) if !langs.is_empty() => {
+                for lang in langs {
+                    if !targets.contains(lang) {
+                        targets.push(lang.clone());
+                    }
+                }
+            }
+            _ => targets.push(
+                params
+                    .target_lang
+                    .clone()
+                    .unwrap_or_else(|| config.target_lang.clone()),
+            ),
+        }
+        ensure!(
+            several_targets || targets.len() == 1,
+            LimitExceededSnafu {
+                param: "target_langs",
+                message: "only one target language is supported here",
+            }
+        );
+
+        // Long inputs are split into sentences, which go through memory, cache and model
+        // on their own and are joined again at the end
+        let inputs = prompts;
+        let segmented: Option<Vec<Segmented>> = (params.segment == Some(true)).then(|| {
+            let source_lang = params.source_lang.as_deref();
+            inputs
+                .iter()
+                .map(|input| segment::split(input, source_lang))
+                .collect()
+        });
+        let prompts = match &segmented {
+            Some(segmented) => segmented
+                .iter()
+                .flat_map(|s| s.sentences().iter().cloned())
+                .collect(),
+            None => inputs.clone(),
+        };
+
+        // Glossary terms are masked so the model copies them through, and replaced with
+        // their mandated translation afterwards
+        let glossary = match &params.glossary_id {
+            Some(id) => {
+                let glossary = self
+                    .glossaries
+                    .get(id)
+                    .ok_or_else(|| GlossaryError::NotFound { id: id.clone() })
+                    .context(GlossarySnafu)?;
+                for target_lang in &targets {
+                    glossary
+                        .check_languages(
+                            params.source_lang.as_deref(),
+                            target_lang,
+                            model_type.unwrap_or_default(),
+                        )
+                        .context(GlossarySnafu)?;
+                }
+                Some(glossary)
+            }
+            None => None,
+        };
+        // Placeholders, URLs and the like are masked the same way and put back verbatim
+        let protector = match params.protect.as_deref() {
+            Some(names) if !names.is_empty() => Some(
+                Protector::new(names, &config.protect_patterns)
+                    .map_err(|message| InvalidProtectionSnafu { message }.build())?,
+            ),
+            _ => None,
+        };
+        let masks: Vec<Option<(Masked, Vec<Option<Term>>)>> = prompts
+            .iter()
+            .map(|prompt| mask_input(prompt, protector.as_ref(), glossary.as_deref()))
+            .collect();
+
+        // Item `i` translates prompt `i % count` into target language `i / count`. Serve
+        // what we can from the translation memory, then from the cache, and translate
+        // the rest
+        let count = prompts.len();
+        let items = count * targets.len();
+        let mut outputs: Vec<Option<Translation>> = vec![None; items];
+        let mut alternatives: Vec<Vec<MemoryMatch>> = vec![Vec::new(); items];
+        let memory = self.memory();
+        let memory_config = &config.translation_memory;
+        if memory.is_enabled()
+            && params.use_memory != Some(false)
+            && let Some(source_lang) = params.source_lang.clone()
+        {
+            // Fuzzy matching scans the whole language pair, so it stays off the runtime
+            let (threshold, limit) = (
+                memory_config.fuzzy_threshold,
+                memory_config.max_alternatives + 1,
+            );
+            let (targets, prompts) = (targets.clone(), prompts.clone());
+            let found = tokio::task::spawn_blocking(move || {
+                (0..items)
+                    .map(|i| {
+                        memory.search(
+                            &source_lang,
+                            &targets[i / count],
+                            &prompts[i % count],
+                            threshold,
+                            limit,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("Join error: {}", e))
+            .context(InferenceSnafu)?;
+            for (i, mut matches) in found.into_iter().enumerate() {
+                if matches
+                    .first()
+                    .is_some_and(|best| best.score >= 1.0 || memory_config.apply_fuzzy)
+                {
+                    let best = matches.remove(0);
+                    outputs[i] = Some(Translation {
+                        text: best.target,
+                        origin: Origin::Memory,
+                        alternatives: matches,
+                        glossary_terms: Vec::new(),
+                        lost_placeholders: Vec::new(),
+                    });
+                } else {
+                    matches.truncate(memory_config.max_alternatives);
+                    alternatives[i] = matches;
+                }
+            }
+        }
+
+        // Sampled translations differ between runs, and a seed can't pin them down while
+        // CTranslate2's is process-wide, so they are never cached
+        let sampling = params.sampling_topk != Some(1);
+        let cache_keys: Option<Vec<CacheKey>> =
+            (self.cache.is_enabled() && params.use_cache != Some(false) && !sampling).then(|| {
+                let target_params: Vec<GenerationParams> = targets
+                    .iter()
+                    .map(|target_lang| GenerationParams {
+                        target_lang: Some(target_lang.clone()),
+                        target_langs: None,
+                        ..params.clone()
+                    })
+                    .collect();
+                (0..items)
+                    .map(|i| {
+                        CacheKey::new(
+                            &resolved_name,
+                            &prompts[i % count],
+                            &target_params[i / count],
+                        )
+                    })
+                    .collect()
+            });
+        if let Some(keys) = &cache_keys {
+            for (i, key) in keys.iter().enumerate() {
+                if outputs[i].is_none()
+                    && let Some(text) = self.cache.get(key)
+                {
+                    outputs[i] = Some(Translation {
+                        text,
+                        origin: Origin::Cache,
+                        alternatives: std::mem::take(&mut alternatives[i]),
+                        glossary_terms: masks[i % count]
+                            .as_ref()
+                            .map(|(_, tags)| applied_terms(tags, &[]))
+                            .unwrap_or_default(),
+                        lost_placeholders: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        let (prompts, target_langs): (Vec<String>, Vec<String>) = outputs
+            .iter()
+            .enumerate()
+            .filter(|(_, output)| output.is_none())
+            .map(|(i, _)| {
+                let prompt = match &masks[i % count] {
+                    Some((masked, _)) => masked.text.clone(),
+                    None => prompts[i % count].clone(),
+                };
+                (prompt, targets[i / count].clone())
+            })
+            .unzip();
+        if prompts.is_empty() {
+            return Ok(group_targets(targets, outputs, segmented.as_deref()));
+        }
+
+        // Token limits apply to whole inputs, as sent before segmentation and masking
+        let model = self.get_model(&resolved_name).await?;
+        if let Some(max) = limits.max_input_tokens {
+            for input in &inputs {
+                let tokens = model.tokenizer.encode(input).context(InferenceSnafu)?.len();
+                ensure!(
+                    tokens <= max,
+                    LimitExceededSnafu {
+                        param: "messages",
+                        message: format!("input of {} tokens exceeds the limit of {}", tokens, max),
+                    }
+                );
+            }
+        }
+
+        // Banned phrases are matched on tokens
+        let suppress_sequences = params
+            .suppress_sequences
+            .as_ref()
+            .unwrap_or(&config.suppress_sequences)
+            .iter()
+            .map(|phrase| model.tokenizer.encode_phrase(phrase))
+            .filter(|tokens| !tokens.as_ref().is_ok_and(Vec::is_empty))
+            .collect::<anyhow::Result<Vec<Vec<String>>>>()
+            .context(InferenceSnafu)?;
+
+        let options = TranslationOptions {
+            beam_size: params.beam_size.unwrap_or(config.beam_size),
+            patience: params.patience.unwrap_or(config.patience),
+            length_penalty: params.length_penalty.unwrap_or(config.length_penalty),
+            coverage_penalty: params.coverage_penalty.unwrap_or(config.coverage_penalty),
+            repetition_penalty: params
+                .repetition_penalty
+                .unwrap_or(config.repetition_penalty),
+            no_repeat_ngram_size: params
+                .no_repeat_ngram_size
+                .unwrap_or(config.no_repeat_ngram_size),
+            disable_unk: params.disable_unk.unwrap_or(config.disable_unk),
+            suppress_sequences,
+            max_input_length: params.max_input_length.unwrap_or(config.max_input_length),
+            min_decoding_length: params
+                .min_decoding_length
+                .unwrap_or(config.min_decoding_length),
+            max_decoding_length: params
+                .max_decoding_length
+                .unwrap_or(config.max_decoding_length),
+            replace_unknowns: params.replace_unknowns.unwrap_or(config.replace_unknowns),
+            sampling_topk: params.sampling_topk.unwrap_or(config.sampling_topk),
+            sampling_topp: params.sampling_topp.unwrap_or(config.sampling_topp),
+            sampling_temperature: params
+                .sampling_temperature
+                .unwrap_or(config.sampling_temperature),
+            ..Default::default()
+        };
+        let seed = params.seed.filter(|_| sampling);
+
+        // Multilingual encoders expect the source language as their first token
+        let sources: Vec<String> = match &params.source_lang {
+            Some(source_lang)
+                if model_spec.is_some_and(|m| tokenizer::uses_source_lang_token(&m.model_type)) =>
+            {
+                prompts
+                    .iter()
+                    .map(|p| tokenizer::tag_source_lang(p, source_lang))
+                    .collect()
+            }
+            _ => prompts.clone(),
+        };
+
+        let texts = tokio::task::spawn_blocking(move || {
+            // Each input starts its output with its own target language
+            let target_prefixes: Vec<Vec<String>> =
+                target_langs.into_iter().map(|lang| vec![lang]).collect();
+
+            // The seed is process-wide in CTranslate2, so concurrent sampling requests
+            // make it best effort
+            if let Some(seed) = seed {
+                ct2rs::set_random_seed(seed);
+            }
 
             let start = std::time::Instant::now();
-            let result = translator
-                .translate_batch_with_target_prefix(&prompts, &target_prefixes, &options, None);
+            let result = model.translator.translate_batch_with_target_prefix(
+                &sources,
+                &target_prefixes,
+                &options,
+                None,
+            );
             let duration = start.elapsed();
 
             match result {
                 Ok(results) => {
                     let texts: Vec<String> = results.iter().map(|(s, _)| s.clone()).collect();
-                    
+
                     // Log stats similar to llama.cpp
                     let total_input_chars: usize = prompts.iter().map(|p| p.len()).sum();
                     let total_output_chars: usize = texts.iter().map(|t| t.len()).sum();
@@ -264,6 +1192,206 @@ impl ModelManager {
         .await
         .map_err(|e| anyhow::anyhow!("Join error: {}", e))
         .context(InferenceSnafu)?
-        .context(InferenceSnafu)
+        .context(InferenceSnafu)?;
+
+        // Fill the gaps left by memory and cache misses, in order
+        let mut texts = texts.into_iter();
+        for (i, output) in outputs.iter_mut().enumerate() {
+            if output.is_none() {
+                let text = texts.next().unwrap_or_default();
+                let (text, glossary_terms, lost_placeholders, complete) = match &masks[i % count] {
+                    Some((masked, tags)) => {
+                        let restored = masked.restore(&text);
+                        let lost_placeholders: Vec<String> = restored
+                            .lost
+                            .iter()
+                            .filter(|&&j| tags[j].is_none())
+                            .map(|&j| masked.replacements[j].clone())
+                            .collect();
+                        let lost_terms = restored.lost.len() - lost_placeholders.len();
+                        if lost_terms > 0 {
+                            tracing::warn!(
+                                "Model dropped {} glossary terms from input {}",
+                                lost_terms,
+                                i % count
+                            );
+                        }
+                        if !lost_placeholders.is_empty() {
+                            ensure!(
+                                params.on_lost_placeholder != Some(LostPlaceholders::Fail),
+                                PlaceholdersLostSnafu {
+                                    placeholders: lost_placeholders.join(", "),
+                                }
+                            );
+                            tracing::warn!(
+                                "Model dropped protected spans from input {}: {}",
+                                i % count,
+                                lost_placeholders.join(", ")
+                            );
+                        }
+                        (
+                            restored.text,
+                            applied_terms(tags, &restored.lost),
+                            lost_placeholders,
+                            restored.lost.is_empty(),
+                        )
+                    }
+                    None => (text, Vec::new(), Vec::new(), true),
+                };
+                // Only complete translations are cached, so a retry gets another chance
+                if complete && let Some(keys) = &cache_keys {
+                    self.cache.insert(keys[i].clone(), text.clone());
+                }
+                *output = Some(Translation {
+                    text,
+                    origin: Origin::Model,
+                    alternatives: std::mem::take(&mut alternatives[i]),
+                    glossary_terms,
+                    lost_placeholders,
+                });
+            }
+        }
+        Ok(group_targets(targets, outputs, segmented.as_deref()))
     }
 }
+
+/// Masks the protected spans and glossary terms of `prompt`, tagging each placeholder
+/// with its glossary term, or `None` for protected spans.
+fn mask_input(
+    prompt: &str,
+    protector: Option<&Protector>,
+    glossary: Option<&Glossary>,
+) -> Option<(Masked, Vec<Option<Term>>)> {
+    if protector.is_none() && glossary.is_none() {
+        return None;
+    }
+    let mut spans: Vec<(Range<usize>, String, Option<Term>)> = protector
+        .map(|p| p.find(prompt))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|range| (range.clone(), prompt[range].to_string(), None))
+        .collect();
+    if let Some(glossary) = glossary {
+        spans.extend(
+            glossary
+                .find(prompt)
+                .into_iter()
+                .map(|(range, term)| (range, term.target.clone(), Some(term))),
+        );
+    }
+    // Of spans starting at the same place, protected ones win over glossary terms
+    spans.sort_by_key(|(range, _, term)| (range.start, term.is_some()));
+    Some(protect::mask(prompt, spans))
+}
+
+/// Glossary terms among the placeholder `tags` whose placeholder was not `lost`, in
+/// order and without duplicates.
+fn applied_terms(tags: &[Option<Term>], lost: &[usize]) -> Vec<Term> {
+    let mut applied: Vec<Term> = Vec::new();
+    for (i, term) in tags.iter().enumerate() {
+        if let Some(term) = term
+            && !lost.contains(&i)
+            && !applied.contains(term)
+        {
+            applied.push(term.clone());
+        }
+    }
+    applied
+}
+
+/// Joins the translated sentences of `segmented` inputs into one translation per input.
+/// Memory alternatives are only kept for inputs of a single sentence.
+fn join_sentences(
+    translations: Vec<Translation>,
+    segmented: Option<&[Segmented]>,
+) -> Vec<Translation> {
+    let Some(segmented) = segmented else {
+        return translations;
+    };
+    let mut translations = translations.into_iter();
+    segmented
+        .iter()
+        .map(|input| {
+            let sentences: Vec<Translation> = translations
+                .by_ref()
+                .take(input.sentences().len())
+                .collect();
+            let texts: Vec<&str> = sentences.iter().map(|t| t.text.as_str()).collect();
+            let mut joined = Translation {
+                text: input.join(&texts),
+                origin: sentences.first().map_or(Origin::Model, |t| t.origin),
+                alternatives: Vec::new(),
+                glossary_terms: Vec::new(),
+                lost_placeholders: Vec::new(),
+            };
+            let single = sentences.len() == 1;
+            for sentence in sentences {
+                if sentence.origin != joined.origin {
+                    joined.origin = Origin::Model;
+                }
+                if single {
+                    joined.alternatives = sentence.alternatives;
+                }
+                for term in sentence.glossary_terms {
+                    if !joined.glossary_terms.contains(&term) {
+                        joined.glossary_terms.push(term);
+                    }
+                }
+                joined.lost_placeholders.extend(sentence.lost_placeholders);
+            }
+            joined
+        })
+        .collect()
+}
+
+/// Rewrites the language codes of `params`, in any code system [`lang::normalize`]
+/// understands, into those of `model_type` models.
+fn normalize_languages(params: &mut GenerationParams, model_type: &str) -> Result<(), ModelError> {
+    let normalize = |param: &str, code: &str| {
+        lang::normalize(code, model_type).context(LanguageSnafu { param })
+    };
+    if let Some(code) = params.source_lang.as_deref()
+        && code != detect::AUTO
+    {
+        params.source_lang = Some(normalize("source_lang", code)?);
+    }
+    if let Some(code) = params.target_lang.as_deref() {
+        params.target_lang = Some(normalize("target_lang", code)?);
+    }
+    if let Some(codes) = params.target_langs.as_deref() {
+        params.target_langs = Some(
+            codes
+                .iter()
+                .map(|code| normalize("target_langs", code))
+                .collect::<Result<_, _>>()?,
+        );
+    }
+    Ok(())
+}
+
+/// Splits the translations of all items into one list per target language, joining
+/// the sentences of `segmented` inputs.
+fn group_targets(
+    targets: Vec<String>,
+    outputs: Vec<Option<Translation>>,
+    segmented: Option<&[Segmented]>,
+) -> Vec<(String, Vec<Translation>)> {
+    let count = outputs.len() / targets.len();
+    let mut outputs = outputs.into_iter().flatten();
+    targets
+        .into_iter()
+        .map(|target_lang| {
+            let translations = outputs.by_ref().take(count).collect();
+            (target_lang, join_sentences(translations, segmented))
+        })
+        .collect()
+}
+
+/// Opens the configured translation memory, running without one if it can't be read
+/// rather than risking to overwrite it.
+fn open_memory(config: &AppConfig) -> TranslationMemory {
+    TranslationMemory::open(&config.translation_memory).unwrap_or_else(|e| {
+        tracing::error!("Translation memory disabled: {}", e);
+        TranslationMemory::default()
+    })
+}
@@ -0,0 +1,227 @@
+//! Masking of spans that must come through translation unchanged.
+//!
+//! Spans are replaced by numbered placeholders such as `[[0]]`, which models copy
+//! through, and swapped back for their replacement afterwards. Besides glossary terms,
+//! this protects placeholders, URLs and code matched by [`Protector`] patterns.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// Placeholders as they come back from the model, which may space them out. Indices are
+/// capped at six digits, so numbers in the input cannot push ours past `usize`.
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\[\s*\[\s*(\d{1,6})\s*\]\s*\]").expect("valid placeholder regex")
+});
+
+/// Placeholder indices stay below this, within the digits [`PLACEHOLDER`] matches.
+const MAX_INDEX: usize = 1_000_000;
+
+/// Built-in protection patterns, selectable by name.
+pub const PRESETS: &[(&str, &str)] = &[
+    // ICU MessageFormat arguments, including one level of nested plural/select branches
+    ("icu", r"\{[^{}]*(?:\{[^{}]*\}[^{}]*)*\}"),
+    // C/POSIX format specifiers, positional ones and Python's %(name)s
+    (
+        "printf",
+        r"%(?:\d+\$|\([A-Za-z_]\w*\))?[-+0#]*(?:\d+|\*)?(?:\.(?:\d+|\*))?(?:hh|h|ll|l|L|z|j|t)?[diouxXeEfFgGaAcsrpn@%]",
+    ),
+    // i18next interpolation, unescaped interpolation and nesting
+    ("i18next", r"\{\{-?\s*[^{}]+?\s*\}\}|\$t\([^)]*\)"),
+    (
+        "url",
+        r"\b(?:https?|ftp)://[^\s<>\x22']*[^\s<>\x22'.,;:!?)\]]",
+    ),
+    ("email", r"\b[\w.+-]+@[\w-]+(?:\.[\w-]+)+\b"),
+    // Markdown inline code
+    ("code", r"`[^`\n]+`"),
+];
+
+/// What happens when the model drops a protected placeholder.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LostPlaceholders {
+    /// Return the translation, listing the lost placeholders
+    #[default]
+    Warn,
+    /// Fail the request
+    Fail,
+}
+
+/// Finds the spans protected by a set of named patterns.
+#[derive(Debug, Clone, Default)]
+pub struct Protector {
+    patterns: Vec<Regex>,
+}
+
+impl Protector {
+    /// Compiles the patterns `names` select, each a [`PRESETS`] entry or a key of
+    /// `custom`. Returns a description of the first unknown name or invalid pattern.
+    pub fn new(names: &[String], custom: &HashMap<String, String>) -> Result<Self, String> {
+        let patterns = names
+            .iter()
+            .map(|name| {
+                let pattern = custom
+                    .get(name)
+                    .map(String::as_str)
+                    .or_else(|| {
+                        PRESETS
+                            .iter()
+                            .find(|(preset, _)| preset == name)
+                            .map(|(_, pattern)| *pattern)
+                    })
+                    .ok_or_else(|| format!("unknown protection pattern '{}'", name))?;
+                Regex::new(pattern).map_err(|e| format!("invalid pattern '{}': {}", name, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Byte ranges of the protected spans of `text`, sorted and without overlaps;
+    /// of spans starting at the same place the longest wins.
+    pub fn find(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = self
+            .patterns
+            .iter()
+            .flat_map(|pattern| pattern.find_iter(text).map(|m| m.range()))
+            .filter(|range| !range.is_empty())
+            .collect();
+        ranges.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+
+        let mut end = 0;
+        ranges.retain(|range| {
+            let keep = range.start >= end;
+            if keep {
+                end = range.end;
+            }
+            keep
+        });
+        ranges
+    }
+}
+
+/// The placeholder standing for span `index`.
+pub fn placeholder(index: usize) -> String {
+    format!("[[{}]]", index)
+}
+
+/// The placeholders in `text` with their index, including spaced-out ones like `[ [0] ]`.
+pub fn placeholders(text: &str) -> impl Iterator<Item = (Range<usize>, usize)> + '_ {
+    PLACEHOLDER.captures_iter(text).filter_map(|caps| {
+        let index = caps[1].parse().ok()?;
+        Some((caps.get(0)?.range(), index))
+    })
+}
+
+/// Text with spans replaced by placeholders.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Masked {
+    pub text: String,
+    /// What each placeholder is replaced with after translation, by index from `offset`
+    pub replacements: Vec<String>,
+    /// Index of the first placeholder, past those the input already contained
+    pub offset: usize,
+}
+
+/// A translation with its placeholders replaced.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Restored {
+    pub text: String,
+    /// Indices of the placeholders missing from the translation
+    pub lost: Vec<usize>,
+}
+
+/// Replaces `spans` of `text`, given as byte ranges with their replacement and a tag,
+/// returning the tag of each placeholder. Spans must be sorted by start; those
+/// overlapping an earlier span are left alone. Numbering starts after the placeholders
+/// `text` already holds, so those come back untouched.
+pub fn mask<T>(
+    text: &str,
+    spans: impl IntoIterator<Item = (Range<usize>, String, T)>,
+) -> (Masked, Vec<T>) {
+    let spans: Vec<_> = spans.into_iter().collect();
+    let mut masked = Masked {
+        offset: free_offset(text, spans.len()),
+        ..Default::default()
+    };
+    let mut tags = Vec::new();
+    let mut pos = 0;
+    for (range, replacement, tag) in spans {
+        if range.start < pos {
+            continue;
+        }
+        masked.text.push_str(&text[pos..range.start]);
+        masked
+            .text
+            .push_str(&placeholder(masked.offset + masked.replacements.len()));
+        masked.replacements.push(replacement);
+        tags.push(tag);
+        pos = range.end;
+    }
+    masked.text.push_str(&text[pos..]);
+    (masked, tags)
+}
+
+/// The first of `count` placeholder indices that `text` doesn't use: past its own
+/// placeholders, or in the first gap between them if that would go over [`MAX_INDEX`].
+fn free_offset(text: &str, count: usize) -> usize {
+    let taken: BTreeSet<usize> = placeholders(text).map(|(_, i)| i).collect();
+    let after = taken.last().map_or(0, |i| i + 1);
+    if after + count <= MAX_INDEX {
+        return after;
+    }
+    let mut start = 0;
+    for &i in &taken {
+        if i - start >= count {
+            break;
+        }
+        start = i + 1;
+    }
+    start
+}
+
+impl Masked {
+    /// Whether any span was masked.
+    pub fn is_empty(&self) -> bool {
+        self.replacements.is_empty()
+    }
+
+    /// Replaces the placeholders in `translated`, reporting those the model dropped.
+    pub fn restore(&self, translated: &str) -> Restored {
+        if self.is_empty() {
+            return Restored {
+                text: translated.to_string(),
+                lost: Vec::new(),
+            };
+        }
+
+        let mut seen = BTreeSet::new();
+        let text = PLACEHOLDER.replace_all(translated, |caps: &regex::Captures| {
+            let replacement = caps[1]
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| i.checked_sub(self.offset))
+                .and_then(|i| self.replacements.get(i).map(|r| (i, r)));
+            match replacement {
+                Some((i, replacement)) => {
+                    seen.insert(i);
+                    replacement.clone()
+                }
+                // Not one of ours, e.g. a placeholder the input already contained
+                None => caps[0].to_string(),
+            }
+        });
+        Restored {
+            text: text.into_owned(),
+            lost: (0..self.replacements.len())
+                .filter(|i| !seen.contains(i))
+                .collect(),
+        }
+    }
+}
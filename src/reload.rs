@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::model::{ModelError, ModelManager, ReloadSummary};
+
+/// How often the config file is checked for changes when `server.watch_config` is set.
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait before preloading again after a failed preload.
+pub const PRELOAD_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Preloads the configured models in the background, retrying until it succeeds.
+pub fn spawn_preload(manager: Arc<ModelManager>) {
+    tokio::spawn(async move {
+        while let Err(e) = manager.preload().await {
+            tracing::error!(
+                "Model preload failed, retrying in {} s: {}",
+                PRELOAD_RETRY_INTERVAL.as_secs(),
+                e
+            );
+            tokio::time::sleep(PRELOAD_RETRY_INTERVAL).await;
+        }
+    });
+}
+
+/// Reloads the configuration and preloads any newly configured models in the background.
+pub async fn reload(manager: &Arc<ModelManager>) -> Result<ReloadSummary, ModelError> {
+    let summary = manager.reload().await?;
+    tracing::info!(
+        "Configuration reloaded: kept {:?}, unloaded {:?}",
+        summary.kept,
+        summary.unloaded
+    );
+
+    spawn_preload(manager.clone());
+    Ok(summary)
+}
+
+/// Reloads the configuration whenever the process receives SIGHUP.
+#[cfg(unix)]
+pub fn spawn_signal_handler(manager: Arc<ModelManager>) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    tokio::spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                tracing::error!("Failed to install SIGHUP handler: {}", e);
+                return;
+            }
+        };
+
+        while hangup.recv().await.is_some() {
+            tracing::info!("Received SIGHUP, reloading configuration");
+            if let Err(e) = reload(&manager).await {
+                tracing::error!(
+                    "Configuration reload failed, keeping the current one: {}",
+                    e
+                );
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn spawn_signal_handler(_manager: Arc<ModelManager>) {}
+
+/// Polls the config file and reloads the configuration when its modification time changes.
+pub fn spawn_file_watcher(manager: Arc<ModelManager>, interval: Duration) {
+    tokio::spawn(async move {
+        let path = PathBuf::from(manager.config().config_path());
+        let mut last_modified = modified_time(&path);
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+            let modified = modified_time(&path);
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            tracing::info!("{} changed, reloading configuration", path.display());
+            if let Err(e) = reload(&manager).await {
+                tracing::error!(
+                    "Configuration reload failed, keeping the current one: {}",
+                    e
+                );
+            }
+        }
+    });
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
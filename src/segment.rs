@@ -0,0 +1,232 @@
+//! Sentence segmentation of long inputs.
+//!
+//! Models like NLLB were trained on single sentences and degrade, or truncate, on long
+//! inputs. Inputs are split into paragraphs at blank lines and into sentences at their
+//! final punctuation, so the sentences can be translated as one batch and joined again
+//! with the whitespace that separated them.
+
+use crate::lang;
+
+/// Ends a sentence when followed by whitespace and a word that may start one.
+const TERMINATORS: &[char] = &[
+    '.', '!', '?', '…', '‼', '⁇', '⁈', '⁉', '।', '॥', '؟', '۔', '։', '።', '။', '།',
+];
+/// Ends a sentence right away: CJK text has no spaces between sentences.
+const CJK_TERMINATORS: &[char] = &['。', '！', '？', '｡', '．'];
+/// Closing quotes and brackets that belong to the sentence before them.
+const CLOSERS: &[char] = &[
+    '"', '\'', '”', '’', '»', '›', ')', ']', '}', '」', '』', '）', '】', '〕', '〉', '》', '］',
+];
+
+/// Words that are followed by a period without ending a sentence, in lowercase and
+/// without their final period, by ISO 639-1 code.
+const ABBREVIATIONS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "mt", "vs", "etc", "e.g", "i.e",
+            "approx", "dept", "est", "fig", "inc", "ltd", "corp", "co", "jan", "feb", "mar", "apr",
+            "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec", "u.s", "a.m", "p.m",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "z.b", "bzw", "usw", "ca", "nr", "dr", "prof", "hr", "fr", "str", "vgl", "d.h", "u.a",
+            "evtl", "ggf", "inkl", "bspw", "bzgl", "abs", "s", "jh", "mio", "mrd", "sog",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "m", "mm", "mme", "mmes", "mlle", "dr", "pr", "me", "etc", "cf", "env", "p.ex", "ex",
+            "av", "bd", "chap", "vol", "no", "p",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "sr", "sra", "srta", "sres", "dr", "dra", "ud", "uds", "etc", "p.ej", "pág", "núm",
+            "av", "avda", "ej", "vol", "ee.uu",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "sig", "sig.ra", "sigg", "dott", "prof", "ing", "avv", "ecc", "pag", "es", "vol", "n",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "sr", "sra", "srta", "dr", "dra", "prof", "etc", "pág", "ex", "av", "nº", "vol",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "dhr", "mevr", "dr", "prof", "bijv", "enz", "o.a", "d.w.z", "nr", "blz", "ca", "m.b.t",
+        ],
+    ),
+    (
+        "ru",
+        &[
+            "г", "гг", "т.е", "т.д", "т.п", "т.к", "др", "пр", "им", "ул", "стр", "см", "ср",
+            "тыс", "млн", "млрд", "руб",
+        ],
+    ),
+];
+
+/// Languages writing ordinal numbers with a period, as in "am 3. Mai".
+const ORDINAL_PERIOD: &[&str] = &[
+    "de", "da", "fi", "hu", "is", "nb", "no", "cs", "sk", "sl", "hr",
+];
+
+/// Segmentation rules of a language.
+struct Rules {
+    abbreviations: &'static [&'static str],
+    ordinal_period: bool,
+}
+
+impl Rules {
+    /// Rules for `lang`, given as a BCP-47 tag or a model language code; English rules
+    /// apply when it is not known.
+    fn for_lang(lang: Option<&str>) -> Self {
+        let tag = lang.map(lang::to_bcp47).unwrap_or_default();
+        let primary = tag
+            .split(['-', '_'])
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let language = if ABBREVIATIONS.iter().any(|(code, _)| *code == primary) {
+            primary.as_str()
+        } else {
+            "en"
+        };
+        Self {
+            abbreviations: ABBREVIATIONS
+                .iter()
+                .find(|(code, _)| *code == language)
+                .map_or(&[][..], |(_, words)| *words),
+            ordinal_period: ORDINAL_PERIOD.contains(&primary.as_str()),
+        }
+    }
+
+    /// Whether the period after `word` does not end a sentence.
+    fn keeps_period(&self, word: &str) -> bool {
+        let word = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+        let mut chars = word.chars();
+        // Initials, as in "J. R. R. Tolkien"
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && c.is_uppercase()
+        {
+            return true;
+        }
+        if self.ordinal_period && !word.is_empty() && word.chars().all(|c| c.is_ascii_digit()) {
+            return true;
+        }
+        let word = word.to_lowercase();
+        self.abbreviations.contains(&word.as_str())
+    }
+}
+
+/// A text split into sentences, along with the whitespace around them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segmented {
+    sentences: Vec<String>,
+    /// Whitespace before each sentence and after the last one
+    gaps: Vec<String>,
+}
+
+impl Segmented {
+    pub fn sentences(&self) -> &[String] {
+        &self.sentences
+    }
+
+    /// Joins the `translations` of the sentences with the original whitespace.
+    pub fn join<S: AsRef<str>>(&self, translations: &[S]) -> String {
+        let mut text = self.gaps[0].clone();
+        for (translation, gap) in translations.iter().zip(&self.gaps[1..]) {
+            text.push_str(translation.as_ref());
+            text.push_str(gap);
+        }
+        text
+    }
+}
+
+/// Splits `text`, written in `lang`, into paragraphs and sentences.
+pub fn split(text: &str, lang: Option<&str>) -> Segmented {
+    let rules = Rules::for_lang(lang);
+    let end = text.trim_end().len();
+    let mut start = text.len() - text.trim_start().len();
+    let mut segmented = Segmented {
+        sentences: Vec::new(),
+        gaps: vec![text[..start].to_string()],
+    };
+
+    let mut pos = start;
+    while pos < end {
+        let rest = &text[pos..];
+        let c = rest.chars().next().unwrap_or_default();
+
+        // End of the sentence, if it ends here
+        let boundary = if c.is_whitespace() {
+            let run = whitespace(rest);
+            if rest[..run].matches('\n').count() >= 2 {
+                Some(pos)
+            } else {
+                pos += run;
+                continue;
+            }
+        } else if CJK_TERMINATORS.contains(&c) {
+            Some(pos + punctuation(rest, CJK_TERMINATORS))
+        } else if TERMINATORS.contains(&c) {
+            let after = pos + punctuation(rest, TERMINATORS);
+            let next = text[after..].trim_start().chars().next();
+            let single_period = text[pos..after].trim_end_matches(CLOSERS) == ".";
+            let word = text[start..pos]
+                .rsplit(char::is_whitespace)
+                .next()
+                .unwrap_or("");
+            let ends = whitespace(&text[after..]) > 0
+                && !next.is_some_and(char::is_lowercase)
+                && !(single_period && rules.keeps_period(word));
+            if !ends {
+                pos = after;
+                continue;
+            }
+            Some(after)
+        } else {
+            None
+        };
+
+        match boundary {
+            Some(boundary) => {
+                let next = boundary + whitespace(&text[boundary..]);
+                segmented.sentences.push(text[start..boundary].to_string());
+                segmented.gaps.push(text[boundary..next].to_string());
+                start = next;
+                pos = next;
+            }
+            None => pos += c.len_utf8(),
+        }
+    }
+
+    if start < end {
+        segmented.sentences.push(text[start..end].to_string());
+        segmented.gaps.push(text[end..].to_string());
+    }
+    segmented
+}
+
+/// Length of the whitespace `text` starts with.
+fn whitespace(text: &str) -> usize {
+    text.len() - text.trim_start().len()
+}
+
+/// Length of the run of `terminators` `text` starts with, along with the closing quotes
+/// and brackets after it.
+fn punctuation(text: &str, terminators: &[char]) -> usize {
+    let rest = text.trim_start_matches(terminators);
+    text.len() - rest.trim_start_matches(CLOSERS).len()
+}
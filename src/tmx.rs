@@ -0,0 +1,234 @@
+//! TMX 1.4 import into and export from the translation memory.
+//!
+//! Languages are written as BCP-47 tags in TMX files and mapped to the codes the target
+//! model expects (e.g. `fr-FR` to `fra_Latn` for NLLB) on import.
+
+use serde::Serialize;
+use snafu::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::lang;
+use crate::memory::{MemoryEntry, MemoryError, TranslationMemory};
+use crate::xml::{self, Token, XmlError};
+
+#[derive(Debug, Snafu)]
+pub enum TmxError {
+    #[snafu(display("Invalid TMX document: {}", source))]
+    Markup { source: XmlError },
+    #[snafu(display("Invalid TMX document: {}", message))]
+    Structure { message: String },
+}
+
+/// One `<tu>`: the same text in several languages, as (language, text) pairs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranslationUnit {
+    pub variants: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tmx {
+    /// `srclang` of the header; `None` for `*all*`
+    pub source_lang: Option<String>,
+    pub units: Vec<TranslationUnit>,
+}
+
+/// Elements holding native codes of the original format rather than text.
+const INLINE_CODES: &[&str] = &["bpt", "ept", "it", "ph", "ut"];
+
+/// Parses a TMX document. Inline codes (`<bpt>`, `<ph>`, ...) are dropped from segments,
+/// while the text of `<hi>` is kept.
+pub fn parse(input: &str) -> Result<Tmx, TmxError> {
+    let tokens = xml::tokenize(input, &[]).context(MarkupSnafu)?;
+    let mut tmx = Tmx::default();
+    let mut seen_root = false;
+    let mut unit: Option<TranslationUnit> = None;
+    let mut variant_lang: Option<String> = None;
+    let mut segment: Option<String> = None;
+    // Depth inside inline code elements, whose content is skipped
+    let mut code_depth = 0usize;
+
+    for token in &tokens {
+        match token {
+            Token::Start {
+                name, self_closing, ..
+            } => match *name {
+                "tmx" => seen_root = true,
+                "header" => {
+                    tmx.source_lang = token
+                        .attr("srclang")
+                        .filter(|lang| lang != "*all*")
+                        .map(|lang| lang.into_owned());
+                }
+                "tu" if !self_closing => unit = Some(TranslationUnit::default()),
+                "tuv" => {
+                    variant_lang = token
+                        .attr("xml:lang")
+                        .or_else(|| token.attr("lang"))
+                        .map(|lang| lang.into_owned());
+                    ensure!(
+                        variant_lang.is_some(),
+                        StructureSnafu {
+                            message: "<tuv> without xml:lang"
+                        }
+                    );
+                }
+                "seg" if !self_closing => segment = Some(String::new()),
+                name if !self_closing && segment.is_some() && INLINE_CODES.contains(&name) => {
+                    code_depth += 1;
+                }
+                _ => {}
+            },
+            Token::End { name, .. } => match *name {
+                "seg" => {
+                    let text = segment.take().unwrap_or_default();
+                    if let (Some(unit), Some(lang)) = (unit.as_mut(), variant_lang.as_ref()) {
+                        unit.variants.push((lang.clone(), text));
+                    }
+                    code_depth = 0;
+                }
+                "tuv" => variant_lang = None,
+                "tu" => tmx.units.extend(unit.take()),
+                name if segment.is_some() && INLINE_CODES.contains(&name) => {
+                    code_depth = code_depth.saturating_sub(1);
+                }
+                _ => {}
+            },
+            Token::Text(text) if code_depth == 0 => {
+                if let Some(segment) = segment.as_mut() {
+                    segment.push_str(&xml::unescape(text));
+                }
+            }
+            Token::CData { content, .. } if code_depth == 0 => {
+                if let Some(segment) = segment.as_mut() {
+                    segment.push_str(content);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ensure!(
+        seen_root,
+        StructureSnafu {
+            message: "missing <tmx> root element"
+        }
+    );
+    Ok(tmx)
+}
+
+/// Outcome of a TMX import.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ImportSummary {
+    /// Translations stored in the memory
+    pub imported: usize,
+    /// Translations left out because a side was empty or its language is unknown
+    pub skipped: usize,
+    /// Languages of the file that have no code for the model type
+    pub unmapped_languages: Vec<String>,
+}
+
+/// Stores every translation of `tmx` from its source language (the header `srclang`, or
+/// the first variant of each unit) into each other language, with language codes mapped
+/// for `model_type`.
+pub fn import(
+    memory: &TranslationMemory,
+    tmx: &Tmx,
+    model_type: &str,
+) -> Result<ImportSummary, MemoryError> {
+    let mut summary = ImportSummary::default();
+    let mut unmapped = BTreeSet::new();
+    let mut pairs: BTreeMap<(String, String), Vec<(String, String)>> = BTreeMap::new();
+
+    for unit in &tmx.units {
+        let source = match &tmx.source_lang {
+            Some(source_lang) => unit
+                .variants
+                .iter()
+                .find(|(lang, _)| lang.eq_ignore_ascii_case(source_lang)),
+            None => unit.variants.first(),
+        };
+        let Some((source_lang, source_text)) = source else {
+            summary.skipped += unit.variants.len();
+            continue;
+        };
+
+        for (target_lang, target_text) in &unit.variants {
+            if target_lang.eq_ignore_ascii_case(source_lang) {
+                continue;
+            }
+            let codes = [source_lang, target_lang].map(|lang| {
+                let code = lang::to_model_code(lang, model_type);
+                if code.is_none() {
+                    unmapped.insert(lang.clone());
+                }
+                code
+            });
+            match codes {
+                [Some(source_code), Some(target_code)]
+                    if !source_text.trim().is_empty() && !target_text.trim().is_empty() =>
+                {
+                    pairs
+                        .entry((source_code, target_code))
+                        .or_default()
+                        .push((source_text.clone(), target_text.clone()));
+                }
+                _ => summary.skipped += 1,
+            }
+        }
+    }
+
+    for ((source_lang, target_lang), translations) in pairs {
+        summary.imported += memory.add_many(&source_lang, &target_lang, &translations)?;
+    }
+    summary.unmapped_languages = unmapped.into_iter().collect();
+    Ok(summary)
+}
+
+/// Writes the entries of one language pair as a TMX 1.4 document, with the model codes
+/// turned back into BCP-47 tags.
+pub fn export(source_lang: &str, target_lang: &str, entries: &[MemoryEntry]) -> String {
+    let source_tag = xml::escape_attr(&lang::to_bcp47(source_lang));
+    let target_tag = xml::escape_attr(&lang::to_bcp47(target_lang));
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<tmx version=\"1.4\">\n");
+    out.push_str(&format!(
+        "  <header creationtool=\"{}\" creationtoolversion=\"{}\" segtype=\"sentence\" \
+         o-tmf=\"json\" adminlang=\"en\" srclang=\"{}\" datatype=\"plaintext\"/>\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        source_tag
+    ));
+    out.push_str("  <body>\n");
+    for entry in entries {
+        out.push_str(&format!("    <tu tuid=\"{}\">\n", entry.id));
+        for (tag, text) in [(&source_tag, &entry.source), (&target_tag, &entry.target)] {
+            out.push_str(&format!(
+                "      <tuv xml:lang=\"{}\"><seg>{}</seg></tuv>\n",
+                tag,
+                xml::escape_text(text)
+            ));
+        }
+        out.push_str("    </tu>\n");
+    }
+    out.push_str("  </body>\n</tmx>\n");
+    out
+}
+
+/// Imports a TMX document into the memory, mapping languages for `model_type`.
+pub fn import_str(
+    memory: &TranslationMemory,
+    input: &str,
+    model_type: &str,
+) -> Result<ImportSummary, ImportError> {
+    let tmx = parse(input).context(TmxSnafu)?;
+    import(memory, &tmx, model_type).context(MemorySnafu)
+}
+
+#[derive(Debug, Snafu)]
+pub enum ImportError {
+    #[snafu(display("{}", source))]
+    Tmx { source: TmxError },
+    #[snafu(display("{}", source))]
+    Memory { source: MemoryError },
+}
@@ -0,0 +1,102 @@
+use ct2rs::Tokenizer;
+use ct2rs::tokenizers::auto::Tokenizer as AutoTokenizer;
+use std::sync::Arc;
+
+/// Delimits an in-band source language tag at the start of a prompt.
+///
+/// `ct2rs` hands the translator's tokenizer nothing but the prompt text, so the source
+/// language travels inside it. The character is from the Private Use Area and never
+/// appears in real input.
+const SOURCE_LANG_DELIMITER: char = '\u{E000}';
+
+/// Model types whose encoder input starts with a source language token.
+pub fn uses_source_lang_token(model_type: &str) -> bool {
+    matches!(model_type, "nllb" | "m2m100")
+}
+
+/// Prefixes `text` with a source language tag that [`ModelTokenizer`] turns into the
+/// language token.
+pub fn tag_source_lang(text: &str, lang: &str) -> String {
+    format!(
+        "{}{}{}{}",
+        SOURCE_LANG_DELIMITER, lang, SOURCE_LANG_DELIMITER, text
+    )
+}
+
+fn split_source_lang(input: &str) -> Option<(&str, &str)> {
+    input
+        .strip_prefix(SOURCE_LANG_DELIMITER)?
+        .split_once(SOURCE_LANG_DELIMITER)
+}
+
+/// Whether a token is a language code token such as `fra_Latn` (NLLB) or `__fr__` (M2M-100).
+fn is_language_token(token: &str) -> bool {
+    let flores = token.len() == 8
+        && token.as_bytes()[3] == b'_'
+        && token[..3].bytes().all(|b| b.is_ascii_lowercase())
+        && token.as_bytes()[4].is_ascii_uppercase()
+        && token[5..].bytes().all(|b| b.is_ascii_lowercase());
+    let m2m = token.len() > 4
+        && token.starts_with("__")
+        && token.ends_with("__")
+        && token[2..token.len() - 2]
+            .bytes()
+            .all(|b| b.is_ascii_lowercase());
+    flores || m2m
+}
+
+/// Whether a token is added by the tokenizer rather than produced from the text.
+fn is_special_token(token: &str) -> bool {
+    matches!(token, "<s>" | "</s>" | "<pad>" | "<unk>") || is_language_token(token)
+}
+
+/// Wraps the model's own tokenizer to honour source language tags.
+///
+/// Tokenizers for multilingual models insert a fixed source language token (NLLB's
+/// `tokenizer.json` always starts with `eng_Latn`), so a tagged prompt has that token
+/// replaced, or inserted when the tokenizer adds none.
+///
+/// Clones share the underlying tokenizer, so the manager can keep one next to the
+/// translator that owns the other.
+#[derive(Clone)]
+pub struct ModelTokenizer {
+    inner: Arc<AutoTokenizer>,
+}
+
+impl ModelTokenizer {
+    pub fn new(inner: AutoTokenizer) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Tokens of `phrase` without the special and language tokens the tokenizer adds,
+    /// as needed to match the phrase inside a longer sequence.
+    pub fn encode_phrase(&self, phrase: &str) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .inner
+            .encode(phrase)?
+            .into_iter()
+            .filter(|token| !is_special_token(token))
+            .collect())
+    }
+}
+
+impl Tokenizer for ModelTokenizer {
+    fn encode(&self, input: &str) -> anyhow::Result<Vec<String>> {
+        let Some((lang, text)) = split_source_lang(input) else {
+            return self.inner.encode(input);
+        };
+
+        let mut tokens = self.inner.encode(text)?;
+        match tokens.first() {
+            Some(first) if is_language_token(first) => tokens[0] = lang.to_string(),
+            _ => tokens.insert(0, lang.to_string()),
+        }
+        Ok(tokens)
+    }
+
+    fn decode(&self, tokens: Vec<String>) -> anyhow::Result<String> {
+        self.inner.decode(tokens)
+    }
+}
@@ -0,0 +1,322 @@
+//! A small, lenient XML tokenizer shared by the TMX, XLIFF and HTML support.
+//!
+//! Tokens keep their raw text so documents can be written back unchanged apart from the
+//! parts that get translated.
+
+use snafu::prelude::*;
+use std::borrow::Cow;
+
+#[derive(Debug, Snafu, PartialEq)]
+#[snafu(display("Malformed markup at byte {}: {}", offset, message))]
+pub struct XmlError {
+    pub offset: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute<'a> {
+    pub name: &'a str,
+    /// Raw value without quotes and still escaped; `None` for HTML boolean attributes
+    pub value: Option<&'a str>,
+}
+
+impl Attribute<'_> {
+    /// The value with character references resolved.
+    pub fn decoded(&self) -> Cow<'_, str> {
+        unescape(self.value.unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token<'a> {
+    /// `<?xml ... ?>` and other processing instructions
+    Declaration(&'a str),
+    /// `<!DOCTYPE ...>`
+    Doctype(&'a str),
+    /// `<!-- ... -->`, delimiters included
+    Comment(&'a str),
+    /// A `<![CDATA[...]]>` section, whose content is taken literally
+    CData {
+        content: &'a str,
+        raw: &'a str,
+    },
+    Start {
+        name: &'a str,
+        attrs: Vec<Attribute<'a>>,
+        self_closing: bool,
+        raw: &'a str,
+    },
+    End {
+        name: &'a str,
+        raw: &'a str,
+    },
+    /// Raw text, still escaped
+    Text(&'a str),
+}
+
+impl<'a> Token<'a> {
+    /// The markup exactly as it appeared in the input.
+    pub fn raw(&self) -> &'a str {
+        match self {
+            Token::Declaration(raw) | Token::Doctype(raw) | Token::Comment(raw) => raw,
+            Token::Text(raw) => raw,
+            Token::CData { raw, .. } | Token::Start { raw, .. } | Token::End { raw, .. } => raw,
+        }
+    }
+
+    /// Value of the attribute `name` of a start tag, decoded.
+    pub fn attr(&self, name: &str) -> Option<Cow<'_, str>> {
+        match self {
+            Token::Start { attrs, .. } => attrs
+                .iter()
+                .find(|a| a.name.eq_ignore_ascii_case(name))
+                .map(Attribute::decoded),
+            _ => None,
+        }
+    }
+}
+
+/// Splits `input` into tokens. Content of the `raw_text` elements (e.g. HTML `script`)
+/// is returned as a single text token without looking for markup inside.
+pub fn tokenize<'a>(input: &'a str, raw_text: &[&str]) -> Result<Vec<Token<'a>>, XmlError> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let bytes = input.as_bytes();
+
+    while pos < input.len() {
+        if bytes[pos] != b'<' {
+            let end = input[pos..].find('<').map_or(input.len(), |i| pos + i);
+            tokens.push(Token::Text(&input[pos..end]));
+            pos = end;
+            continue;
+        }
+
+        let rest = &input[pos..];
+        if let Some(body) = rest.strip_prefix("<!--") {
+            let end = body.find("-->").context(XmlSnafu {
+                offset: pos,
+                message: "unterminated comment",
+            })?;
+            let len = "<!--".len() + end + "-->".len();
+            tokens.push(Token::Comment(&rest[..len]));
+            pos += len;
+        } else if let Some(body) = rest.strip_prefix("<![CDATA[") {
+            let end = body.find("]]>").context(XmlSnafu {
+                offset: pos,
+                message: "unterminated CDATA section",
+            })?;
+            let len = "<![CDATA[".len() + end + "]]>".len();
+            tokens.push(Token::CData {
+                content: &body[..end],
+                raw: &rest[..len],
+            });
+            pos += len;
+        } else if rest.starts_with("<?") {
+            let end = rest.find("?>").context(XmlSnafu {
+                offset: pos,
+                message: "unterminated processing instruction",
+            })?;
+            tokens.push(Token::Declaration(&rest[..end + 2]));
+            pos += end + 2;
+        } else if rest.starts_with("<!") {
+            let end = rest.find('>').context(XmlSnafu {
+                offset: pos,
+                message: "unterminated declaration",
+            })?;
+            tokens.push(Token::Doctype(&rest[..end + 1]));
+            pos += end + 1;
+        } else if let Some(body) = rest.strip_prefix("</") {
+            let end = body.find('>').context(XmlSnafu {
+                offset: pos,
+                message: "unterminated end tag",
+            })?;
+            tokens.push(Token::End {
+                name: body[..end].trim(),
+                raw: &rest[..end + 3],
+            });
+            pos += end + 3;
+        } else if rest[1..].starts_with(|c: char| c.is_alphabetic() || c == '_' || c == ':') {
+            let (token, len) = start_tag(rest).map_err(|message| XmlError {
+                offset: pos,
+                message,
+            })?;
+            pos += len;
+
+            // Raw text elements run until their end tag
+            if let Token::Start {
+                name,
+                self_closing: false,
+                ..
+            } = &token
+                && raw_text.iter().any(|r| r.eq_ignore_ascii_case(name))
+            {
+                let closing = format!("</{}", name.to_ascii_lowercase());
+                let end = input[pos..]
+                    .to_ascii_lowercase()
+                    .find(&closing)
+                    .map_or(input.len(), |i| pos + i);
+                tokens.push(token);
+                if end > pos {
+                    tokens.push(Token::Text(&input[pos..end]));
+                }
+                pos = end;
+                continue;
+            }
+            tokens.push(token);
+        } else {
+            // A lone `<` in text, as lenient parsers accept it
+            let end = input[pos + 1..]
+                .find('<')
+                .map_or(input.len(), |i| pos + 1 + i);
+            tokens.push(Token::Text(&input[pos..end]));
+            pos = end;
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses the start tag at the beginning of `input`, returning it and its length.
+fn start_tag(input: &str) -> Result<(Token<'_>, usize), String> {
+    let is_name_end = |c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=';
+    let name_end = input[1..].find(is_name_end).map_or(input.len(), |i| i + 1);
+    let name = &input[1..name_end];
+
+    let mut attrs = Vec::new();
+    let mut pos = name_end;
+    loop {
+        let rest = &input[pos..];
+        let trimmed = rest.trim_start();
+        pos += rest.len() - trimmed.len();
+
+        if trimmed.starts_with("/>") {
+            let raw = &input[..pos + 2];
+            let token = Token::Start {
+                name,
+                attrs,
+                self_closing: true,
+                raw,
+            };
+            return Ok((token, pos + 2));
+        }
+        if trimmed.starts_with('>') {
+            let raw = &input[..pos + 1];
+            let token = Token::Start {
+                name,
+                attrs,
+                self_closing: false,
+                raw,
+            };
+            return Ok((token, pos + 1));
+        }
+        if trimmed.is_empty() {
+            return Err(format!("unterminated start tag <{}", name));
+        }
+        if trimmed.starts_with('/') {
+            pos += 1;
+            continue;
+        }
+
+        let attr_end = trimmed.find(is_name_end).unwrap_or(trimmed.len());
+        let attr_name = &trimmed[..attr_end];
+        pos += attr_end;
+
+        let rest = &input[pos..];
+        let after_ws = rest.trim_start();
+        if let Some(value_part) = after_ws.strip_prefix('=') {
+            let value_part = value_part.trim_start();
+            pos = input.len() - value_part.len();
+            let value = match value_part.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = value_part[1..].find(quote).ok_or_else(|| {
+                        format!("unterminated value of attribute '{}'", attr_name)
+                    })?;
+                    pos += end + 2;
+                    &value_part[1..end + 1]
+                }
+                _ => {
+                    let end = value_part
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(value_part.len());
+                    pos += end;
+                    &value_part[..end]
+                }
+            };
+            attrs.push(Attribute {
+                name: attr_name,
+                value: Some(value),
+            });
+        } else {
+            attrs.push(Attribute {
+                name: attr_name,
+                value: None,
+            });
+        }
+    }
+}
+
+/// Resolves the predefined entities and numeric character references; unknown entities
+/// are left as they are.
+pub fn unescape(text: &str) -> Cow<'_, str> {
+    unescape_with(text, |_| None)
+}
+
+/// Like [`unescape`], resolving further named entities through `named`.
+pub fn unescape_with<'a>(text: &'a str, named: impl Fn(&str) -> Option<char>) -> Cow<'a, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let resolved = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 32)
+            .and_then(|end| {
+                let entity = &rest[1..end + 1];
+                let c = match entity {
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "amp" => Some('&'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    _ => match entity.strip_prefix('#') {
+                        Some(hex) if hex.starts_with(['x', 'X']) => {
+                            u32::from_str_radix(&hex[1..], 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                        }
+                        Some(dec) => dec.parse().ok().and_then(char::from_u32),
+                        None => named(entity),
+                    },
+                };
+                c.map(|c| (c, end + 2))
+            });
+        match resolved {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Escapes text content.
+pub fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Escapes an attribute value for use inside double quotes.
+pub fn escape_attr(text: &str) -> String {
+    escape_text(text).replace('"', "&quot;")
+}
@@ -45,3 +45,73 @@ async fn chat_completions_returns_400_if_model_not_loaded() {
             || error_msg.contains("Model not found")
     );
 }
+
+#[tokio::test]
+async fn chat_completions_rejects_requests_beyond_limits() {
+    use ctranslate2_server::{config::ModelSpec, model::Limits};
+    let mut config = AppConfig::default();
+    config.limits.max_beam_size = Some(8);
+    config.models.insert(
+        "nllb".to_string(),
+        ModelSpec {
+            path: "./models/missing".to_string(),
+            model_type: "nllb".to_string(),
+            limits: Limits {
+                max_input_chars: Some(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+    let state = AppState {
+        model_manager: Arc::new(ModelManager::new(config)),
+    };
+
+    let cases = [
+        (json!({ "beam_size": 500 }), "beam_size"),
+        (json!({ "beam_size": 0 }), "beam_size"),
+        (json!({ "temperature": -1.0, "top_k": 10 }), "temperature"),
+        (json!({ "top_p": 2.0 }), "top_p"),
+        (json!({ "patience": 0.0 }), "patience"),
+        (
+            json!({ "messages": [{"role": "user", "content": "Far too long for this node"}] }),
+            "messages",
+        ),
+        (json!({ "target_langs": [] }), "target_langs"),
+        (json!({ "target_lang": "tlh" }), "target_lang"),
+        (json!({ "source_lang": "sr-Latn" }), "source_lang"),
+        (
+            json!({ "target_langs": ["fra_Latn", "deu_Latn"], "format": "html" }),
+            "target_langs",
+        ),
+    ];
+    for (overrides, param) in cases {
+        let mut request_body = json!({
+            "model": "nllb",
+            "messages": [{"role": "user", "content": "Hello"}]
+        });
+        for (key, value) in overrides.as_object().unwrap() {
+            request_body[key] = value.clone();
+        }
+
+        let response = app(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/chat/completions")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&request_body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // Rejected before the (missing) model is loaded
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(body["param"], param);
+    }
+}
@@ -0,0 +1,88 @@
+use ctranslate2_server::cache::{CacheKey, TranslationCache};
+use ctranslate2_server::config::CacheConfig;
+use ctranslate2_server::model::GenerationParams;
+
+fn key(input: &str) -> CacheKey {
+    CacheKey::new("nllb", input, &GenerationParams::default())
+}
+
+fn cache(max_entries: usize) -> TranslationCache {
+    TranslationCache::new(&CacheConfig {
+        max_entries,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let cache = cache(2);
+    cache.insert(key("a"), "A".into());
+    cache.insert(key("b"), "B".into());
+    assert_eq!(cache.get(&key("a")).as_deref(), Some("A"));
+
+    cache.insert(key("c"), "C".into());
+    assert_eq!(cache.get(&key("b")), None);
+    assert_eq!(cache.get(&key("a")).as_deref(), Some("A"));
+    assert_eq!(cache.get(&key("c")).as_deref(), Some("C"));
+
+    let stats = cache.stats();
+    assert_eq!(stats.entries, 2);
+    assert_eq!((stats.hits, stats.misses), (3, 1));
+}
+
+#[test]
+fn keys_cover_model_params_and_normalized_input() {
+    assert_eq!(key("  Hello \n world "), key("Hello world"));
+    assert_ne!(
+        key("Hello"),
+        CacheKey::new("t5", "Hello", &Default::default())
+    );
+
+    let beam = GenerationParams {
+        beam_size: Some(2),
+        ..Default::default()
+    };
+    assert_ne!(key("Hello"), CacheKey::new("nllb", "Hello", &beam));
+
+    // Presets and the bypass flag only matter through the settings they lead to
+    let preset = GenerationParams {
+        preset: Some("fast".into()),
+        use_cache: Some(true),
+        ..Default::default()
+    };
+    assert_eq!(key("Hello"), CacheKey::new("nllb", "Hello", &preset));
+}
+
+#[test]
+fn byte_limit_and_disabled_cache() {
+    let bounded = TranslationCache::new(&CacheConfig {
+        max_entries: 100,
+        max_bytes: 2000,
+        ..Default::default()
+    });
+    bounded.insert(key("small"), "x".into());
+    bounded.insert(key("large"), "x".repeat(5000));
+    assert!(bounded.get(&key("small")).is_some());
+    assert!(bounded.get(&key("large")).is_none());
+    assert!(bounded.stats().bytes <= 2000);
+
+    let disabled = cache(0);
+    disabled.insert(key("a"), "A".into());
+    assert_eq!(disabled.get(&key("a")), None);
+    assert!(!disabled.stats().enabled);
+}
+
+#[test]
+fn entries_expire_after_ttl() {
+    let cache = TranslationCache::new(&CacheConfig {
+        max_entries: 10,
+        ttl_secs: 1,
+        ..Default::default()
+    });
+    cache.insert(key("a"), "A".into());
+    assert!(cache.get(&key("a")).is_some());
+
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    assert!(cache.get(&key("a")).is_none());
+    assert_eq!(cache.stats().entries, 0);
+}
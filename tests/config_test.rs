@@ -23,6 +23,8 @@ fn cli_args_override_everything() {
         host: None,
         port: Some(7070),
         config: "config.toml".into(),
+        check_config: false,
+        command: None,
     };
     unsafe { std::env::set_var("SERVER_PORT", "9090") };
     let config = AppConfig::load(Some(args)).unwrap();
@@ -30,3 +32,155 @@ fn cli_args_override_everything() {
     // Cleanup
     unsafe { std::env::remove_var("SERVER_PORT") };
 }
+
+#[test]
+fn aliases_can_be_virtual_models() {
+    use ctranslate2_server::config::Args;
+    let path = std::env::temp_dir().join(format!("ct2-aliases-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+[aliases]
+"nllb" = "nllb-200-distilled-600M"
+"nllb-to-french" = { target = "nllb", target_lang = "fra_Latn" }
+"nllb-fast" = { target = "nllb-to-french", beam_size = 1, target_lang = "deu_Latn" }
+"#,
+    )
+    .unwrap();
+
+    let config = AppConfig::load(Some(Args {
+        host: None,
+        port: None,
+        config: path.display().to_string(),
+        check_config: false,
+        command: None,
+    }))
+    .unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(
+        config.resolve_model_name("nllb-fast").unwrap(),
+        "nllb-200-distilled-600M"
+    );
+    assert!(config.alias_params("nllb").beam_size.is_none());
+
+    let french = config.alias_params("nllb-to-french");
+    assert_eq!(french.target_lang.as_deref(), Some("fra_Latn"));
+    assert_eq!(french.beam_size, None);
+
+    // The nearest alias wins, the rest is inherited along the chain
+    let fast = config.alias_params("nllb-fast");
+    assert_eq!(fast.target_lang.as_deref(), Some("deu_Latn"));
+    assert_eq!(fast.beam_size, Some(1));
+}
+
+#[test]
+fn effective_spec_ignores_generation_settings() {
+    use ctranslate2_server::config::ModelSpec;
+    let mut config = AppConfig::default();
+    config.models.insert(
+        "nllb".to_string(),
+        ModelSpec {
+            path: "./models/nllb".to_string(),
+            model_type: "nllb".to_string(),
+            ..Default::default()
+        },
+    );
+
+    let spec = config.effective_spec("nllb").unwrap();
+    assert_eq!(spec.device.as_deref(), Some("cpu"));
+
+    // Generation settings don't change how the model loads
+    let mut tuned = config.clone();
+    let nllb = tuned.models.get_mut("nllb").unwrap();
+    nllb.beam_size = Some(2);
+    nllb.preset = Some("fast".to_string());
+    assert_eq!(tuned.effective_spec("nllb"), Some(spec.clone()));
+    tuned.models.get_mut("nllb").unwrap().device = Some("cuda".to_string());
+    assert_ne!(tuned.effective_spec("nllb"), Some(spec));
+}
+
+#[test]
+fn effective_spec_inherits_replica_settings() {
+    use ctranslate2_server::config::ModelSpec;
+    let mut config = AppConfig {
+        compute_type: "int8".to_string(),
+        num_threads_per_replica: 4,
+        ..Default::default()
+    };
+    config.models.insert(
+        "nllb".to_string(),
+        ModelSpec {
+            path: "./models/nllb".to_string(),
+            model_type: "nllb".to_string(),
+            compute_type: Some("int8_float16".to_string()),
+            ..Default::default()
+        },
+    );
+
+    let spec = config.effective_spec("nllb").unwrap();
+    assert_eq!(spec.compute_type.as_deref(), Some("int8_float16"));
+    assert_eq!(spec.num_threads_per_replica, Some(4));
+    assert_eq!(spec.max_queued_batches, Some(0));
+    assert_eq!(spec.cpu_core_offset, Some(-1));
+    assert_eq!(spec.device.as_deref(), Some("cpu"));
+
+    let mut tuned = config.clone();
+    tuned.models.get_mut("nllb").unwrap().compute_type = Some("int8".to_string());
+    assert_ne!(tuned.effective_spec("nllb"), Some(spec));
+}
+
+#[test]
+fn presets_fill_unset_params() {
+    use ctranslate2_server::config::Args;
+    let path = std::env::temp_dir().join(format!("ct2-presets-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+[presets.fast]
+beam_size = 1
+max_decoding_length = 64
+
+[presets.quality]
+beam_size = 8
+length_penalty = 1.2
+
+[aliases]
+"nllb-fast" = { target = "nllb", preset = "fast", max_decoding_length = 128 }
+"#,
+    )
+    .unwrap();
+
+    let config = AppConfig::load(Some(Args {
+        host: None,
+        port: None,
+        config: path.display().to_string(),
+        check_config: false,
+        command: None,
+    }))
+    .unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(config.presets.len(), 2);
+
+    // Settings given next to the preset win over the preset's own
+    let fast = config
+        .with_preset(config.alias_params("nllb-fast"))
+        .unwrap();
+    assert_eq!(fast.beam_size, Some(1));
+    assert_eq!(fast.max_decoding_length, Some(128));
+
+    let request = ctranslate2_server::model::GenerationParams {
+        preset: Some("quality".to_string()),
+        ..Default::default()
+    };
+    let quality = config.with_preset(request).unwrap();
+    assert_eq!(quality.beam_size, Some(8));
+    assert_eq!(quality.length_penalty, Some(1.2));
+
+    let missing = ctranslate2_server::model::GenerationParams {
+        preset: Some("turbo".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(config.with_preset(missing).unwrap_err(), "turbo");
+}
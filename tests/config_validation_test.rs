@@ -0,0 +1,300 @@
+use ctranslate2_server::config::{AppConfig, Args, GlossaryConfig, ModelSpec};
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ct2-validate-{}-{}", name, std::process::id()))
+}
+
+/// Creates an empty model directory that passes the path and tokenizer checks.
+fn model_dir(name: &str) -> String {
+    let dir = temp_path(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tokenizer.json"), "{}").unwrap();
+    dir.display().to_string()
+}
+
+fn valid_config() -> AppConfig {
+    let mut config = AppConfig::default();
+    config.models.insert(
+        "nllb-600M".to_string(),
+        ModelSpec {
+            path: model_dir("nllb"),
+            model_type: "nllb".to_string(),
+            ..Default::default()
+        },
+    );
+    config
+        .aliases
+        .insert("nllb".to_string(), "nllb-600M".into());
+    config
+}
+
+fn issues(config: &AppConfig) -> Vec<String> {
+    config.validate(&[]).unwrap_err().issues
+}
+
+#[test]
+fn valid_config_passes() {
+    assert_eq!(valid_config().validate(&[]).unwrap(), Vec::<String>::new());
+}
+
+#[test]
+fn alias_to_missing_model_suggests_closest_name() {
+    let mut config = valid_config();
+    config
+        .aliases
+        .insert("broken".to_string(), "nllb-60M".into());
+
+    let issues = issues(&config);
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].contains("aliases.broken"));
+    assert!(issues[0].contains("did you mean 'nllb-600M'?"));
+}
+
+#[test]
+fn alias_cycles_are_detected() {
+    let mut config = valid_config();
+    config.aliases.insert("a".to_string(), "b".into());
+    config.aliases.insert("b".to_string(), "a".into());
+
+    let issues = issues(&config);
+    assert!(issues.iter().any(|i| i.contains("alias cycle a -> b -> a")));
+}
+
+#[test]
+fn problems_are_aggregated() {
+    let mut config = valid_config();
+    config.device = "cuad".to_string();
+    config.default_model = "missing".to_string();
+    config.models.insert(
+        "broken".to_string(),
+        ModelSpec {
+            path: "/non/existent/path".to_string(),
+            model_type: "nlb".to_string(),
+            ..Default::default()
+        },
+    );
+
+    let issues = issues(&config);
+    assert_eq!(issues.len(), 3, "{:?}", issues);
+    assert!(issues.iter().any(|i| i.contains("did you mean 'cuda'?")));
+    assert!(issues.iter().any(|i| i.starts_with("default_model")));
+    assert!(issues.iter().any(|i| i.contains("did you mean 'nllb'?")));
+}
+
+#[test]
+fn missing_model_directories_only_fail_models_in_use() {
+    let mut config = valid_config();
+    config.models.insert(
+        "lazy".to_string(),
+        ModelSpec {
+            path: "/non/existent/path".to_string(),
+            model_type: "nllb".to_string(),
+            ..Default::default()
+        },
+    );
+    config.aliases.insert("later".to_string(), "lazy".into());
+
+    let warnings = config.validate(&[]).unwrap();
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    assert!(warnings[0].starts_with("models.lazy.path"));
+
+    // A command translating with it needs it, through an alias too
+    let issues = config.validate(&["later"]).unwrap_err().issues;
+    assert_eq!(issues.len(), 1, "{:?}", issues);
+    assert!(issues[0].starts_with("models.lazy.path"));
+
+    config.models.get_mut("lazy").unwrap().preload = true;
+    let issues = config.validate(&[]).unwrap_err().issues;
+    assert!(issues[0].starts_with("models.lazy.path"));
+}
+
+#[test]
+fn model_languages_are_checked_against_the_model_type() {
+    let mut config = valid_config();
+    let spec = config.models.get_mut("nllb-600M").unwrap();
+    spec.source_lang = Some("en".to_string());
+    spec.target_lang = Some("tlh".to_string());
+
+    let issues = issues(&config);
+    assert_eq!(issues.len(), 1, "{:?}", issues);
+    assert!(issues[0].starts_with("models.nllb-600M.target_lang: Unknown language code 'tlh'"));
+}
+
+#[test]
+fn unknown_keys_in_file_are_reported() {
+    let path = temp_path("keys").with_extension("toml");
+    std::fs::write(
+        &path,
+        format!(
+            r#"
+default_model = "nllb"
+beam_sise = 4
+
+[server]
+host = "0.0.0.0"
+port = 8080
+
+[models."nllb"]
+path = "{}"
+model_type = "nllb"
+tokenizer_pth = "./tokenizer"
+"#,
+            model_dir("keys-model")
+        ),
+    )
+    .unwrap();
+
+    let config = AppConfig::load(Some(Args {
+        host: None,
+        port: None,
+        config: path.display().to_string(),
+        check_config: true,
+        command: None,
+    }))
+    .unwrap();
+
+    let issues = issues(&config);
+    assert!(
+        issues
+            .iter()
+            .any(|i| i.contains("beam_sise: unknown key (did you mean 'beam_size'?)"))
+    );
+    assert!(issues.iter().any(|i| {
+        i.contains("models.nllb.tokenizer_pth: unknown key (did you mean 'tokenizer_path'?)")
+    }));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn replica_settings_are_validated() {
+    let mut config = valid_config();
+    config.compute_type = "int8_float61".to_string();
+    let spec = config.models.get_mut("nllb-600M").unwrap();
+    spec.max_queued_batches = Some(-5);
+    spec.cpu_core_offset = Some(-2);
+
+    let issues = issues(&config);
+    assert_eq!(issues.len(), 3, "{:?}", issues);
+    assert!(
+        issues
+            .iter()
+            .any(|i| i.contains("compute_type") && i.contains("did you mean 'int8_float16'?"))
+    );
+    assert!(
+        issues
+            .iter()
+            .any(|i| i.starts_with("models.nllb-600M.max_queued_batches"))
+    );
+    assert!(
+        issues
+            .iter()
+            .any(|i| i.starts_with("models.nllb-600M.cpu_core_offset"))
+    );
+}
+
+#[test]
+fn preset_references_are_validated() {
+    use ctranslate2_server::model::GenerationParams;
+    let mut config = valid_config();
+    config.presets.insert(
+        "quality".to_string(),
+        GenerationParams {
+            beam_size: Some(0),
+            ..Default::default()
+        },
+    );
+    config.presets.insert(
+        "nested".to_string(),
+        GenerationParams {
+            preset: Some("quality".to_string()),
+            ..Default::default()
+        },
+    );
+    config.models.get_mut("nllb-600M").unwrap().preset = Some("qualty".to_string());
+
+    let issues = issues(&config);
+    assert_eq!(issues.len(), 3, "{:?}", issues);
+    assert!(
+        issues
+            .iter()
+            .any(|i| i.starts_with("presets.quality.beam_size"))
+    );
+    assert!(
+        issues
+            .iter()
+            .any(|i| i.starts_with("presets.nested.preset"))
+    );
+    assert!(
+        issues
+            .iter()
+            .any(|i| i.starts_with("models.nllb-600M.preset")
+                && i.contains("did you mean 'quality'?"))
+    );
+}
+
+#[test]
+fn limits_are_validated() {
+    let mut config = valid_config();
+    config.limits.max_beam_size = Some(0);
+    config
+        .models
+        .get_mut("nllb-600M")
+        .unwrap()
+        .limits
+        .allowed_target_langs = Some(vec![]);
+
+    let issues = issues(&config);
+    assert_eq!(issues.len(), 2, "{:?}", issues);
+    assert!(issues.iter().any(|i| i.starts_with("limits.max_beam_size")));
+    assert!(
+        issues
+            .iter()
+            .any(|i| i.starts_with("models.nllb-600M.limits.allowed_target_langs"))
+    );
+}
+
+#[test]
+fn glossary_files_are_validated() {
+    let mut config = valid_config();
+    let good = temp_path("glossary.csv");
+    std::fs::write(&good, "source,target\nAcme Cloud,Acme Cloud\n").unwrap();
+    let bad = temp_path("glossary-bad.csv");
+    std::fs::write(&bad, "Acme Cloud\n").unwrap();
+    for (id, path) in [("good", &good), ("bad", &bad)] {
+        config.glossaries.insert(
+            id.to_string(),
+            GlossaryConfig {
+                path: path.display().to_string(),
+                source_lang: None,
+                target_lang: "fra_Latn".to_string(),
+                case_sensitive: false,
+            },
+        );
+    }
+
+    let issues = issues(&config);
+    assert_eq!(issues.len(), 1, "{:?}", issues);
+    assert!(issues[0].starts_with("glossaries.bad"));
+    assert!(issues[0].contains("Line 1"));
+}
+
+#[test]
+fn protection_patterns_are_validated() {
+    let mut config = valid_config();
+    config.protect = vec!["icu".to_string(), "ticket".to_string()];
+    config
+        .protect_patterns
+        .insert("ticket".to_string(), r"JIRA-\d+".to_string());
+    config
+        .protect_patterns
+        .insert("broken".to_string(), "(unclosed".to_string());
+    config.models.get_mut("nllb-600M").unwrap().protect = Some(vec!["prinf".to_string()]);
+
+    let issues = issues(&config);
+    assert_eq!(issues.len(), 2, "{:?}", issues);
+    assert!(issues[0].starts_with("protect_patterns.broken: invalid pattern"));
+    assert!(issues[1].starts_with("models.nllb-600M.protect"));
+    assert!(issues[1].contains("did you mean 'printf'?"));
+}
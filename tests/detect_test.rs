@@ -0,0 +1,187 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use ctranslate2_server::{
+    app,
+    config::{AppConfig, ModelSpec},
+    detect::{self, Script},
+    model::ModelManager,
+    state::AppState,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn language(text: &str) -> Option<String> {
+    detect::detect(text).language
+}
+
+#[test]
+fn languages_sharing_the_latin_script_are_told_apart() {
+    for (expected, text) in [
+        (
+            "eng_Latn",
+            "The weather is nice today and I want to go for a walk.",
+        ),
+        (
+            "fra_Latn",
+            "Je voudrais réserver une table pour deux personnes ce soir.",
+        ),
+        (
+            "deu_Latn",
+            "Ich möchte heute Abend einen Tisch für zwei Personen reservieren.",
+        ),
+        (
+            "spa_Latn",
+            "Me gustaría reservar una mesa para dos personas esta noche.",
+        ),
+        (
+            "por_Latn",
+            "Eu gostaria de reservar uma mesa para duas pessoas esta noite.",
+        ),
+        (
+            "ita_Latn",
+            "Vorrei prenotare un tavolo per due persone stasera.",
+        ),
+        (
+            "pol_Latn",
+            "Chciałbym zarezerwować stolik dla dwóch osób na dzisiejszy wieczór.",
+        ),
+        (
+            "tur_Latn",
+            "Bu akşam iki kişilik bir masa ayırtmak istiyorum.",
+        ),
+        ("vie_Latn", "Tôi muốn đặt một bàn cho hai người tối nay."),
+    ] {
+        assert_eq!(language(text).as_deref(), Some(expected), "{}", text);
+    }
+}
+
+#[test]
+fn russian_and_ukrainian_are_told_apart() {
+    assert_eq!(
+        language("Где находится ближайшая станция метро?").as_deref(),
+        Some("rus_Cyrl")
+    );
+    assert_eq!(
+        language("Де знаходиться найближча станція метро?").as_deref(),
+        Some("ukr_Cyrl")
+    );
+}
+
+#[test]
+fn cjk_languages_are_told_apart_by_script() {
+    let japanese = detect::detect("今夜二人分のテーブルを予約したいです。");
+    assert_eq!(japanese.language.as_deref(), Some("jpn_Jpan"));
+    assert_eq!(japanese.script, Some(Script::Jpan));
+    assert_eq!(
+        language("오늘 저녁 두 사람 자리를 예약하고 싶습니다.").as_deref(),
+        Some("kor_Hang")
+    );
+    assert_eq!(
+        language("这个问题还没有解决。").as_deref(),
+        Some("zho_Hans")
+    );
+    assert_eq!(
+        language("這個問題還沒有解決。").as_deref(),
+        Some("zho_Hant")
+    );
+}
+
+#[test]
+fn candidates_are_ranked_with_confidences() {
+    let detection = detect::detect("Я хотів би забронювати столик на двох на сьогоднішній вечір.");
+    assert_eq!(detection.language.as_deref(), Some("ukr_Cyrl"));
+    assert!(!detection.candidates.is_empty() && detection.candidates.len() <= 3);
+    assert_eq!(detection.candidates[0].confidence, detection.confidence);
+    assert!(
+        detection
+            .candidates
+            .windows(2)
+            .all(|pair| pair[0].confidence >= pair[1].confidence)
+    );
+}
+
+#[test]
+fn texts_without_letters_have_no_language() {
+    let detection = detect::detect("12 345, 67!");
+    assert_eq!(detection.language, None);
+    assert_eq!(detection.script, None);
+    assert!(detection.candidates.is_empty());
+}
+
+#[test]
+fn detections_map_to_model_codes() {
+    let detection = detect::detect("Bonjour, comment allez-vous ?");
+    assert_eq!(
+        detection.clone().for_model("m2m100").language.as_deref(),
+        Some("__fr__")
+    );
+    assert_eq!(detection.for_model("t5").language.as_deref(), Some("fr"));
+}
+
+#[tokio::test]
+async fn detect_endpoint_reports_codes_of_the_model() {
+    let mut config = AppConfig::default();
+    config.models.insert(
+        "m2m".to_string(),
+        ModelSpec {
+            path: "./models/missing".to_string(),
+            model_type: "m2m100".to_string(),
+            ..Default::default()
+        },
+    );
+    let state = AppState {
+        model_manager: Arc::new(ModelManager::new(config)),
+    };
+    let request_body = json!({
+        "model": "m2m",
+        "input": ["Where is the nearest pharmacy?", "42"]
+    });
+    let response = app(state)
+        .oneshot(
+            Request::post("/v1/detect")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_vec(&request_body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["model"], "m2m");
+    assert_eq!(body["detections"][0]["language"], "__en__");
+    assert_eq!(body["detections"][0]["script"], "Latn");
+    assert_eq!(body["detections"][1]["language"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn detect_endpoint_applies_limits() {
+    let mut config = AppConfig::default();
+    config.limits.max_batch_items = Some(1);
+    let state = AppState {
+        model_manager: Arc::new(ModelManager::new(config)),
+    };
+    let request_body = json!({ "input": ["Bonjour", "Hello"] });
+    let response = app(state)
+        .oneshot(
+            Request::post("/v1/detect")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_vec(&request_body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["param"], "input");
+}
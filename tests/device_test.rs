@@ -0,0 +1,63 @@
+use ct2rs::Device;
+use ctranslate2_server::device::DeviceSpec;
+
+#[test]
+fn device_syntax_is_parsed() {
+    assert_eq!("cpu".parse(), Ok(DeviceSpec::Cpu));
+    assert_eq!("AUTO".parse(), Ok(DeviceSpec::Auto));
+    assert_eq!("gpu".parse(), Ok(DeviceSpec::Cuda(None)));
+    assert_eq!("cuda:0,1".parse(), Ok(DeviceSpec::Cuda(Some(vec![0, 1]))));
+    assert_eq!(DeviceSpec::Cuda(Some(vec![0, 1])).to_string(), "cuda:0,1");
+
+    assert!("cuda:".parse::<DeviceSpec>().is_err());
+    assert!("cuda:-1".parse::<DeviceSpec>().is_err());
+    assert!("cpu:0".parse::<DeviceSpec>().is_err());
+    assert!("tpu".parse::<DeviceSpec>().is_err());
+}
+
+#[test]
+fn auto_uses_cuda_only_when_gpus_are_present() {
+    let auto = DeviceSpec::Auto;
+    assert_eq!(
+        auto.resolve(&[0, 1], false, Some(2)),
+        Ok((Device::CUDA, vec![0, 1]))
+    );
+    assert_eq!(
+        auto.resolve(&[0, 1], false, Some(0)),
+        Ok((Device::CPU, vec![0]))
+    );
+}
+
+#[test]
+fn cuda_falls_back_to_cpu_only_when_allowed() {
+    let cuda = DeviceSpec::Cuda(Some(vec![1]));
+    assert_eq!(
+        cuda.resolve(&[0], false, Some(2)),
+        Ok((Device::CUDA, vec![1]))
+    );
+
+    let err = cuda.resolve(&[0], false, Some(1)).unwrap_err();
+    assert!(err.contains("CUDA device 1 not found"), "{}", err);
+    assert!(err.contains("allow_cpu_fallback"), "{}", err);
+    assert_eq!(
+        cuda.resolve(&[0], true, Some(0)),
+        Ok((Device::CPU, vec![0]))
+    );
+
+    assert_eq!(
+        DeviceSpec::Cpu.resolve(&[0], false, Some(4)),
+        Ok((Device::CPU, vec![0]))
+    );
+}
+
+#[test]
+fn cuda_without_cuda_support_is_reported() {
+    let err = DeviceSpec::Cuda(None)
+        .resolve(&[0], false, None)
+        .unwrap_err();
+    assert!(err.contains("--features cuda"), "{}", err);
+    assert_eq!(
+        DeviceSpec::Auto.resolve(&[0], false, None),
+        Ok((Device::CPU, vec![0]))
+    );
+}
@@ -0,0 +1,368 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use ctranslate2_server::{
+    app,
+    config::{AppConfig, ModelSpec},
+    format::{
+        markdown::MarkdownDocument,
+        markup::MarkupDocument,
+        subtitle::{SubtitleDocument, SubtitleFormat},
+    },
+    model::ModelManager,
+    state::AppState,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn attributes() -> Vec<String> {
+    vec!["alt".to_string(), "title".to_string()]
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+const PAGE: &str = "<html><head><title>Welcome</title></head>
+<body>
+  <p class=\"intro\">Click <a href=\"/x\" title=\"Open the page\">here</a> to
+  start.</p>
+  <img src=\"a.png\" alt=\"A cat\">
+  <pre>let x = 1;</pre>
+  <p>Fish &amp; chips&nbsp;today <code>cargo run</code></p>
+  <script>if (a < b) {}</script>
+</body></html>";
+
+#[test]
+fn html_text_and_attributes_are_translated_around_the_markup() {
+    let document = MarkupDocument::parse(PAGE, true, &attributes()).unwrap();
+    assert_eq!(
+        document.prompts(),
+        strings(&[
+            "Welcome",
+            "Open the page",
+            "Click [[0]]here[[1]] to start.",
+            "A cat",
+            "Fish & chips[[0]]today [[1]]",
+        ])
+    );
+
+    let (html, lost) = document.render(&strings(&[
+        "Bienvenue",
+        "Ouvrir la page",
+        "Cliquez [[0]]ici[[1]] pour commencer.",
+        "Un \"chat\"",
+        "Poisson & frites[[0]]aujourd'hui [ [1] ]",
+    ]));
+    assert!(lost.is_empty());
+    assert_eq!(
+        html,
+        "<html><head><title>Bienvenue</title></head>
+<body>
+  <p class=\"intro\">Cliquez <a href=\"/x\" title=\"Ouvrir la page\">ici</a> pour commencer.</p>
+  <img src=\"a.png\" alt=\"Un &quot;chat&quot;\">
+  <pre>let x = 1;</pre>
+  <p>Poisson &amp; frites&nbsp;aujourd'hui <code>cargo run</code></p>
+  <script>if (a < b) {}</script>
+</body></html>"
+    );
+}
+
+#[test]
+fn misplaced_tags_are_dropped_rather_than_breaking_the_markup() {
+    let input = "<p>Say <b>hello <i>world</i></b> <br> now</p>";
+    let document = MarkupDocument::parse(input, true, &[]).unwrap();
+    assert_eq!(
+        document.prompts(),
+        strings(&["Say [[0]]hello [[1]]world[[2]][[3]] [[4]] now"])
+    );
+
+    // <i> closes after <b>, the second <br> is a duplicate
+    let (html, lost) = document.render(&strings(&[
+        "Dis [[0]]bonjour [[1]]monde[[3]][[2]] [[4]] [[4]] maintenant",
+    ]));
+    assert_eq!(html, "<p>Dis <b>bonjour monde</b> <br>  maintenant</p>");
+    assert_eq!(lost, strings(&["<i>"]));
+
+    let (html, lost) = document.render(&strings(&["Dis bonjour < monde"]));
+    assert_eq!(html, "<p>Dis bonjour &lt; monde</p>");
+    assert_eq!(lost, strings(&["<b>", "<i>", "<br>"]));
+}
+
+#[test]
+fn literal_placeholders_are_kept_apart_from_tags() {
+    let input = "<p>See [[0]] <b>x</b></p>";
+    let document = MarkupDocument::parse(input, true, &[]).unwrap();
+    assert_eq!(document.prompts(), strings(&["See [[0]] [[1]]x[[2]]"]));
+
+    let (html, lost) = document.render(&strings(&["Voir [[0]] [[1]]x[[2]]"]));
+    assert!(lost.is_empty());
+    assert_eq!(html, "<p>Voir [[0]] <b>x</b></p>");
+}
+
+#[test]
+fn xml_elements_in_mixed_content_are_inline() {
+    let input = "<?xml version=\"1.0\"?>
+<doc>
+  <title>Hello</title>
+  <para>Press <b>Save</b> now.</para>
+  <note translate=\"no\">Keep me</note>
+  <para><![CDATA[raw]]></para>
+</doc>";
+    let document = MarkupDocument::parse(input, false, &attributes()).unwrap();
+    assert_eq!(
+        document.prompts(),
+        strings(&["Hello", "Press [[0]]Save[[1]] now."])
+    );
+
+    let (xml, lost) = document.render(&strings(&[
+        "Bonjour",
+        "Appuyez sur [[0]]Enregistrer[[1]] maintenant.",
+    ]));
+    assert!(lost.is_empty());
+    assert_eq!(
+        xml,
+        "<?xml version=\"1.0\"?>
+<doc>
+  <title>Bonjour</title>
+  <para>Appuyez sur <b>Enregistrer</b> maintenant.</para>
+  <note translate=\"no\">Keep me</note>
+  <para><![CDATA[raw]]></para>
+</doc>"
+    );
+}
+
+const GUIDE: &str = r#"---
+title: Getting started
+layout: post
+description: "A short guide"
+---
+# Install the `cli` tool
+
+Run the **installer** from [our site](https://example.com/download "Download")
+and follow the steps.
+
+```sh
+cargo install tool
+```
+
+- First item with `code`
+- Second item, see https://example.com/docs.
+
+| Option | Meaning |
+|--------|---------|
+| `-v` | Verbose output |
+
+> Quoted text here.
+"#;
+
+#[test]
+fn markdown_prose_is_translated_around_code_and_links() {
+    let document = MarkdownDocument::parse(GUIDE);
+    assert_eq!(
+        document.prompts(),
+        strings(&[
+            "Getting started",
+            "A short guide",
+            "Install the [[0]] tool",
+            "Run the [[0]]installer[[1]] from [[2]]our site[[3]] and follow the steps.",
+            "First item with [[0]]",
+            "Second item, see [[0]].",
+            "Option",
+            "Meaning",
+            "Verbose output",
+            "Quoted text here.",
+        ])
+    );
+
+    let (markdown, lost) = document.render(&strings(&[
+        "Prise en main",
+        "Un guide \"court\"",
+        "Installer l'outil [[0]]",
+        "Lancez l'[[0]]installateur[[1]] depuis [[2]]notre site[[3]] et suivez les étapes.",
+        "Premier élément avec [[0]]",
+        "Deuxième élément, voir [[0]].",
+        "Option",
+        "Signification",
+        "Sortie détaillée",
+        "Texte cité ici.",
+    ]));
+    assert!(lost.is_empty());
+    assert_eq!(
+        markdown,
+        r#"---
+title: Prise en main
+layout: post
+description: "Un guide \"court\""
+---
+# Installer l'outil `cli`
+
+Lancez l'**installateur** depuis [notre site](https://example.com/download "Download") et suivez les étapes.
+
+```sh
+cargo install tool
+```
+
+- Premier élément avec `code`
+- Deuxième élément, voir https://example.com/docs.
+
+| Option | Signification |
+|--------|---------|
+| `-v` | Sortie détaillée |
+
+> Texte cité ici.
+"#
+    );
+}
+
+#[test]
+fn markdown_line_breaks_code_blocks_and_lost_markers() {
+    let input = "Use **bold** and [a link](http://x.y).\n\n    let max_len = 1;\n\nLine one  \nline two\\\nset max_input_length here\n";
+    let document = MarkdownDocument::parse(input);
+    assert_eq!(
+        document.prompts(),
+        strings(&[
+            "Use [[0]]bold[[1]] and [[2]]a link[[3]].",
+            "Line one[[0]]line two[[1]]set max_input_length here",
+        ])
+    );
+
+    let (markdown, lost) = document.render(&strings(&[
+        "Utilisez [[0]]gras et [[2]]un lien[[3]].",
+        "Ligne un[[0]]ligne deux[[1]]régler max_input_length ici",
+    ]));
+    assert_eq!(
+        markdown,
+        "Utilisez gras et [un lien](http://x.y).\n\n    let max_len = 1;\n\nLigne un  \nligne deux\\\nrégler max_input_length ici\n"
+    );
+    assert_eq!(lost, strings(&["**"]));
+}
+
+#[test]
+fn srt_cues_keep_numbers_timings_and_tags() {
+    let input = "1\r\n00:00:01,000 --> 00:00:03,500\r\n<i>Where are you</i>\r\ngoing tonight?\r\n\r\n2\r\n00:00:04,000 --> 00:00:06,000\r\n- Home.\r\n- {\\an8}Me too!\r\n\r\n3\r\n00:00:07,000 --> 00:00:08,000\r\n♪ ♪\r\n";
+    let document = SubtitleDocument::parse(input).unwrap();
+    assert_eq!(document.format(), SubtitleFormat::Srt);
+    assert_eq!(
+        document.prompts(),
+        strings(&[
+            "[[0]]Where are you[[1]] going tonight?",
+            "Home.",
+            "[[0]]Me too!",
+        ])
+    );
+
+    let translations = strings(&[
+        "[[0]]Où vas-tu[[1]] ce soir ?",
+        "Maison.",
+        "[[0]]Moi aussi !",
+    ]);
+    let (srt, lost) = document.render(&translations, None);
+    assert!(lost.is_empty());
+    assert_eq!(
+        srt,
+        "1\r\n00:00:01,000 --> 00:00:03,500\r\n<i>Où vas-tu</i>\r\nce soir ?\r\n\r\n2\r\n00:00:04,000 --> 00:00:06,000\r\n- Maison.\r\n- {\\an8}Moi aussi !\r\n\r\n3\r\n00:00:07,000 --> 00:00:08,000\r\n♪ ♪\r\n"
+    );
+
+    let (srt, _) = document.render(&translations, Some(10));
+    assert!(srt.contains("<i>Où vas-tu</i>\r\nce soir ?\r\n"));
+}
+
+#[test]
+fn webvtt_header_notes_and_cue_settings_are_kept() {
+    let input = "WEBVTT - Episode 1\n\nNOTE Translated by the team\n\nintro\n00:01.000 --> 00:04.000 align:start line:0\nA <c.loud>long</c> day <00:02.500>begins\n";
+    let document = SubtitleDocument::parse(input).unwrap();
+    assert_eq!(document.format(), SubtitleFormat::Vtt);
+    assert_eq!(
+        document.prompts(),
+        strings(&["A [[0]]long[[1]] day [[2]]begins"])
+    );
+
+    let (vtt, lost) = document.render(&strings(&["Une [[0]]longue journée [[2]]commence"]), None);
+    assert_eq!(
+        vtt,
+        "WEBVTT - Episode 1\n\nNOTE Translated by the team\n\nintro\n00:01.000 --> 00:04.000 align:start line:0\nUne longue journée <00:02.500>commence\n"
+    );
+    assert_eq!(lost, strings(&["<c.loud>"]));
+}
+
+#[test]
+fn invalid_subtitle_timings_are_reported_with_their_line() {
+    let error = SubtitleDocument::parse("1\n00:00:01 --> 00:00:02\nHello\n")
+        .err()
+        .unwrap();
+    assert_eq!(error.line, 2);
+
+    let error = SubtitleDocument::parse("1\nHello\n").err().unwrap();
+    assert_eq!(error.line, 2);
+}
+
+#[tokio::test]
+async fn subtitles_endpoint_rejects_malformed_files() {
+    let state = AppState {
+        model_manager: Arc::new(ModelManager::new(AppConfig::default())),
+    };
+    let response = app(state)
+        .oneshot(
+            Request::post("/v1/subtitles?target_lang=fra_Latn")
+                .header("Content-Type", "application/x-subrip")
+                .body(Body::from("1\n00:00:01,000 -> 00:00:02,000\nHello\n"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["param"], "body");
+    assert!(body["error"].as_str().unwrap().contains("line 2"));
+}
+
+#[tokio::test]
+async fn malformed_documents_are_rejected_before_loading() {
+    let mut config = AppConfig::default();
+    config.models.insert(
+        "nllb".to_string(),
+        ModelSpec {
+            path: "./models/missing".to_string(),
+            model_type: "nllb".to_string(),
+            ..Default::default()
+        },
+    );
+    let state = AppState {
+        model_manager: Arc::new(ModelManager::new(config)),
+    };
+
+    let request_body = json!({
+        "model": "nllb",
+        "format": "xml",
+        "messages": [{"role": "user", "content": "<doc>Hello <!-- unterminated"}]
+    });
+    let response = app(state)
+        .oneshot(
+            Request::post("/v1/chat/completions")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_vec(&request_body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["param"], "messages");
+    assert!(
+        body["error"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid XML document")
+    );
+}
@@ -0,0 +1,292 @@
+use axum::{
+    body::Body,
+    http::{HeaderValue, Request, StatusCode, header::AUTHORIZATION},
+};
+use ctranslate2_server::{
+    app,
+    config::{AppConfig, GlossaryConfig, ModelSpec},
+    glossary::{self, Glossary, GlossaryError, GlossaryOrigin, Term},
+    model::{GenerationParams, ModelError, ModelManager},
+    protect,
+    state::AppState,
+};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn term(source: &str, target: &str) -> Term {
+    Term {
+        source: source.to_string(),
+        target: target.to_string(),
+    }
+}
+
+fn brands() -> Glossary {
+    Glossary::new(
+        "brands",
+        Some("eng_Latn".to_string()),
+        "fra_Latn".to_string(),
+        vec![
+            term("Acme", "Acme"),
+            term("Acme Cloud", "Acme Cloud"),
+            term("dashboard", "tableau de bord"),
+        ],
+        false,
+        GlossaryOrigin::Api,
+    )
+    .unwrap()
+}
+
+#[test]
+fn terms_are_parsed_from_csv_and_tsv() {
+    let csv =
+        "source,target,note\n\"Acme, Inc.\",\"Acme, Inc.\",legal name\n# comment\n\nWidget,\n";
+    assert_eq!(
+        glossary::parse_terms(csv, None).unwrap(),
+        vec![term("Acme, Inc.", "Acme, Inc."), term("Widget", "Widget")]
+    );
+
+    let tsv = "dashboard\ttableau de bord\n\"quoted \"\"term\"\"\"\tterme\n";
+    assert_eq!(
+        glossary::parse_terms(tsv, None).unwrap(),
+        vec![
+            term("dashboard", "tableau de bord"),
+            term("quoted \"term\"", "terme")
+        ]
+    );
+
+    assert!(matches!(
+        glossary::parse_terms("ok,fine\nmissing target\n", None),
+        Err(GlossaryError::Syntax { line: 2, .. })
+    ));
+}
+
+#[test]
+fn longest_terms_are_masked_and_restored() {
+    let glossary = brands();
+    let (masked, terms) = glossary.mask("Open the acme cloud Dashboard, not the dashboards.");
+    assert_eq!(masked.text, "Open the [[0]] [[1]], not the dashboards.");
+    assert_eq!(
+        terms,
+        vec![
+            term("Acme Cloud", "Acme Cloud"),
+            term("dashboard", "tableau de bord")
+        ]
+    );
+
+    let restored = masked.restore("Ouvrez le [[1]] [ [0] ]");
+    assert_eq!(restored.text, "Ouvrez le tableau de bord Acme Cloud");
+    assert!(restored.lost.is_empty());
+
+    let restored = masked.restore("Ouvrez [[1]]");
+    assert_eq!(restored.text, "Ouvrez tableau de bord");
+    assert_eq!(restored.lost, vec![0]);
+}
+
+#[test]
+fn placeholders_not_ours_are_kept() {
+    let (masked, _) = protect::mask("a b", [(0..1, "A".to_string(), ())]);
+    assert_eq!(masked.text, "[[0]] b");
+    assert_eq!(masked.restore("[[0]] [[7]]").text, "A [[7]]");
+}
+
+#[test]
+fn glossaries_only_apply_to_their_language_pair() {
+    let glossary = brands();
+    assert!(
+        glossary
+            .check_languages(Some("eng_Latn"), "fra_Latn", "nllb")
+            .is_ok()
+    );
+    assert!(glossary.check_languages(None, "fra_Latn", "nllb").is_ok());
+    assert!(matches!(
+        glossary.check_languages(Some("eng_Latn"), "deu_Latn", "nllb"),
+        Err(GlossaryError::LanguageMismatch { .. })
+    ));
+
+    // Languages declared in other codes match the model's own codes for them
+    let glossary = Glossary::new(
+        "short",
+        Some("en".to_string()),
+        "fr".to_string(),
+        vec![term("Acme", "Acme")],
+        false,
+        GlossaryOrigin::Config,
+    )
+    .unwrap();
+    assert!(
+        glossary
+            .check_languages(Some("eng_Latn"), "fra_Latn", "nllb")
+            .is_ok()
+    );
+    assert!(
+        glossary
+            .check_languages(Some("__en__"), "__fr__", "m2m100")
+            .is_ok()
+    );
+    assert!(matches!(
+        glossary.check_languages(Some("eng_Latn"), "deu_Latn", "nllb"),
+        Err(GlossaryError::LanguageMismatch { .. })
+    ));
+}
+
+#[tokio::test]
+async fn unknown_or_mismatched_glossaries_fail_before_loading() {
+    let mut config = AppConfig::default();
+    config.models.insert(
+        "nllb".to_string(),
+        ModelSpec {
+            path: "/non/existent/path".to_string(),
+            model_type: "nllb".to_string(),
+            ..Default::default()
+        },
+    );
+    let manager = ModelManager::new(config);
+    manager.glossaries().insert(brands());
+
+    let result = manager
+        .generate(
+            "nllb",
+            vec!["Hello".to_string()],
+            GenerationParams {
+                glossary_id: Some("missing".to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(ModelError::Glossary {
+            source: GlossaryError::NotFound { .. },
+            ..
+        })
+    ));
+
+    let result = manager
+        .generate(
+            "nllb",
+            vec!["Hello".to_string()],
+            GenerationParams {
+                glossary_id: Some("brands".to_string()),
+                target_lang: Some("deu_Latn".to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(ModelError::Glossary {
+            source: GlossaryError::LanguageMismatch { .. },
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn admin_endpoints_manage_glossaries() {
+    let path = std::env::temp_dir().join(format!("ct2-glossary-{}.tsv", std::process::id()));
+    std::fs::write(&path, "Acme\tAcme\n").unwrap();
+    let mut config = AppConfig::default();
+    config.glossaries.insert(
+        "configured".to_string(),
+        GlossaryConfig {
+            path: path.display().to_string(),
+            source_lang: None,
+            target_lang: "deu_Latn".to_string(),
+            case_sensitive: true,
+        },
+    );
+    config.server.admin_token = Some("secret".to_string());
+    config.models.insert(
+        "nllb".to_string(),
+        ModelSpec {
+            path: "/non/existent/path".to_string(),
+            model_type: "nllb".to_string(),
+            ..Default::default()
+        },
+    );
+    let state = AppState {
+        model_manager: Arc::new(ModelManager::new(config)),
+    };
+
+    let send = |mut request: Request<Body>| {
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        let app = app(state.clone());
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (
+                status,
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default(),
+            )
+        }
+    };
+
+    let (status, info) = send(
+        Request::put("/admin/glossaries/brands?source_lang=eng_Latn&target_lang=fra_Latn")
+            .header("Content-Type", "text/csv")
+            .body(Body::from("source,target\nAcme Cloud,Acme Cloud\n"))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(info["terms"], 1);
+    assert_eq!(info["origin"], "api");
+
+    let (status, list) = send(
+        Request::get("/admin/glossaries")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list[0]["id"], "brands");
+    assert_eq!(list[1]["id"], "configured");
+    assert_eq!(list[1]["origin"], "config");
+
+    let (status, glossary) = send(
+        Request::get("/admin/glossaries/brands")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(glossary["terms"][0]["source"], "Acme Cloud");
+
+    // Languages are declared in the codes of the model the glossary is meant for
+    let (status, info) = send(
+        Request::put("/admin/glossaries/short?source_lang=en&target_lang=fr&model=nllb")
+            .body(Body::from("Acme,Acme\n"))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(info["source_lang"], "eng_Latn");
+    assert_eq!(info["target_lang"], "fra_Latn");
+
+    let (status, _) = send(
+        Request::put("/admin/glossaries/broken?target_lang=fra_Latn")
+            .body(Body::from("only one column\n"))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send(
+        Request::delete("/admin/glossaries/configured")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let uri = "/admin/glossaries/brands";
+    let (status, _) = send(Request::delete(uri).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(Request::delete(uri).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -2,7 +2,12 @@ use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
-use ctranslate2_server::{app, config::AppConfig, model::ModelManager, state::AppState};
+use ctranslate2_server::{
+    app,
+    config::{AppConfig, ModelSpec},
+    model::ModelManager,
+    state::AppState,
+};
 use std::sync::Arc;
 use tower::ServiceExt; // for `oneshot`
 
@@ -24,3 +29,48 @@ async fn health_check_works() {
 
     assert_eq!(response.status(), StatusCode::OK);
 }
+
+async fn get_status(state: AppState, uri: &str) -> StatusCode {
+    app(state)
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn liveness_and_readiness_without_preload() {
+    let model_manager = Arc::new(ModelManager::new(AppConfig::default()));
+    let state = AppState { model_manager };
+
+    assert_eq!(
+        get_status(state.clone(), "/health/live").await,
+        StatusCode::OK
+    );
+    assert_eq!(get_status(state, "/health/ready").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn readiness_returns_503_until_preload_completes() {
+    let mut config = AppConfig::default();
+    config.models.insert(
+        "t5".to_string(),
+        ModelSpec {
+            path: "/non/existent/path".to_string(),
+            model_type: "t5".to_string(),
+            preload: true,
+            ..Default::default()
+        },
+    );
+    let model_manager = Arc::new(ModelManager::new(config));
+    let state = AppState { model_manager };
+
+    assert_eq!(
+        get_status(state.clone(), "/health/live").await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        get_status(state, "/health/ready").await,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+}
@@ -0,0 +1,116 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use ctranslate2_server::{
+    app,
+    config::AppConfig,
+    format::json::{JsonDocument, Selector},
+    model::ModelManager,
+    state::AppState,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn selectors(items: &[&str]) -> Vec<Selector> {
+    items.iter().map(|s| s.parse().unwrap()).collect()
+}
+
+fn catalog() -> serde_json::Value {
+    json!({
+        "sku": "AB-12",
+        "products": [
+            {"name": "Red shoes", "price": 20, "tags": ["summer", "sale"]},
+            {"name": "Blue hat", "details": {"care": "Hand wash", "material": "Cotton"}}
+        ],
+        "title": "Spring catalog"
+    })
+}
+
+#[test]
+fn included_strings_are_translated_in_place() {
+    let include = selectors(&["$.products[*].name", "$..details", "$['title']"]);
+    let exclude = selectors(&["$..material"]);
+    let document = JsonDocument::new(catalog(), &include, &exclude);
+    assert_eq!(
+        document.prompts(),
+        ["Red shoes", "Blue hat", "Hand wash", "Spring catalog"]
+    );
+
+    let output = document.render(&[
+        "Chaussures rouges".to_string(),
+        "Chapeau bleu".to_string(),
+        "Lavage à la main".to_string(),
+        "Catalogue de printemps".to_string(),
+    ]);
+    assert_eq!(
+        output,
+        json!({
+            "sku": "AB-12",
+            "products": [
+                {"name": "Chaussures rouges", "price": 20, "tags": ["summer", "sale"]},
+                {"name": "Chapeau bleu", "details": {"care": "Lavage à la main", "material": "Cotton"}}
+            ],
+            "title": "Catalogue de printemps"
+        })
+    );
+    // Keys keep their order
+    let keys: Vec<&String> = output.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["sku", "products", "title"]);
+}
+
+#[test]
+fn without_include_every_string_with_words_is_translated() {
+    let document = JsonDocument::new(catalog(), &[], &selectors(&["$.products[0].tags[1]"]));
+    assert_eq!(
+        document.prompts(),
+        [
+            "AB-12",
+            "Red shoes",
+            "summer",
+            "Blue hat",
+            "Hand wash",
+            "Cotton",
+            "Spring catalog"
+        ]
+    );
+}
+
+#[test]
+fn malformed_selectors_are_rejected() {
+    for selector in ["products.name", "$.products[", "$.a..", "$[x]", "$['a]"] {
+        assert!(
+            selector.parse::<Selector>().is_err(),
+            "{} should be rejected",
+            selector
+        );
+    }
+}
+
+#[tokio::test]
+async fn json_endpoint_names_the_invalid_selector_field() {
+    let state = AppState {
+        model_manager: Arc::new(ModelManager::new(AppConfig::default())),
+    };
+    let request_body = json!({
+        "input": {"a": "Hello"},
+        "exclude": ["a.b"]
+    });
+    let response = app(state)
+        .oneshot(
+            Request::post("/v1/json")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_vec(&request_body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["param"], "exclude");
+}
@@ -0,0 +1,84 @@
+use ctranslate2_server::lang::{self, LanguageError};
+
+#[test]
+fn code_systems_normalize_to_flores_for_nllb() {
+    for tag in [
+        "fr", "fr-FR", "FR_fr", "fra", "__fr__", "fra_Latn", "FRA_LATN",
+    ] {
+        assert_eq!(
+            lang::normalize(tag, "nllb").as_deref(),
+            Ok("fra_Latn"),
+            "{}",
+            tag
+        );
+    }
+    assert_eq!(
+        lang::normalize("zh-Hans", "nllb").as_deref(),
+        Ok("zho_Hans")
+    );
+    assert_eq!(
+        lang::normalize("zh-Hant", "nllb").as_deref(),
+        Ok("zho_Hant")
+    );
+    assert_eq!(lang::normalize("zh-TW", "nllb").as_deref(), Ok("zho_Hant"));
+    assert_eq!(lang::normalize("ast", "nllb").as_deref(), Ok("ast_Latn"));
+    assert_eq!(
+        lang::normalize("ace-Latn", "nllb").as_deref(),
+        Ok("ace_Latn")
+    );
+    // Four-character variants are not scripts
+    assert_eq!(
+        lang::normalize("de-1996", "nllb").as_deref(),
+        Ok("deu_Latn")
+    );
+}
+
+#[test]
+fn code_systems_normalize_to_tokens_for_m2m100() {
+    for tag in ["de", "de-AT", "deu", "deu_Latn", "__de__"] {
+        assert_eq!(
+            lang::normalize(tag, "m2m100").as_deref(),
+            Ok("__de__"),
+            "{}",
+            tag
+        );
+    }
+    assert_eq!(
+        lang::normalize("zh-Hant", "m2m100").as_deref(),
+        Ok("__zh__")
+    );
+    assert_eq!(
+        lang::normalize("nob_Latn", "m2m100").as_deref(),
+        Ok("__no__")
+    );
+    assert_eq!(
+        lang::normalize("ast_Latn", "m2m100").as_deref(),
+        Ok("__ast__")
+    );
+}
+
+#[test]
+fn languages_the_model_lacks_are_rejected() {
+    assert_eq!(
+        lang::normalize("tlh", "nllb"),
+        Err(LanguageError::Unknown {
+            code: "tlh".to_string()
+        })
+    );
+    assert_eq!(
+        lang::normalize("sr-Latn", "nllb"),
+        Err(LanguageError::Unsupported {
+            code: "sr-Latn".to_string(),
+            model_type: "nllb".to_string()
+        })
+    );
+    assert!(matches!(
+        lang::normalize("fao_Latn", "m2m100"),
+        Err(LanguageError::Unsupported { .. })
+    ));
+}
+
+#[test]
+fn codes_pass_through_for_other_model_types() {
+    assert_eq!(lang::normalize("fr-FR", "t5").as_deref(), Ok("fr-FR"));
+}
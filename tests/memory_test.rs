@@ -0,0 +1,246 @@
+use axum::{
+    body::Body,
+    http::{HeaderValue, Request, StatusCode, header::AUTHORIZATION},
+};
+use ctranslate2_server::{
+    app,
+    config::{AppConfig, ModelSpec, TranslationMemoryConfig},
+    memory::{MemoryError, TranslationMemory, similarity},
+    model::ModelManager,
+    state::AppState,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn memory_config(name: &str) -> TranslationMemoryConfig {
+    let dir = std::env::temp_dir().join(format!("ct2-memory-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    TranslationMemoryConfig {
+        path: Some(dir.display().to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn similarity_ignores_whitespace() {
+    assert_eq!(similarity("Hello  world", " Hello world"), 1.0);
+    assert!(similarity("Save the file", "Save the files") > 0.9);
+    assert!(similarity("Save the file", "Open a window") < 0.5);
+}
+
+#[test]
+fn entries_are_matched_and_persisted_per_language_pair() {
+    let config = memory_config("persist");
+    let memory = TranslationMemory::open(&config).unwrap();
+    assert!(memory.is_enabled());
+
+    let saved = memory
+        .add(
+            "eng_Latn",
+            "fra_Latn",
+            "Save the file",
+            "Enregistrer le fichier",
+        )
+        .unwrap();
+    memory
+        .add("eng_Latn", "deu_Latn", "Save the file", "Datei speichern")
+        .unwrap();
+
+    let exact = memory.search("eng_Latn", "fra_Latn", "Save  the file", 0.8, 5);
+    assert_eq!(exact.len(), 1);
+    assert_eq!(exact[0].score, 1.0);
+    assert_eq!(exact[0].target, "Enregistrer le fichier");
+
+    let fuzzy = memory.search("eng_Latn", "fra_Latn", "Save the files", 0.8, 5);
+    assert_eq!(fuzzy.len(), 1);
+    assert!(fuzzy[0].score < 1.0);
+    assert!(
+        memory
+            .search("eng_Latn", "fra_Latn", "Open a window", 0.8, 5)
+            .is_empty()
+    );
+
+    // Survives a restart
+    let reopened = TranslationMemory::open(&config).unwrap();
+    assert_eq!(reopened.entries("eng_Latn", "deu_Latn").len(), 1);
+
+    reopened.delete(saved.id).unwrap();
+    assert!(reopened.entries("eng_Latn", "fra_Latn").is_empty());
+    assert!(matches!(
+        reopened.delete(saved.id),
+        Err(MemoryError::EntryNotFound { .. })
+    ));
+    assert!(matches!(
+        reopened.add("../etc", "fra_Latn", "a", "b"),
+        Err(MemoryError::InvalidLanguage { .. })
+    ));
+}
+
+#[test]
+fn ids_are_not_reused_after_deletion() {
+    let config = memory_config("ids");
+    let memory = TranslationMemory::open(&config).unwrap();
+    memory
+        .add("eng_Latn", "fra_Latn", "Open", "Ouvrir")
+        .unwrap();
+    let newest = memory
+        .add("eng_Latn", "fra_Latn", "Close", "Fermer")
+        .unwrap();
+    memory.delete(newest.id).unwrap();
+    let replaced = memory
+        .add("eng_Latn", "fra_Latn", "Open", "Ouvrir le fichier")
+        .unwrap();
+    assert!(replaced.id > newest.id);
+
+    // Replacements and deletions are replayed on restart, and the log gets compacted
+    let reopened = TranslationMemory::open(&config).unwrap();
+    let entries = reopened.entries("eng_Latn", "fra_Latn");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].target, "Ouvrir le fichier");
+    reopened.delete(replaced.id).unwrap();
+
+    let reopened = TranslationMemory::open(&config).unwrap();
+    let added = reopened
+        .add("eng_Latn", "fra_Latn", "Save", "Enregistrer")
+        .unwrap();
+    assert!(added.id > replaced.id);
+}
+
+#[test]
+fn exact_lookups_follow_replacements_and_deletions() {
+    let config = memory_config("exact");
+    let memory = TranslationMemory::open(&config).unwrap();
+    let first = memory
+        .add("eng_Latn", "fra_Latn", "Open", "Ouvrir")
+        .unwrap();
+    memory
+        .add("eng_Latn", "fra_Latn", "Close", "Fermer")
+        .unwrap();
+    memory
+        .add("eng_Latn", "fra_Latn", "Save", "Sauver")
+        .unwrap();
+    memory.delete(first.id).unwrap();
+    memory
+        .add("eng_Latn", "fra_Latn", "Save", "Enregistrer")
+        .unwrap();
+
+    let found = memory.search("eng_Latn", "fra_Latn", "Save", 0.8, 1);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].target, "Enregistrer");
+    assert!(
+        memory
+            .search("eng_Latn", "fra_Latn", "Open", 1.0, 5)
+            .is_empty()
+    );
+    let targets: Vec<String> = memory
+        .entries("eng_Latn", "fra_Latn")
+        .into_iter()
+        .map(|e| e.target)
+        .collect();
+    assert_eq!(targets, ["Fermer", "Enregistrer"]);
+}
+
+#[test]
+fn disabled_memory_rejects_writes() {
+    let memory = TranslationMemory::open(&TranslationMemoryConfig::default()).unwrap();
+    assert!(!memory.is_enabled());
+    assert!(matches!(
+        memory.add("eng_Latn", "fra_Latn", "a", "b"),
+        Err(MemoryError::Disabled)
+    ));
+}
+
+#[tokio::test]
+async fn admin_endpoints_add_search_and_delete() {
+    let mut config = AppConfig {
+        translation_memory: memory_config("admin"),
+        ..Default::default()
+    };
+    config.models.insert(
+        "nllb".to_string(),
+        ModelSpec {
+            path: "/non/existent/path".to_string(),
+            model_type: "nllb".to_string(),
+            ..Default::default()
+        },
+    );
+    config.server.admin_token = Some("secret".to_string());
+    let state = AppState {
+        model_manager: Arc::new(ModelManager::new(config)),
+    };
+
+    let send = |mut request: Request<Body>| {
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        let app = app(state.clone());
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (
+                status,
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            )
+        }
+    };
+
+    let entry = json!({
+        "source_lang": "eng_Latn",
+        "target_lang": "fra_Latn",
+        "source": "Cancel",
+        "target": "Annuler"
+    });
+    let (status, added) = send(
+        Request::post("/admin/memory")
+            .header("Content-Type", "application/json")
+            .body(Body::from(entry.to_string()))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, found) = send(
+        Request::get("/admin/memory?source_lang=eng_Latn&target_lang=fra_Latn&q=Cancel")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found[0]["target"], "Annuler");
+
+    // Languages are stored in the codes of the model the entry is for
+    let entry = json!({
+        "source_lang": "en",
+        "target_lang": "fr",
+        "source": "Open",
+        "target": "Ouvrir",
+        "model": "nllb"
+    });
+    let (status, _) = send(
+        Request::post("/admin/memory")
+            .header("Content-Type", "application/json")
+            .body(Body::from(entry.to_string()))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, found) = send(
+        Request::get("/admin/memory?source_lang=en-US&target_lang=fr&q=Open&model=nllb")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found[0]["source_lang"], "eng_Latn");
+    assert_eq!(found[0]["target_lang"], "fra_Latn");
+
+    let uri = format!("/admin/memory/{}", added["id"]);
+    let (status, _) = send(Request::delete(&uri).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(Request::delete(&uri).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -13,13 +13,7 @@ async fn model_manager_load_error_on_invalid_path() {
         ctranslate2_server::config::ModelSpec {
             path: "/non/existent/path".to_string(),
             model_type: "t5".to_string(),
-            tokenizer_path: None,
-            target_lang: None,
-            device: None,
-            device_indices: None,
-            beam_size: None,
-            repetition_penalty: None,
-            no_repeat_ngram_size: None,
+            ..Default::default()
         },
     );
 
@@ -39,13 +33,7 @@ async fn model_manager_generate_error_if_not_loaded() {
         ctranslate2_server::config::ModelSpec {
             path: "/tmp".to_string(),
             model_type: "t5".to_string(),
-            tokenizer_path: None,
-            target_lang: None,
-            device: None,
-            device_indices: None,
-            beam_size: None,
-            repetition_penalty: None,
-            no_repeat_ngram_size: None,
+            ..Default::default()
         },
     );
     let manager = ModelManager::new(config);
@@ -55,12 +43,40 @@ async fn model_manager_generate_error_if_not_loaded() {
     assert!(result.is_err());
 }
 
+#[tokio::test]
+async fn model_manager_not_ready_until_preload_succeeds() {
+    let mut config = AppConfig::default();
+    config.models.insert(
+        "t5".to_string(),
+        ctranslate2_server::config::ModelSpec {
+            path: "/non/existent/path".to_string(),
+            model_type: "t5".to_string(),
+            preload: true,
+            warmup_inputs: vec!["Hello".into()],
+            ..Default::default()
+        },
+    );
+
+    let manager = ModelManager::new(config);
+    assert!(!manager.is_ready());
+    assert!(manager.preload().await.is_err());
+    assert!(!manager.is_ready());
+}
+
+#[tokio::test]
+async fn model_manager_ready_without_preloaded_models() {
+    let manager = ModelManager::new(AppConfig::default());
+    assert!(manager.is_ready());
+    assert!(manager.preload().await.is_ok());
+    assert!(manager.is_ready());
+}
+
 #[test]
 fn resolve_alias_works() {
     let mut config = AppConfig::default();
     config
         .aliases
-        .insert("nllb-small".to_string(), "nllb".to_string());
+        .insert("nllb-small".to_string(), "nllb".into());
     config.default_model = "nllb".to_string();
 
     let manager = ModelManager::new(config);
@@ -69,3 +85,199 @@ fn resolve_alias_works() {
     assert_eq!(manager.resolve_model_name("default"), "nllb");
     assert_eq!(manager.resolve_model_name("other"), "other");
 }
+
+#[test]
+fn resolve_alias_chains_and_default_alias() {
+    let mut config = AppConfig::default();
+    config
+        .aliases
+        .insert("nllb".to_string(), "nllb-distilled".into());
+    config.aliases.insert(
+        "nllb-distilled".to_string(),
+        "nllb-200-distilled-600M".into(),
+    );
+    config.default_model = "nllb".to_string();
+
+    let manager = ModelManager::new(config);
+
+    assert_eq!(
+        manager.resolve_model_name("nllb"),
+        "nllb-200-distilled-600M"
+    );
+    assert_eq!(
+        manager.resolve_model_name("default"),
+        "nllb-200-distilled-600M"
+    );
+    assert_eq!(manager.resolve_model_name(""), "nllb-200-distilled-600M");
+}
+
+#[test]
+fn resolve_alias_cycle_is_an_error() {
+    let mut config = AppConfig::default();
+    config.aliases.insert("a".to_string(), "b".into());
+    config.aliases.insert("b".to_string(), "a".into());
+
+    let manager = ModelManager::new(config);
+
+    let err = manager.try_resolve_model_name("a").unwrap_err();
+    assert!(err.to_string().contains("Alias cycle a -> b -> a"));
+    assert_eq!(manager.resolve_model_name("a"), "a");
+}
+
+#[test]
+fn generation_params_fall_back_per_field() {
+    let request = GenerationParams {
+        beam_size: Some(2),
+        suppress_sequences: Some(vec!["banned".into()]),
+        ..Default::default()
+    };
+    let model = GenerationParams {
+        beam_size: Some(8),
+        length_penalty: Some(1.5),
+        disable_unk: Some(true),
+        ..Default::default()
+    };
+
+    let params = request.or(&model);
+    assert_eq!(params.beam_size, Some(2));
+    assert_eq!(params.length_penalty, Some(1.5));
+    assert_eq!(params.disable_unk, Some(true));
+    assert_eq!(params.suppress_sequences, Some(vec!["banned".to_string()]));
+    assert_eq!(params.patience, None);
+}
+
+#[test]
+fn target_languages_fall_back_as_one_setting() {
+    let alias = GenerationParams {
+        target_langs: Some(vec!["fra_Latn".into(), "deu_Latn".into()]),
+        ..Default::default()
+    };
+    let defaults = GenerationParams {
+        target_lang: Some("eng_Latn".into()),
+        ..Default::default()
+    };
+
+    let params = GenerationParams::default().or(&alias).or(&defaults);
+    assert_eq!(params.target_lang, None);
+    assert_eq!(params.target_langs, alias.target_langs);
+
+    let request = GenerationParams {
+        target_lang: Some("spa_Latn".into()),
+        ..Default::default()
+    };
+    let params = request.or(&alias).or(&defaults);
+    assert_eq!(params.target_lang.as_deref(), Some("spa_Latn"));
+    assert_eq!(params.target_langs, None);
+}
+
+#[test]
+fn limits_name_the_offending_field() {
+    use ctranslate2_server::model::{Limits, ModelError};
+    let limits = Limits {
+        max_batch_items: Some(2),
+        max_decoding_length: Some(64),
+        allowed_target_langs: Some(vec!["fra_Latn".into(), "deu_Latn".into()]),
+        ..Default::default()
+    }
+    .or(&Limits {
+        max_beam_size: Some(4),
+        max_decoding_length: Some(512),
+        ..Default::default()
+    });
+    assert_eq!(limits.max_beam_size, Some(4));
+    assert_eq!(limits.max_decoding_length, Some(64));
+
+    let prompts = vec!["Hello".to_string()];
+    let param = |prompts: &[String], params: GenerationParams| match limits.check(prompts, &params)
+    {
+        Err(ModelError::LimitExceeded { param, .. }) => Some(param),
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(()) => None,
+    };
+
+    assert_eq!(param(&prompts, GenerationParams::default()), None);
+    assert_eq!(
+        param(&vec!["Hello".to_string(); 3], GenerationParams::default()).as_deref(),
+        Some("messages")
+    );
+    let params = GenerationParams {
+        max_decoding_length: Some(100),
+        ..Default::default()
+    };
+    assert_eq!(param(&prompts, params).as_deref(), Some("max_tokens"));
+    let params = GenerationParams {
+        target_lang: Some("zho_Hans".into()),
+        ..Default::default()
+    };
+    assert_eq!(param(&prompts, params).as_deref(), Some("target_lang"));
+
+    // Each input counts once per target language
+    let params = GenerationParams {
+        target_langs: Some(vec!["fra_Latn".into(), "deu_Latn".into()]),
+        ..Default::default()
+    };
+    assert_eq!(param(&prompts, params.clone()), None);
+    assert_eq!(
+        param(&vec!["Hello".to_string(); 2], params).as_deref(),
+        Some("messages")
+    );
+    let params = GenerationParams {
+        target_langs: Some(vec!["fra_Latn".into(), "zho_Hans".into()]),
+        ..Default::default()
+    };
+    assert_eq!(param(&prompts, params).as_deref(), Some("target_langs"));
+}
+
+#[tokio::test]
+async fn language_codes_are_normalized_for_the_model() {
+    use ctranslate2_server::{
+        config::ModelSpec,
+        lang::LanguageError,
+        model::{Limits, ModelError},
+    };
+    let mut config = AppConfig::default();
+    config.models.insert(
+        "nllb".to_string(),
+        ModelSpec {
+            path: "/non/existent/path".to_string(),
+            model_type: "nllb".to_string(),
+            limits: Limits {
+                allowed_target_langs: Some(vec!["fr".into()]),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+    let manager = ModelManager::new(config);
+    let generate = |target_lang: &str| {
+        manager.generate(
+            "nllb",
+            vec!["Hello".into()],
+            GenerationParams {
+                target_lang: Some(target_lang.into()),
+                source_lang: Some("en-US".into()),
+                ..Default::default()
+            },
+        )
+    };
+
+    // `fr-FR` and `fr` are both `fra_Latn`, so only loading the missing model fails
+    assert!(matches!(
+        generate("fr-FR").await,
+        Err(ModelError::LoadError { .. })
+    ));
+    match generate("tlh").await {
+        Err(ModelError::Language { param, source, .. }) => {
+            assert_eq!(param, "target_lang");
+            assert!(matches!(source, LanguageError::Unknown { .. }));
+        }
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    assert!(matches!(
+        generate("sr-Latn").await,
+        Err(ModelError::Language {
+            source: LanguageError::Unsupported { .. },
+            ..
+        })
+    ));
+}
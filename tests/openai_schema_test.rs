@@ -27,9 +27,35 @@ fn serialize_chat_completion_response() {
         model: "gpt-3.5-turbo".into(),
         choices: vec![],
         usage: None,
+        translations: Default::default(),
     };
 
     let json = serde_json::to_value(&response).unwrap();
     assert_eq!(json["id"], "chatcmpl-123");
     assert_eq!(json["object"], "chat.completion");
+    assert!(json.get("translations").is_none());
+}
+
+#[test]
+fn deserialize_decoding_extensions() {
+    let json = json!({
+        "model": "nllb",
+        "messages": [{"role": "user", "content": "Hello!"}],
+        "length_penalty": 1.5,
+        "patience": 2.0,
+        "min_decoding_length": 3,
+        "disable_unk": true,
+        "suppress_sequences": ["Acme Corp"]
+    });
+
+    let request: ChatCompletionRequest = serde_json::from_value(json).unwrap();
+    assert_eq!(request.length_penalty, Some(1.5));
+    assert_eq!(request.patience, Some(2.0));
+    assert_eq!(request.min_decoding_length, Some(3));
+    assert_eq!(request.disable_unk, Some(true));
+    assert_eq!(request.coverage_penalty, None);
+    assert_eq!(
+        request.suppress_sequences,
+        Some(vec!["Acme Corp".to_string()])
+    );
 }
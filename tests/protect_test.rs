@@ -0,0 +1,138 @@
+use ctranslate2_server::{
+    config::{AppConfig, ModelSpec},
+    model::{GenerationParams, ModelError, ModelManager},
+    protect::{self, Protector},
+};
+use std::collections::HashMap;
+
+fn protected(names: &[&str], text: &str) -> Vec<String> {
+    let names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+    let protector = Protector::new(&names, &HashMap::new()).unwrap();
+    protector
+        .find(text)
+        .into_iter()
+        .map(|range| text[range].to_string())
+        .collect()
+}
+
+#[test]
+fn presets_match_their_placeholders() {
+    assert_eq!(
+        protected(
+            &["icu"],
+            "Hi {name}, you have {count, plural, one {# item} other {# items}}."
+        ),
+        vec!["{name}", "{count, plural, one {# item} other {# items}}"]
+    );
+    assert_eq!(
+        protected(
+            &["printf"],
+            "%s has %1$d files, %(pct).1f%% done, 50% of %-5s"
+        ),
+        vec!["%s", "%1$d", "%(pct).1f", "%%", "%-5s"]
+    );
+    assert_eq!(
+        protected(&["i18next"], "{{count}} items, see $t(common.more)"),
+        vec!["{{count}}", "$t(common.more)"]
+    );
+    assert_eq!(
+        protected(
+            &["url", "email", "code"],
+            "Visit https://example.com/a?b=1, mail dev@example.org or run `make`."
+        ),
+        vec!["https://example.com/a?b=1", "dev@example.org", "`make`"]
+    );
+}
+
+#[test]
+fn overlapping_matches_keep_the_longest() {
+    assert_eq!(
+        protected(&["icu", "i18next"], "{{count}} left"),
+        vec!["{{count}}"]
+    );
+}
+
+#[test]
+fn custom_patterns_extend_and_override_presets() {
+    let custom = HashMap::from([
+        ("ticket".to_string(), r"JIRA-\d+".to_string()),
+        ("url".to_string(), r"internal://\S+".to_string()),
+    ]);
+    let names = vec!["ticket".to_string(), "url".to_string()];
+    let protector = Protector::new(&names, &custom).unwrap();
+    let text = "Fix JIRA-42 at internal://x, not https://example.com";
+    let spans: Vec<&str> = protector.find(text).into_iter().map(|r| &text[r]).collect();
+    assert_eq!(spans, vec!["JIRA-42", "internal://x,"]);
+
+    let err = Protector::new(&["icu2".to_string()], &custom).unwrap_err();
+    assert!(err.contains("unknown protection pattern 'icu2'"));
+    let broken = HashMap::from([("broken".to_string(), "(".to_string())]);
+    assert!(Protector::new(&["broken".to_string()], &broken).is_err());
+}
+
+#[test]
+fn protected_spans_are_restored_verbatim() {
+    let text = "Hello {name}, open %s";
+    let protector =
+        Protector::new(&["icu".to_string(), "printf".to_string()], &HashMap::new()).unwrap();
+    let spans = protector
+        .find(text)
+        .into_iter()
+        .map(|range| (range.clone(), text[range].to_string(), ()));
+    let (masked, _) = protect::mask(text, spans);
+    assert_eq!(masked.text, "Hello [[0]], open [[1]]");
+
+    let restored = masked.restore("Bonjour [[0]], ouvrez [ [1] ]");
+    assert_eq!(restored.text, "Bonjour {name}, ouvrez %s");
+    assert!(restored.lost.is_empty());
+
+    let restored = masked.restore("Bonjour, ouvrez [[1]]");
+    assert_eq!(restored.lost, vec![0]);
+}
+
+#[test]
+fn placeholders_already_in_the_input_are_left_alone() {
+    let text = "Keep [[0]] and {name}";
+    let (masked, _) = protect::mask(text, [(15..21, "{name}".to_string(), ())]);
+    assert_eq!(masked.text, "Keep [[0]] and [[1]]");
+    assert_eq!(masked.restore("[[1]] [[0]]").text, "{name} [[0]]");
+
+    // Huge indices are not placeholders and leave the numbering alone
+    let text = "Keep [[18446744073709551615]] and {name}";
+    let (masked, _) = protect::mask(text, [(34..40, "{name}".to_string(), ())]);
+    assert_eq!(masked.text, "Keep [[18446744073709551615]] and [[0]]");
+
+    // Nor can the largest index push ours out of reach of the restore
+    let text = "Keep [[999999]] and {name}";
+    let (masked, _) = protect::mask(text, [(20..26, "{name}".to_string(), ())]);
+    assert_eq!(masked.text, "Keep [[999999]] and [[0]]");
+    let restored = masked.restore("[[0]] [[999999]]");
+    assert_eq!(restored.text, "{name} [[999999]]");
+    assert!(restored.lost.is_empty());
+}
+
+#[tokio::test]
+async fn unknown_protection_patterns_fail_before_loading() {
+    let mut config = AppConfig::default();
+    config.models.insert(
+        "nllb".to_string(),
+        ModelSpec {
+            path: "/non/existent/path".to_string(),
+            model_type: "nllb".to_string(),
+            ..Default::default()
+        },
+    );
+    let manager = ModelManager::new(config);
+
+    let result = manager
+        .generate(
+            "nllb",
+            vec!["Hello {name}".to_string()],
+            GenerationParams {
+                protect: Some(vec!["icu".to_string(), "nope".to_string()]),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert!(matches!(result, Err(ModelError::InvalidProtection { .. })));
+}
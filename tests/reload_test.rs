@@ -0,0 +1,134 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use ctranslate2_server::{
+    app,
+    config::{AppConfig, Args},
+    model::ModelManager,
+    state::AppState,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ct2-reload-{}-{}", name, std::process::id()))
+}
+
+/// Creates an empty model directory that passes config validation.
+fn model_dir(name: &str) -> String {
+    let dir = temp_path(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("tokenizer.json"), "{}").unwrap();
+    dir.display().to_string()
+}
+
+fn write_config(name: &str, contents: &str) -> PathBuf {
+    let path = temp_path(name).with_extension("toml");
+    std::fs::write(&path, contents.replace("$MODELS", &model_dir("models"))).unwrap();
+    path
+}
+
+fn load(path: &Path) -> AppConfig {
+    AppConfig::load(Some(Args {
+        host: None,
+        port: None,
+        config: path.display().to_string(),
+        check_config: false,
+        command: None,
+    }))
+    .unwrap()
+}
+
+const BASE_CONFIG: &str = r#"
+default_model = "small"
+
+[server]
+host = "0.0.0.0"
+port = 8080
+
+[aliases]
+"small" = "t5-small"
+
+[models."t5-small"]
+path = "$MODELS"
+model_type = "t5"
+"#;
+
+#[tokio::test]
+async fn reload_swaps_in_new_aliases() {
+    let path = write_config("swap", BASE_CONFIG);
+    let manager = ModelManager::new(load(&path));
+    assert_eq!(manager.resolve_model_name("small"), "t5-small");
+
+    let updated = format!(
+        "{}\n[models.\"t5-base\"]\npath = \"$MODELS\"\nmodel_type = \"t5\"\n",
+        BASE_CONFIG
+    )
+    .replace("\"small\" = \"t5-small\"", "\"small\" = \"t5-base\"");
+    std::fs::write(&path, updated.replace("$MODELS", &model_dir("models"))).unwrap();
+
+    let summary = manager.reload().await.unwrap();
+    assert!(summary.kept.is_empty());
+    assert!(summary.unloaded.is_empty());
+    assert_eq!(manager.resolve_model_name("small"), "t5-base");
+    assert!(manager.config().models.contains_key("t5-base"));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn invalid_reload_keeps_current_config() {
+    let path = write_config("invalid", BASE_CONFIG);
+    let manager = ModelManager::new(load(&path));
+
+    let broken = BASE_CONFIG.replace("\"small\" = \"t5-small\"", "\"small\" = \"missing\"");
+    std::fs::write(&path, broken.replace("$MODELS", &model_dir("models"))).unwrap();
+
+    assert!(manager.reload().await.is_err());
+    assert_eq!(manager.resolve_model_name("small"), "t5-small");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn admin_reload_requires_token() {
+    let mut config = AppConfig::default();
+    config.server.admin_token = Some("secret".to_string());
+    let model_manager = Arc::new(ModelManager::new(config));
+    let state = AppState { model_manager };
+
+    let response = app(state)
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/reload")
+                .header("Authorization", "Bearer wrong")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn admin_endpoints_are_disabled_without_token() {
+    let model_manager = Arc::new(ModelManager::new(AppConfig::default()));
+    let state = AppState { model_manager };
+
+    let response = app(state)
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/reload")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,195 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use ctranslate2_server::{
+    app,
+    config::AppConfig,
+    format::resource::{ResourceDocument, ResourceFormat},
+    model::ModelManager,
+    state::AppState,
+};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn json_values_are_translated_with_icu_branches_and_placeholders() {
+    let input = r#"{
+  "app": {
+    "title": "My App",
+    "count": 3,
+    "inbox": "Hi {name}, you have {n, plural, one {# message} other {# messages}}!",
+    "formats": ["Save %s", "%d%%"]
+  }
+}"#;
+    let document = ResourceDocument::parse(input, ResourceFormat::Json, false).unwrap();
+    assert_eq!(
+        document.prompts(),
+        strings(&[
+            "My App",
+            "Hi [[0]], you have ",
+            "[[0]] message",
+            "[[0]] messages",
+            "Save [[0]]",
+        ])
+    );
+
+    let (json, lost) = document.render(&strings(&[
+        "Mon appli",
+        "Salut [[0]], vous avez ",
+        "[[0]] \"message\"",
+        "[[0]] messages",
+        "Enregistrer [[0]]",
+    ]));
+    assert!(lost.is_empty());
+    assert_eq!(
+        json,
+        r#"{
+  "app": {
+    "title": "Mon appli",
+    "count": 3,
+    "inbox": "Salut {name}, vous avez {n, plural, one {# \"message\"} other {# messages}}!",
+    "formats": ["Enregistrer %s", "%d%%"]
+  }
+}"#
+    );
+}
+
+#[test]
+fn yaml_keeps_keys_comments_and_quoting() {
+    let input = "# Greetings\nen:\n  title: My App # shown in the title bar\n  quote: 'It''s here'\n  enabled: true\n  days:\n    - Monday\n  help: |\n    First line\n    Second line\n";
+    let document = ResourceDocument::parse(input, ResourceFormat::Yaml, false).unwrap();
+    assert_eq!(
+        document.prompts(),
+        strings(&["My App", "It's here", "Monday", "First line\nSecond line"])
+    );
+
+    let (yaml, _) = document.render(&strings(&[
+        "Appli: la mienne",
+        "C'est ici",
+        "Lundi",
+        "Première ligne\nDeuxième ligne",
+    ]));
+    assert_eq!(
+        yaml,
+        "# Greetings\nen:\n  title: \"Appli: la mienne\" # shown in the title bar\n  quote: 'C''est ici'\n  enabled: true\n  days:\n    - Lundi\n  help: |\n    Première ligne\n    Deuxième ligne\n"
+    );
+}
+
+const PO: &str = r#"msgid ""
+msgstr ""
+"Plural-Forms: nplurals=3; plural=(n==1 ? 0 : n%10>=2 && n%10<=4 ? 1 : 2);\n"
+
+#: src/main.c:12
+msgid "Open"
+msgstr "Otwórz"
+
+#, c-format
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] ""
+msgstr[1] ""
+msgstr[2] ""
+"#;
+
+#[test]
+fn po_translated_messages_are_kept_unless_forced() {
+    let document = ResourceDocument::parse(PO, ResourceFormat::Po, false).unwrap();
+    assert_eq!(document.prompts(), strings(&["[[0]] file", "[[0]] files"]));
+    assert_eq!((document.pending(), document.skipped()), (1, 1));
+
+    let (po, _) = document.render(&strings(&["[[0]] plik", "[[0]] pliki"]));
+    assert!(po.contains("msgid \"Open\"\nmsgstr \"Otwórz\"\n"));
+    assert!(
+        po.ends_with("msgstr[0] \"%d plik\"\nmsgstr[1] \"%d pliki\"\nmsgstr[2] \"%d pliki\"\n")
+    );
+
+    let forced = ResourceDocument::parse(PO, ResourceFormat::Po, true).unwrap();
+    assert_eq!(forced.prompts().len(), 3);
+}
+
+#[test]
+fn xliff_targets_are_filled_around_inline_elements() {
+    let input = r#"<xliff version="1.2"><file source-language="en" target-language="fr"><body>
+    <trans-unit id="greeting">
+      <source>Hello <g id="1">world</g> &amp; you</source>
+    </trans-unit>
+    <trans-unit id="done"><source>Done</source><target>Fini</target></trans-unit>
+    <trans-unit id="brand" translate="no"><source>Acme</source></trans-unit>
+</body></file></xliff>"#;
+    let document = ResourceDocument::parse(input, ResourceFormat::Xliff, false).unwrap();
+    assert_eq!(
+        document.prompts(),
+        strings(&["Hello [[0]]world[[1]] & you"])
+    );
+
+    let (xliff, lost) = document.render(&strings(&["Bonjour [[0]]le monde[[1]] & vous"]));
+    assert!(lost.is_empty());
+    assert!(xliff.contains(
+        "<source>Hello <g id=\"1\">world</g> &amp; you</source>\n      <target>Bonjour <g id=\"1\">le monde</g> &amp; vous</target>\n    </trans-unit>"
+    ));
+    assert!(xliff.contains("<target>Fini</target>"));
+    assert!(xliff.contains("<source>Acme</source></trans-unit>"));
+}
+
+#[test]
+fn previous_translations_of_a_bundle_are_reused() {
+    let mut document =
+        ResourceDocument::parse(r#"{"a": "One", "b": "Two"}"#, ResourceFormat::Json, false)
+            .unwrap();
+    let previous = ResourceDocument::parse(r#"{"a": "Un"}"#, ResourceFormat::Json, false).unwrap();
+    document.reuse(&previous);
+    assert_eq!(document.prompts(), strings(&["Two"]));
+
+    let (json, _) = document.render(&strings(&["Deux"]));
+    assert_eq!(json, r#"{"a": "Un", "b": "Deux"}"#);
+}
+
+#[test]
+fn malformed_resources_report_their_line() {
+    let error =
+        ResourceDocument::parse("msgid \"a\"\nmsgstr \"b\nfoo\n", ResourceFormat::Po, false)
+            .err()
+            .unwrap();
+    assert_eq!(error.to_string(), "Invalid PO file, line 2: invalid string");
+
+    let error = ResourceDocument::parse("en:\n  just text\n", ResourceFormat::Yaml, false)
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Invalid YAML file, line 2: expected a key"
+    );
+}
+
+#[tokio::test]
+async fn resources_endpoint_needs_a_known_format() {
+    let state = AppState {
+        model_manager: Arc::new(ModelManager::new(AppConfig::default())),
+    };
+    let response = app(state.clone())
+        .oneshot(
+            Request::post("/v1/resources?target_lang=fra_Latn")
+                .header("Content-Type", "text/plain")
+                .body(Body::from("hello"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app(state)
+        .oneshot(
+            Request::post("/v1/resources?target_lang=fra_Latn")
+                .header("Content-Type", "application/json")
+                .body(Body::from("{\"a\": "))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -0,0 +1,66 @@
+use ctranslate2_server::segment;
+
+fn sentences(text: &str, lang: &str) -> Vec<String> {
+    segment::split(text, Some(lang)).sentences().to_vec()
+}
+
+#[test]
+fn paragraphs_and_sentences_are_split_and_joined_with_their_whitespace() {
+    let text = "  Dr. Smith arrived at 5 p.m. yesterday. He said \"Stop!\" Then he left... \
+                and came back?  Yes.\n\nNew paragraph\nwith a wrapped line.\n";
+    let segmented = segment::split(text, Some("eng_Latn"));
+    assert_eq!(
+        segmented.sentences(),
+        &[
+            "Dr. Smith arrived at 5 p.m. yesterday.",
+            "He said \"Stop!\"",
+            "Then he left... and came back?",
+            "Yes.",
+            "New paragraph\nwith a wrapped line.",
+        ]
+    );
+    assert_eq!(segmented.join(segmented.sentences()), text);
+    assert_eq!(
+        segmented.join(&["A.", "B.", "C?", "D.", "E."]),
+        "  A. B. C?  D.\n\nE.\n"
+    );
+}
+
+#[test]
+fn abbreviations_and_ordinals_follow_the_language() {
+    assert_eq!(
+        sentences(
+            "Am 3. Mai kommt er, z.B. mit dem Zug. Danach geht es weiter.",
+            "de"
+        ),
+        vec![
+            "Am 3. Mai kommt er, z.B. mit dem Zug.",
+            "Danach geht es weiter."
+        ]
+    );
+    assert_eq!(
+        sentences("Am 3. Mai kommt er. Gut.", "en"),
+        vec!["Am 3.", "Mai kommt er.", "Gut."]
+    );
+    assert_eq!(
+        sentences("M. Dupont est là. Il parle.", "fra_Latn"),
+        vec!["M. Dupont est là.", "Il parle."]
+    );
+}
+
+#[test]
+fn cjk_sentences_end_without_spaces() {
+    let segmented = segment::split("今天天气很好。我们去公园吧！你来吗？好的", Some("zh"));
+    assert_eq!(
+        segmented.sentences(),
+        &["今天天气很好。", "我们去公园吧！", "你来吗？", "好的"]
+    );
+    assert_eq!(segmented.join(&["A.", "B!", "C?", "D"]), "A.B!C?D");
+}
+
+#[test]
+fn blank_input_has_no_sentences() {
+    let segmented = segment::split("   \n  ", None);
+    assert!(segmented.sentences().is_empty());
+    assert_eq!(segmented.join::<&str>(&[]), "   \n  ");
+}
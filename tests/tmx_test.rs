@@ -0,0 +1,201 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use ctranslate2_server::{
+    app,
+    config::{AppConfig, ModelSpec, TranslationMemoryConfig},
+    lang,
+    memory::TranslationMemory,
+    model::ModelManager,
+    state::AppState,
+    tmx,
+};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tmx version="1.4">
+  <header creationtool="test" segtype="sentence" o-tmf="none" adminlang="en-US"
+          srclang="en-US" datatype="plaintext"/>
+  <body>
+    <tu>
+      <tuv xml:lang="en-US"><seg>Click <bpt i="1">&lt;b&gt;</bpt>Save<ept i="1">&lt;/b&gt;</ept> &amp; exit</seg></tuv>
+      <tuv xml:lang="fr-FR"><seg>Cliquez sur <bpt i="1">&lt;b&gt;</bpt>Enregistrer<ept i="1">&lt;/b&gt;</ept> et quittez</seg></tuv>
+      <tuv xml:lang="de-DE"><seg><![CDATA[Klicken Sie auf Speichern & beenden]]></seg></tuv>
+    </tu>
+    <tu>
+      <tuv lang="EN-US"><seg>Cancel</seg></tuv>
+      <tuv lang="tlh"><seg>Qo'</seg></tuv>
+    </tu>
+  </body>
+</tmx>
+"#;
+
+fn memory_config(name: &str) -> TranslationMemoryConfig {
+    let dir = std::env::temp_dir().join(format!("ct2-tmx-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    TranslationMemoryConfig {
+        path: Some(dir.display().to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn language_tags_map_to_model_codes() {
+    assert_eq!(
+        lang::to_model_code("fr-FR", "nllb").as_deref(),
+        Some("fra_Latn")
+    );
+    assert_eq!(
+        lang::to_model_code("zh-TW", "nllb").as_deref(),
+        Some("zho_Hant")
+    );
+    assert_eq!(
+        lang::to_model_code("eng_Latn", "nllb").as_deref(),
+        Some("eng_Latn")
+    );
+    assert_eq!(
+        lang::to_model_code("de", "m2m100").as_deref(),
+        Some("__de__")
+    );
+    assert_eq!(
+        lang::to_model_code("pt-BR", "marian").as_deref(),
+        Some("pt")
+    );
+    assert_eq!(lang::to_model_code("tlh", "nllb"), None);
+
+    assert_eq!(lang::to_bcp47("fra_Latn"), "fr");
+    assert_eq!(lang::to_bcp47("__de__"), "de");
+    assert_eq!(lang::to_bcp47("zho_Hant"), "zh-Hant");
+}
+
+#[test]
+fn segments_drop_inline_codes() {
+    let parsed = tmx::parse(SAMPLE).unwrap();
+    assert_eq!(parsed.source_lang.as_deref(), Some("en-US"));
+    assert_eq!(parsed.units.len(), 2);
+    assert_eq!(
+        parsed.units[0].variants,
+        vec![
+            ("en-US".to_string(), "Click Save & exit".to_string()),
+            (
+                "fr-FR".to_string(),
+                "Cliquez sur Enregistrer et quittez".to_string()
+            ),
+            (
+                "de-DE".to_string(),
+                "Klicken Sie auf Speichern & beenden".to_string()
+            ),
+        ]
+    );
+
+    assert!(tmx::parse("<xliff></xliff>").is_err());
+}
+
+#[test]
+fn import_maps_languages_and_export_round_trips() {
+    let memory = TranslationMemory::open(&memory_config("roundtrip")).unwrap();
+    let summary = tmx::import_str(&memory, SAMPLE, "nllb").unwrap();
+    assert_eq!(summary.imported, 2);
+    assert_eq!(summary.skipped, 1);
+    assert_eq!(summary.unmapped_languages, vec!["tlh".to_string()]);
+
+    let entries = memory.entries("eng_Latn", "fra_Latn");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].target, "Cliquez sur Enregistrer et quittez");
+
+    let exported = tmx::export(
+        "eng_Latn",
+        "deu_Latn",
+        &memory.entries("eng_Latn", "deu_Latn"),
+    );
+    assert!(exported.contains(r#"srclang="en""#));
+    assert!(exported.contains("Click Save &amp; exit"));
+
+    let reparsed = tmx::parse(&exported).unwrap();
+    assert_eq!(
+        reparsed.units[0].variants[1],
+        (
+            "de".to_string(),
+            "Klicken Sie auf Speichern & beenden".to_string()
+        )
+    );
+}
+
+#[tokio::test]
+async fn admin_endpoints_import_and_export() {
+    let mut config = AppConfig {
+        translation_memory: memory_config("admin"),
+        ..Default::default()
+    };
+    config.models.insert(
+        "nllb".to_string(),
+        ModelSpec {
+            path: "/non/existent/path".to_string(),
+            model_type: "nllb".to_string(),
+            ..Default::default()
+        },
+    );
+    config.server.admin_token = Some("secret".to_string());
+    config.default_model = "nllb".to_string();
+    let state = AppState {
+        model_manager: Arc::new(ModelManager::new(config)),
+    };
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::post("/admin/memory/tmx")
+                .header("Authorization", "Bearer secret")
+                .body(Body::from(SAMPLE))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let summary: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(summary["imported"], 2);
+
+    let response = app(state.clone())
+        .oneshot(
+            Request::get("/admin/memory/tmx?source_lang=en&target_lang=fr-FR")
+                .header("Authorization", "Bearer secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/x-tmx+xml");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let document = String::from_utf8(body.to_vec()).unwrap();
+    assert!(document.contains("<seg>Cliquez sur Enregistrer et quittez</seg>"));
+
+    // Codes the model doesn't know are rejected rather than exported as an empty pair
+    let response = app(state.clone())
+        .oneshot(
+            Request::get("/admin/memory/tmx?source_lang=en&target_lang=tlh")
+                .header("Authorization", "Bearer secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app(state)
+        .oneshot(
+            Request::post("/admin/memory/tmx")
+                .header("Authorization", "Bearer secret")
+                .body(Body::from("<tmx><body><tu><tuv><seg>x</seg>"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}